@@ -0,0 +1,176 @@
+//! The frame-step debugger: freeze a running cart, advance it one
+//! `update` + `draw` at a time, and see what each frame did.
+//!
+//! `F9` pauses, `F10` runs exactly one frame, `F5` resumes. While paused a
+//! panel at the bottom of the screen shows the frame number, the fuel each
+//! phase burned, the buttons the next frame will see and the latest log
+//! lines. Pressing a game button while paused latches it for the next
+//! stepped frame (press again to unlatch), so a frame-perfect input does not
+//! need a key held down alongside `F10`. `Tab` opens the memory inspector
+//! on the paused cart.
+
+use crate::logview::level_color;
+use rico8_runtime::{fb::Framebuffer, font, input::BUTTON_COUNT, log::LogLine, palette::col};
+use std::collections::VecDeque;
+
+/// Log lines the panel keeps.
+const LOG_LINES: usize = 3;
+
+/// Characters per panel row.
+const COLS: usize = 31;
+
+/// Button labels, in ABI order.
+const BUTTON_NAMES: [&str; BUTTON_COUNT] = ["L", "R", "U", "D", "O", "X"];
+
+/// Pause/step state for Run mode, plus the recent logs the panel shows.
+#[derive(Default)]
+pub struct Debugger {
+    paused: bool,
+    /// A single frame asked for with `F10`, not yet run.
+    step: bool,
+    /// Buttons pressed while paused, held for the next stepped frame only.
+    latched: u32,
+    logs: VecDeque<LogLine>,
+}
+
+impl Debugger {
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Run one frame and stay paused; pauses first if running.
+    pub fn step(&mut self) {
+        self.step = self.paused;
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step = false;
+        self.latched = 0;
+    }
+
+    /// Back to running, with no logs: a new run or the end of one.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether this tick runs a frame. Takes a pending step.
+    pub fn should_run(&mut self) -> bool {
+        !self.paused || std::mem::take(&mut self.step)
+    }
+
+    /// A game button went down. While paused it toggles the latch instead of
+    /// waiting for a held key to meet `F10`.
+    pub fn button_pressed(&mut self, b: usize) {
+        if self.paused && b < BUTTON_COUNT {
+            self.latched ^= 1 << b;
+        }
+    }
+
+    /// The latched buttons, cleared: they last for one stepped frame.
+    pub fn take_latched(&mut self) -> u32 {
+        std::mem::take(&mut self.latched)
+    }
+
+    /// Keep a cart log line for the panel.
+    pub fn log(&mut self, line: &LogLine) {
+        if self.logs.len() == LOG_LINES {
+            self.logs.pop_front();
+        }
+        self.logs.push_back(line.clone());
+    }
+
+    /// Draw the paused panel over the bottom of `fb`. `held` is player 1's
+    /// held-button mask; `fuel` the fuel of the last `update` and `draw`.
+    pub fn overlay(&self, fb: &mut Framebuffer, frame: u64, fuel: (u64, u64), held: u32) {
+        let rows = 3 + self.logs.len().max(1) as i32;
+        let y0 = 127 - rows * font::GLYPH_H;
+        fb.reset_state();
+        fb.rectfill(0, y0, 127, 127, col::BLACK);
+        let mut y = y0 + 1;
+        fb.print("PAUSED f10 step f5 go tab mem", 1, y, col::RED);
+        y += font::GLYPH_H;
+        fb.print(&format!("FRAME {frame}"), 1, y, col::WHITE);
+        y += font::GLYPH_H;
+        fb.print(
+            &format!("FUEL U{} D{}", fuel.0, fuel.1),
+            1,
+            y,
+            col::LIGHT_GREY,
+        );
+        // Buttons share the fuel row's right end: latched in yellow, held in
+        // white, the rest dim.
+        for (i, name) in BUTTON_NAMES.iter().enumerate() {
+            let color = if self.latched & (1 << i) != 0 {
+                col::YELLOW
+            } else if held & (1 << i) != 0 {
+                col::WHITE
+            } else {
+                col::DARK_GREY
+            };
+            fb.print(name, 103 + i as i32 * font::GLYPH_W, y, color);
+        }
+        y += font::GLYPH_H;
+        if self.logs.is_empty() {
+            fb.print("no logs", 1, y, col::DARK_GREY);
+        }
+        for line in &self.logs {
+            let shown: String = line.text.chars().take(COLS).collect();
+            fb.print(&shown, 1, y, level_color(line.level));
+            y += font::GLYPH_H;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_run_one_frame_each_and_only_while_paused() {
+        let mut d = Debugger::default();
+        assert!(d.should_run());
+        d.pause();
+        assert!(!d.should_run());
+        d.step();
+        assert!(d.should_run());
+        assert!(!d.should_run(), "one frame per step");
+        d.resume();
+        assert!(d.should_run());
+        d.step();
+        assert!(d.is_paused(), "stepping a running cart pauses it");
+        assert!(!d.should_run());
+    }
+
+    #[test]
+    fn latches_toggle_while_paused_and_last_one_frame() {
+        let mut d = Debugger::default();
+        d.button_pressed(4);
+        assert_eq!(d.take_latched(), 0, "running carts do not latch");
+        d.pause();
+        d.button_pressed(4);
+        d.button_pressed(1);
+        d.button_pressed(1);
+        assert_eq!(d.take_latched(), 1 << 4);
+        assert_eq!(d.take_latched(), 0);
+    }
+
+    #[test]
+    fn keeps_the_latest_logs() {
+        let mut d = Debugger::default();
+        for i in 0..5 {
+            d.log(&LogLine {
+                frame: i,
+                level: Default::default(),
+                text: format!("l{i}"),
+            });
+        }
+        let texts: Vec<_> = d.logs.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["l2", "l3", "l4"]);
+    }
+}
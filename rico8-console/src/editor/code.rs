@@ -28,6 +28,8 @@ const C_COMMENT: u8 = col::LAVENDER;
 const C_TYPE: u8 = col::YELLOW;
 const C_MACRO: u8 = col::ORANGE;
 const C_PUNCT: u8 = col::LIGHT_GREY;
+/// Background of the marked line.
+const C_MARK: u8 = col::DARK_PURPLE;
 
 const KEYWORDS: &[&str] = &[
     "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
@@ -47,6 +49,9 @@ pub struct CodeEditor {
     status: ui::StatusMsg,
     history: History<Snapshot>,
     frame: u64,
+    /// A line to highlight, such as where the cart panicked. Cleared by the
+    /// next edit.
+    mark: Option<usize>,
 }
 
 impl CodeEditor {
@@ -62,6 +67,7 @@ impl CodeEditor {
             status: ui::StatusMsg::default(),
             history: History::new(),
             frame: 0,
+            mark: None,
         }
     }
 
@@ -81,9 +87,24 @@ impl CodeEditor {
             self.scroll_x = 0;
             self.anchor = None;
             self.history.clear();
+            self.mark = None;
         }
     }
 
+    /// Put the cursor at 0-based `line` and `col` (clamped to the text),
+    /// scrolled to the middle of the screen, and highlight that line.
+    pub fn goto(&mut self, line: usize, col: usize) {
+        self.line = line;
+        self.col = col;
+        self.anchor = None;
+        self.clamp_cursor();
+        self.pref_col = self.col;
+        self.mark = Some(self.line);
+        self.scroll_y = self.line.saturating_sub(ROWS / 2);
+        self.scroll_x = 0;
+        self.scroll_to_cursor();
+    }
+
     pub fn text(&self) -> String {
         self.lines.join("\n")
     }
@@ -150,6 +171,7 @@ impl CodeEditor {
     /// Open an undo step, snapshotting the buffer before an edit. Idempotent
     /// within a single `key` call, so one keypress is one undo step.
     fn push_undo(&mut self) {
+        self.mark = None;
         let snap = self.snapshot();
         self.history.begin(&snap);
     }
@@ -161,6 +183,7 @@ impl CodeEditor {
         self.line = line;
         self.col = col;
         self.anchor = None;
+        self.mark = None;
         self.clamp_cursor();
     }
 
@@ -377,7 +400,17 @@ impl CodeEditor {
                     }
                 }
             }
-            Key::Escape | Key::CaptureLabel | Key::ToggleStats => {}
+            Key::Escape
+            | Key::CaptureLabel
+            | Key::Screenshot
+            | Key::Record
+            | Key::ToggleStats
+            | Key::Pause
+            | Key::Step
+            | Key::Resume
+            | Key::Slower
+            | Key::Faster
+            | Key::Live => {}
         }
         // Close the undo step opened by this keypress (a no-op when nothing
         // changed, e.g. cursor motion or an undo/redo that already closed it).
@@ -453,6 +486,10 @@ impl CodeEditor {
             let Some(line) = lines.get(li) else { break };
             let y = AREA_Y + row as i32 * font::GLYPH_H;
 
+            if self.mark == Some(li) {
+                fb.rectfill(0, y - 1, 127, y + font::GLYPH_H - 2, C_MARK);
+            }
+
             // Selection background.
             if let Some(((l0, c0), (l1, c1))) = sel {
                 if li >= l0 && li <= l1 {
@@ -786,6 +823,19 @@ mod tests {
         assert!(!scan_block_state("// /* not", false));
     }
 
+    #[test]
+    fn goto_marks_the_line_until_the_next_edit() {
+        let text: String = (0..60).map(|i| format!("l{i}\n")).collect();
+        let (mut e, mut code) = ed_with(&text);
+        e.goto(40, 99);
+        assert_eq!((e.line, e.col, e.mark), (40, 3, Some(40)));
+        assert!(e.scroll_y <= 40 && 40 < e.scroll_y + ROWS);
+        e.key(Key::Up, Mods::default(), &mut code);
+        assert_eq!(e.mark, Some(40), "moving around keeps the mark");
+        e.key(Key::Char('x'), Mods::default(), &mut code);
+        assert_eq!(e.mark, None);
+    }
+
     #[test]
     fn paste_scrolls_cursor_into_view() {
         let (mut e, mut code) = ed_with("");
@@ -0,0 +1,356 @@
+//! `rico8 gym`: a line-based stepping protocol for bots and automated play.
+//!
+//! One command per line on stdin, one reply per command on stdout:
+//!
+//! ```text
+//! reset [seed]            restart the cart (optionally reseeding rnd)  -> observation
+//! step [buttons] [frames] hold a button mask for N frames (default 1)  -> observation
+//! snapshot                save the whole VM state                      -> snapshot <id>
+//! restore <id>            go back to a saved state                     -> observation
+//! quit                    end the session (so does end of input)
+//! ```
+//!
+//! An observation is a header line, the cart's log lines, then the screen:
+//!
+//! ```text
+//! frame <n> done <0|1> logs <k>
+//! <k lines, one per log message>
+//! screen <payload>
+//! ```
+//!
+//! `buttons` is the bitmask `rico8 test` scripts use (bit 0 left .. bit 5 X).
+//! The screen payload is 16384 hex digits of palette indices, row by row
+//! (`--screen raw`), a base64 PNG (`--screen png`), or left out entirely
+//! (`--screen none`). `done` turns 1 when the cart hits a runtime error,
+//! whose message arrives as a final `error: ...` log line, or when
+//! `--max-frames` is reached; stepping a done cart does nothing until a
+//! `reset` or `restore`. A malformed command gets `error <message>` and the
+//! session carries on.
+
+use anyhow::{anyhow, bail, Context, Result};
+use rico8_runtime::{
+    audio::AudioHandle,
+    cart::{self, Cart},
+    fb::Framebuffer,
+    log::LogLevel,
+    script,
+    vm::{GameVm, LoadOptions, VmSnapshot},
+};
+use std::io::{BufRead, Write};
+
+/// How each observation carries the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenFormat {
+    Raw,
+    Png,
+    None,
+}
+
+impl ScreenFormat {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "png" => Ok(Self::Png),
+            "none" => Ok(Self::None),
+            _ => bail!("--screen must be raw, png or none, got {s}"),
+        }
+    }
+}
+
+/// One running gym session over a loaded cart.
+pub struct Gym<'a> {
+    cart: &'a Cart,
+    screen: ScreenFormat,
+    /// Episode length; `done` turns on once the VM reaches this frame.
+    max_frames: Option<u64>,
+    vm: GameVm,
+    /// The cart crashed; stepping is a no-op until reset or restore.
+    crashed: bool,
+    /// Saved states with the crash flag they were taken under.
+    snapshots: Vec<(VmSnapshot, bool)>,
+}
+
+impl<'a> Gym<'a> {
+    pub fn new(cart: &'a Cart, screen: ScreenFormat, max_frames: Option<u64>) -> Result<Self> {
+        Ok(Self {
+            cart,
+            screen,
+            max_frames,
+            vm: load(cart, None)?,
+            crashed: false,
+            snapshots: Vec::new(),
+        })
+    }
+
+    /// Answer commands from `input` until `quit` or end of input.
+    pub fn serve(&mut self, input: impl BufRead, mut out: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line.context("Reading a gym command")?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            if words == ["quit"] {
+                break;
+            }
+            if let Err(e) = self.command(&words, &mut out) {
+                writeln!(out, "error {}", one_line(&format!("{e:#}")))?;
+            }
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn command(&mut self, words: &[&str], out: &mut impl Write) -> Result<()> {
+        match words {
+            ["reset", rest @ ..] => {
+                let seed = match rest {
+                    [] => None,
+                    [seed] => Some(number(seed, "seed")?),
+                    _ => bail!("usage: reset [seed]"),
+                };
+                self.vm = load(self.cart, seed)?;
+                self.crashed = false;
+                self.observe(out)
+            }
+            ["step", rest @ ..] => {
+                let (mask, frames) = match rest {
+                    [] => (0, 1),
+                    [mask] => (number(mask, "buttons")?, 1),
+                    [mask, frames] => (number(mask, "buttons")?, number(frames, "frames")?),
+                    _ => bail!("usage: step [buttons] [frames]"),
+                };
+                if mask > 0x3f {
+                    bail!("buttons must be a mask of bits 0-5, got {mask}");
+                }
+                for _ in 0..frames {
+                    if self.done() {
+                        break;
+                    }
+                    script::set_buttons(&mut self.vm, mask as u8);
+                    if let Err(e) = self.vm.call_update().and_then(|()| self.vm.call_draw()) {
+                        let st = self.vm.state_mut();
+                        st.log(LogLevel::Error, format!("error: {e}"));
+                        self.crashed = true;
+                    }
+                }
+                self.observe(out)
+            }
+            ["snapshot"] => {
+                self.snapshots.push((self.vm.snapshot(), self.crashed));
+                writeln!(out, "snapshot {}", self.snapshots.len() - 1)?;
+                Ok(())
+            }
+            ["restore", id] => {
+                let id = number(id, "snapshot id")? as usize;
+                let (snap, crashed) = self
+                    .snapshots
+                    .get(id)
+                    .ok_or_else(|| anyhow!("no snapshot {id}"))?;
+                self.vm.restore(snap)?;
+                self.crashed = *crashed;
+                self.vm.state_mut().logs.clear();
+                self.observe(out)
+            }
+            [cmd, ..] => bail!("unknown command {cmd}"),
+            [] => Ok(()),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.crashed
+            || self
+                .max_frames
+                .is_some_and(|max| self.vm.state().frame >= max)
+    }
+
+    /// Write one observation, draining the logs it reports.
+    fn observe(&mut self, out: &mut impl Write) -> Result<()> {
+        let done = self.done() as u8;
+        let st = self.vm.state_mut();
+        let logs = std::mem::take(&mut st.logs);
+        writeln!(out, "frame {} done {done} logs {}", st.frame, logs.len())?;
+        for log in &logs {
+            writeln!(out, "{}", one_line(&log.text))?;
+        }
+        match self.screen {
+            ScreenFormat::Raw => writeln!(out, "screen {}", hex_screen(&st.fb))?,
+            ScreenFormat::Png => writeln!(
+                out,
+                "screen {}",
+                crate::webexport::base64(&cart::encode_screen_png(&st.fb, 1))
+            )?,
+            ScreenFormat::None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Boot `cart`, with `rnd` seeded before its init when there is a `seed`.
+fn load(cart: &Cart, seed: Option<u32>) -> Result<GameVm> {
+    let options = LoadOptions { seed };
+    GameVm::load_with(&cart.wasm, &cart.assets, AudioHandle::dummy(), options)
+        .context("Loading cart into the VM")
+}
+
+fn number(s: &str, what: &str) -> Result<u32> {
+    s.parse()
+        .map_err(|_| anyhow!("{what} must be a number, got {s}"))
+}
+
+/// Keep a message on its protocol line.
+fn one_line(s: &str) -> String {
+    s.lines().collect::<Vec<_>>().join(" | ")
+}
+
+/// One hex digit per pixel, row by row.
+pub fn hex_screen(fb: &Framebuffer) -> String {
+    fb.pixels()
+        .iter()
+        .map(|&p| char::from_digit(p as u32 & 0xf, 16).unwrap_or('0'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Moves a pixel right while button 1 is held, logs each move, and traps
+    /// once it reaches x = 5.
+    const WALKER: &str = r#"
+        (module
+          (import "rico8" "clear" (func $cls (param i32)))
+          (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+          (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+          (import "rico8" "log" (func $log (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "moved")
+          (global $x (mut i32) (i32.const 0))
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (if (i32.ne (call $btn (i32.const 1)) (i32.const 0))
+              (then (global.set $x (i32.add (global.get $x) (i32.const 1)))
+                    (call $log (i32.const 0) (i32.const 5))))
+            (if (i32.ge_s (global.get $x) (i32.const 5)) (then unreachable)))
+          (func (export "rico8_draw")
+            (call $cls (i32.const 1))
+            (call $pset (global.get $x) (i32.const 0) (i32.const 8)))
+        )
+    "#;
+
+    fn run(cart: &Cart, screen: ScreenFormat, max: Option<u64>, script: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        Gym::new(cart, screen, max)
+            .unwrap()
+            .serve(script.as_bytes(), &mut out)
+            .unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn walker() -> Cart {
+        Cart {
+            wasm: wat::parse_str(WALKER).unwrap(),
+            assets: Default::default(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn step_reports_frame_logs_and_screen() {
+        let cart = walker();
+        let out = run(&cart, ScreenFormat::Raw, None, "step 2 3\nstep 0\n");
+        assert_eq!(out[0], "frame 3 done 0 logs 3");
+        assert_eq!(&out[1..4], ["moved", "moved", "moved"]);
+        let screen = out[4].strip_prefix("screen ").unwrap();
+        assert_eq!(screen.len(), 128 * 128);
+        assert_eq!(&screen[..5], "11181");
+        assert_eq!(out[5], "frame 4 done 0 logs 0");
+    }
+
+    #[test]
+    fn crash_and_frame_limit_end_the_episode() {
+        let cart = walker();
+        let out = run(&cart, ScreenFormat::None, None, "step 2 10\nstep 2\n");
+        assert_eq!(out[0], "frame 5 done 1 logs 6");
+        assert!(out[6].starts_with("error: "), "got {}", out[6]);
+        assert_eq!(out[7], "frame 5 done 1 logs 0");
+
+        let out = run(
+            &cart,
+            ScreenFormat::None,
+            Some(2),
+            "step 0 5\nreset\nstep\n",
+        );
+        assert_eq!(
+            out,
+            [
+                "frame 2 done 1 logs 0",
+                "frame 0 done 0 logs 0",
+                "frame 1 done 0 logs 0"
+            ]
+        );
+    }
+
+    #[test]
+    fn restore_rewinds_to_a_snapshot() {
+        let cart = walker();
+        let script = "step 2 2\nsnapshot\nstep 2 10\nrestore 0\nstep 0\nrestore 9\nbogus\n";
+        let out = run(&cart, ScreenFormat::None, None, script);
+        assert_eq!(out[3], "snapshot 0");
+        assert!(out[4].starts_with("frame 5 done 1"), "got {}", out[4]);
+        let restored = out
+            .iter()
+            .position(|l| l == "frame 2 done 0 logs 0")
+            .unwrap();
+        assert_eq!(out[restored + 1], "frame 3 done 0 logs 0");
+        assert_eq!(out[restored + 2], "error no snapshot 9");
+        assert_eq!(out[restored + 3], "error unknown command bogus");
+    }
+
+    #[test]
+    fn reset_seeds_rnd_before_init() {
+        // Rolls three pixel positions in init.
+        let wat = r#"
+            (module
+              (import "rico8" "rnd" (func $rnd (result f32)))
+              (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func $roll (result i32)
+                (i32.trunc_f32_s (f32.mul (call $rnd) (f32.const 128))))
+              (func (export "rico8_init")
+                (i32.store (i32.const 0) (call $roll))
+                (i32.store (i32.const 4) (call $roll))
+                (i32.store (i32.const 8) (call $roll)))
+              (func (export "rico8_update"))
+              (func (export "rico8_draw")
+                (call $pset (i32.load (i32.const 0)) (i32.const 0) (i32.const 8))
+                (call $pset (i32.load (i32.const 4)) (i32.const 1) (i32.const 8))
+                (call $pset (i32.load (i32.const 8)) (i32.const 2) (i32.const 8))))
+        "#;
+        let cart = Cart {
+            wasm: wat::parse_str(wat).unwrap(),
+            assets: Default::default(),
+            source: None,
+        };
+        let script = "reset 7\nstep\nreset 7\nstep\nreset 8\nstep\n";
+        let out = run(&cart, ScreenFormat::Raw, None, script);
+        assert_eq!(out[3], out[7], "the same seed rolls the same level");
+        assert_ne!(out[3], out[11]);
+    }
+
+    #[test]
+    fn png_screens_decode() {
+        let cart = walker();
+        let out = run(&cart, ScreenFormat::Png, None, "reset 7\n");
+        assert_eq!(out[0], "frame 0 done 0 logs 0");
+        assert!(
+            out[1].starts_with("screen iVBORw0KGgo"),
+            "got {}",
+            &out[1][..20]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! The memory inspector: a hex/ASCII view of a paused or trapped cart's
+//! linear memory, colored by region (stack, data, bss, heap).
+//!
+//! It works on a copy taken when it opens, so it can outlive the VM of a
+//! cart that trapped. Arrows and page keys scroll, `Tab` jumps to the next
+//! region, typing hex digits then `Enter` jumps to an address, and `Esc`
+//! closes it.
+
+use crate::shell::Key;
+use rico8_runtime::{
+    fb::Framebuffer,
+    font,
+    memmap::{MemoryMap, Region},
+    palette::col,
+};
+
+/// Bytes per row: `AAAAA 00112233 44556677 ........` fills the 32 columns.
+const ROW_BYTES: usize = 8;
+const ROWS: usize = 15;
+const AREA_Y: i32 = 9;
+
+/// Longest address worth typing: memory is capped at 128K (5 hex digits).
+const JUMP_DIGITS: usize = 5;
+
+#[derive(Clone)]
+pub struct MemoryInspector {
+    bytes: Vec<u8>,
+    map: MemoryMap,
+    /// Address of the top row, a multiple of [`ROW_BYTES`].
+    top: usize,
+    /// Hex digits typed so far for an address jump.
+    jump: Option<String>,
+}
+
+impl MemoryInspector {
+    /// Browse `bytes` laid out as `map`, starting at the top of the heap
+    /// (where running out of memory shows) or at 0.
+    pub fn new(bytes: Vec<u8>, map: MemoryMap) -> Self {
+        let mut inspector = Self {
+            bytes,
+            map,
+            top: 0,
+            jump: None,
+        };
+        let start = inspector.map.heap_base.unwrap_or(0) as usize;
+        inspector.scroll_to(start);
+        inspector
+    }
+
+    /// Scroll so `addr`'s row is on top, as far as the end allows.
+    pub fn scroll_to(&mut self, addr: usize) {
+        let last_top = (self.bytes.len().div_ceil(ROW_BYTES))
+            .saturating_sub(ROWS)
+            .saturating_mul(ROW_BYTES);
+        self.top = (addr / ROW_BYTES * ROW_BYTES).min(last_top);
+    }
+
+    /// Handle a key. Returns false once the inspector closes.
+    pub fn key(&mut self, key: Key) -> bool {
+        if let Some(jump) = &mut self.jump {
+            match key {
+                Key::Char(c) if c.is_ascii_hexdigit() && jump.len() < JUMP_DIGITS => {
+                    jump.push(c.to_ascii_uppercase());
+                }
+                Key::Backspace => {
+                    jump.pop();
+                }
+                Key::Enter => {
+                    let addr = usize::from_str_radix(jump, 16).unwrap_or(0);
+                    self.jump = None;
+                    self.scroll_to(addr);
+                }
+                Key::Escape => self.jump = None,
+                _ => {}
+            }
+            return true;
+        }
+        let page = ROWS * ROW_BYTES;
+        match key {
+            Key::Escape => return false,
+            Key::Up => self.scroll_to(self.top.saturating_sub(ROW_BYTES)),
+            Key::Down => self.scroll_to(self.top + ROW_BYTES),
+            Key::PageUp => self.scroll_to(self.top.saturating_sub(page)),
+            Key::PageDown => self.scroll_to(self.top + page),
+            Key::Home => self.scroll_to(0),
+            Key::End => self.scroll_to(self.bytes.len()),
+            Key::Tab => {
+                let starts = self.map.starts();
+                let next = starts
+                    .iter()
+                    .find(|&&(addr, _)| addr as usize > self.top)
+                    .or(starts.first());
+                if let Some(&(addr, _)) = next {
+                    self.scroll_to(addr as usize);
+                }
+            }
+            Key::Char(c) if c.is_ascii_hexdigit() => {
+                self.jump = Some(c.to_ascii_uppercase().to_string());
+            }
+            _ => {}
+        }
+        true
+    }
+
+    pub fn draw(&self, fb: &mut Framebuffer) {
+        fb.reset_state();
+        fb.cls(col::BLACK);
+        fb.rectfill(0, 0, 127, 7, col::DARK_BLUE);
+        let header = match &self.jump {
+            Some(jump) => format!("GO TO {jump}_"),
+            None => format!(
+                "MEM {:05X} {:<5} /{:05X}",
+                self.top,
+                self.map.region(self.top as u32).name(),
+                self.bytes.len()
+            ),
+        };
+        fb.print(&header, 1, 1, col::WHITE);
+
+        for row in 0..ROWS {
+            let addr = self.top + row * ROW_BYTES;
+            if addr >= self.bytes.len() {
+                break;
+            }
+            let y = AREA_Y + row as i32 * font::GLYPH_H;
+            fb.print(&format!("{addr:05X}"), 0, y, col::DARK_GREY);
+            let end = (addr + ROW_BYTES).min(self.bytes.len());
+            for (i, &byte) in self.bytes[addr..end].iter().enumerate() {
+                let color = region_color(self.map.region((addr + i) as u32));
+                // Columns: address, space, two groups of four bytes, space.
+                let hex_col = 6 + i * 2 + i / 4;
+                fb.print(
+                    &format!("{byte:02X}"),
+                    hex_col as i32 * font::GLYPH_W,
+                    y,
+                    color,
+                );
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                let ascii_col = 24 + i;
+                fb.print(
+                    c.encode_utf8(&mut [0u8; 4]),
+                    ascii_col as i32 * font::GLYPH_W,
+                    y,
+                    color,
+                );
+            }
+        }
+
+        // Legend, in the regions' own colors.
+        let y = 127 - font::GLYPH_H;
+        let mut x = 1;
+        for region in [Region::Stack, Region::Data, Region::Bss, Region::Heap] {
+            fb.print(region.name(), x, y, region_color(region));
+            x += (region.name().len() as i32 + 1) * font::GLYPH_W;
+        }
+        fb.print("tab esc", 127 - 7 * font::GLYPH_W, y, col::DARK_GREY);
+    }
+}
+
+fn region_color(region: Region) -> u8 {
+    match region {
+        Region::Stack => col::BLUE,
+        Region::Data => col::GREEN,
+        Region::Bss => col::YELLOW,
+        Region::Heap => col::PINK,
+        Region::Unknown => col::LIGHT_GREY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspector() -> MemoryInspector {
+        let map = MemoryMap {
+            stack: Some(0..0x1000),
+            data: Some(0x1000..0x1100),
+            bss: Some(0x1100..0x1200),
+            heap_base: Some(0x1200),
+        };
+        MemoryInspector::new(vec![0; 0x10000], map)
+    }
+
+    #[test]
+    fn opens_on_the_heap_and_tabs_through_regions() {
+        let mut m = inspector();
+        assert_eq!(m.top, 0x1200);
+        m.key(Key::Tab);
+        assert_eq!(m.top, 0, "wraps to the stack");
+        m.key(Key::Tab);
+        assert_eq!(m.top, 0x1000);
+        m.key(Key::Tab);
+        assert_eq!(m.top, 0x1100);
+    }
+
+    #[test]
+    fn jumps_to_a_typed_address_and_stays_in_bounds() {
+        let mut m = inspector();
+        for c in "2a0f".chars() {
+            m.key(Key::Char(c));
+        }
+        assert_eq!(m.jump.as_deref(), Some("2A0F"));
+        m.key(Key::Enter);
+        assert_eq!(m.top, 0x2A08);
+        m.key(Key::End);
+        assert_eq!(m.top, 0x10000 - ROWS * ROW_BYTES, "the last page is full");
+        m.key(Key::PageDown);
+        assert_eq!(m.top, 0x10000 - ROWS * ROW_BYTES);
+        assert!(!m.key(Key::Escape));
+    }
+}
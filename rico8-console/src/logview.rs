@@ -0,0 +1,166 @@
+//! The log viewer: the last run's log lines, each stamped with its frame and
+//! colored by level, scrollable and filtered down to a minimum level.
+//!
+//! `log [level]` opens it from the console, on the latest lines. Arrows and
+//! page keys scroll, `Left`/`Right` pan across long lines, `Tab` raises the
+//! filter a level (wrapping back to debug), and `Esc` closes it. `savelog`
+//! writes the same lines to a file, unfiltered.
+
+use crate::shell::Key;
+use rico8_runtime::{
+    fb::Framebuffer,
+    font,
+    log::{LogLevel, LogLine},
+    palette::col,
+};
+
+const ROWS: usize = 15;
+const AREA_Y: i32 = 9;
+
+/// Columns of a row: the frame number, a space, then the text.
+const FRAME_COLS: usize = 6;
+const TEXT_COLS: usize = 32 - FRAME_COLS;
+
+/// How far `Left`/`Right` pan, in characters.
+const PAN: usize = 8;
+
+pub struct LogView {
+    lines: Vec<LogLine>,
+    /// Lines below this level are hidden.
+    min: LogLevel,
+    /// Index of the top row among the shown lines.
+    top: usize,
+    /// Characters of text scrolled off the left edge.
+    left: usize,
+}
+
+impl LogView {
+    /// View `lines` at `min` and above, scrolled to the end.
+    pub fn new(lines: Vec<LogLine>, min: LogLevel) -> Self {
+        let mut view = Self {
+            lines,
+            min,
+            top: 0,
+            left: 0,
+        };
+        view.scroll_to(usize::MAX);
+        view
+    }
+
+    /// The lines the filter lets through, oldest first.
+    fn shown(&self) -> impl Iterator<Item = &LogLine> {
+        self.lines.iter().filter(|l| l.level >= self.min)
+    }
+
+    /// Scroll so shown line `row` is on top, as far as the end allows.
+    fn scroll_to(&mut self, row: usize) {
+        let last_top = self.shown().count().saturating_sub(ROWS);
+        self.top = row.min(last_top);
+    }
+
+    /// Handle a key. Returns false once the viewer closes.
+    pub fn key(&mut self, key: Key) -> bool {
+        match key {
+            Key::Escape => return false,
+            Key::Up => self.scroll_to(self.top.saturating_sub(1)),
+            Key::Down => self.scroll_to(self.top + 1),
+            Key::PageUp => self.scroll_to(self.top.saturating_sub(ROWS)),
+            Key::PageDown => self.scroll_to(self.top + ROWS),
+            Key::Home => self.scroll_to(0),
+            Key::End => self.scroll_to(usize::MAX),
+            Key::Left => self.left = self.left.saturating_sub(PAN),
+            Key::Right => self.left += PAN,
+            Key::Tab => {
+                self.min = self.min.next();
+                self.scroll_to(usize::MAX);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    pub fn draw(&self, fb: &mut Framebuffer) {
+        fb.reset_state();
+        fb.cls(col::BLACK);
+        fb.rectfill(0, 0, 127, 7, col::DARK_BLUE);
+        let shown = self.shown().count();
+        let header = format!("LOG {}+ {}/{}", self.min.name(), shown, self.lines.len());
+        fb.print(&header, 1, 1, col::WHITE);
+
+        if shown == 0 {
+            fb.print("no log lines", 1, AREA_Y, col::DARK_GREY);
+        }
+        for (row, line) in self.shown().skip(self.top).take(ROWS).enumerate() {
+            let y = AREA_Y + row as i32 * font::GLYPH_H;
+            // Long runs outgrow five digits; keep the low ones, they move.
+            let frame = format!("{:>5}", line.frame % 100_000);
+            fb.print(&frame, 0, y, col::DARK_GREY);
+            let text: String = line.text.chars().skip(self.left).take(TEXT_COLS).collect();
+            let x = FRAME_COLS as i32 * font::GLYPH_W;
+            fb.print(&text, x, y, level_color(line.level));
+        }
+
+        // Legend, in the levels' own colors.
+        let y = 127 - font::GLYPH_H;
+        let mut x = 1;
+        for level in LogLevel::ALL {
+            fb.print(level.name(), x, y, level_color(level));
+            x += (level.name().len() as i32 + 1) * font::GLYPH_W;
+        }
+        fb.print("tab esc", 127 - 7 * font::GLYPH_W, y, col::DARK_GREY);
+    }
+}
+
+/// The color a log line of `level` is drawn in, here and in the debugger.
+pub fn level_color(level: LogLevel) -> u8 {
+    match level {
+        LogLevel::Debug => col::BLUE,
+        LogLevel::Info => col::LIGHT_GREY,
+        LogLevel::Warn => col::YELLOW,
+        LogLevel::Error => col::RED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view() -> LogView {
+        let lines = (0..40)
+            .map(|i| LogLine {
+                frame: i,
+                level: LogLevel::ALL[i as usize % 4],
+                text: format!("line {i}"),
+            })
+            .collect();
+        LogView::new(lines, LogLevel::Debug)
+    }
+
+    #[test]
+    fn opens_on_the_latest_lines_and_scrolls_in_bounds() {
+        let mut v = view();
+        assert_eq!(v.top, 40 - ROWS);
+        v.key(Key::PageDown);
+        assert_eq!(v.top, 40 - ROWS, "the last page is full");
+        v.key(Key::Home);
+        v.key(Key::Up);
+        assert_eq!(v.top, 0);
+        v.key(Key::Left);
+        assert_eq!(v.left, 0);
+        assert!(!v.key(Key::Escape));
+    }
+
+    #[test]
+    fn tab_raises_the_filter_and_wraps() {
+        let mut v = view();
+        v.key(Key::Tab);
+        v.key(Key::Tab);
+        assert_eq!(v.min, LogLevel::Warn);
+        assert!(v.shown().all(|l| l.level >= LogLevel::Warn));
+        assert_eq!(v.shown().count(), 20);
+        assert_eq!(v.top, 20 - ROWS);
+        v.key(Key::Tab);
+        v.key(Key::Tab);
+        assert_eq!(v.min, LogLevel::Debug);
+    }
+}
@@ -6,8 +6,13 @@
 
 mod builder;
 mod clipboard;
+mod debugger;
 mod editor;
 mod gpu;
+mod gym;
+mod inspector;
+mod logview;
+mod remote;
 mod shell;
 mod ui;
 mod watch;
@@ -66,7 +71,13 @@ fn main() -> Result<()> {
         ["extract", png, dir] => headless_extract(Path::new(png), Path::new(dir)),
         ["import-pico8", rest @ ..] => headless_import_pico8_cli(rest),
         ["export-web", input, out] => headless_export_web(Path::new(input), Path::new(out)),
-        ["verify", png] => headless_verify(Path::new(png)),
+        ["render-audio", rest @ ..] => headless_render_audio(rest),
+        ["verify", rest @ ..] => headless_verify(rest),
+        ["test", rest @ ..] => headless_test(rest),
+        ["render", rest @ ..] => headless_render(rest),
+        ["fuzz", rest @ ..] => headless_fuzz(rest),
+        ["bench", rest @ ..] => headless_bench(rest),
+        ["gym", rest @ ..] => headless_gym(rest),
         ["snap", project, outdir] => headless_snap(Path::new(project), Path::new(outdir)),
         ["run", path] => run_windowed(Some(path.to_string()), true),
         ["run"] => {
@@ -103,7 +114,22 @@ fn print_help() {
          \x20                            Append selected assets into an existing project\n\
          \x20 rico8 export-web <dir|cart.png> <out.html>\n\
          \x20                            Export a self-contained playable web page\n\
-         \x20 rico8 verify <cart.png>    Load a cart and run 60 frames headless",
+         \x20 rico8 render-audio <dir|cart.png> <out.wav> [--sfx N | --music N] [--seconds S]\n\
+         \x20                            [--band-limited]\n\
+         \x20                            Render a song (default: pattern 0) or SFX to WAV\n\
+         \x20 rico8 verify <cart.png|dir> [--json]\n\
+         \x20                            Run 60 frames headless; a dir checks every cart\n\
+         \x20 rico8 test <dir|cart.png> <script> [--update]\n\
+         \x20                            Run a scripted input/screen test (non-zero on failure)\n\
+         \x20 rico8 render <dir|cart.png> --out <dir> [--frames N] [--input script]\n\
+         \x20                            [--scale K] [--band-limited]\n\
+         \x20                            Dump numbered PNG frames plus a matching WAV\n\
+         \x20 rico8 fuzz <dir|cart.png> [--frames N] [--runs M] [--seed S] [--out dir]\n\
+         \x20                            Mash random buttons; save a repro script per crash\n\
+         \x20 rico8 bench <dir|cart.png> [--frames N] [--input script] [--csv out.csv]\n\
+         \x20                            Fuel per phase (min/mean/p95/p99/max) and memory\n\
+         \x20 rico8 gym <dir|cart.png> [--screen raw|png|none] [--max-frames N]\n\
+         \x20                            Step the cart from stdin for bots (reset/step/snapshot/restore)",
         shell::VERSION
     );
 }
@@ -261,63 +287,756 @@ fn headless_import_pico8_into(
     Ok(())
 }
 
+/// Load a cart PNG as-is, or build a project directory into a source-less
+/// cart, for the subcommands that only need something to run.
+fn load_or_build(input: &Path) -> Result<Cart> {
+    if input.extension().is_some_and(|e| e == "png") {
+        return cart::load_png(input);
+    }
+    let project = Project::load(input)?;
+    let result = builder::run_build(input, Instant::now());
+    if !result.success {
+        for line in &result.errors {
+            eprintln!("{line}");
+        }
+        bail!("Build failed");
+    }
+    let wasm = std::fs::read(project.wasm_path()).context("Reading built wasm")?;
+    Ok(Cart {
+        wasm,
+        assets: project.assets.clone(),
+        source: None,
+    })
+}
+
 /// Export a project or cart as a single playable HTML file.
 fn headless_export_web(input: &Path, out: &Path) -> Result<()> {
-    let cart = if input.extension().is_some_and(|e| e == "png") {
-        cart::load_png(input)?
+    // Web players can't edit; a source-less cart keeps the page lean.
+    let cart = load_or_build(input)?;
+    webexport::export_html(&cart, out, &webexport::web_crate_dir(&sdk_path()))?;
+    println!("Exported {}", out.display());
+    Ok(())
+}
+
+/// Render a song or a single SFX from a project or cart to a WAV file.
+/// `<input> <out.wav> [--sfx N | --music N] [--seconds S] [--band-limited]`;
+/// the song starting at pattern 0 is the default, and `--seconds` caps loops.
+fn headless_render_audio(args: &[&str]) -> Result<()> {
+    use rico8_runtime::{audio::OscQuality, wav};
+    let (mut input, mut out) = (None, None);
+    let mut quality = OscQuality::Authentic;
+    let (mut sfx, mut music, mut seconds) = (None, None, None);
+    let mut it = args.iter();
+    while let Some(&a) = it.next() {
+        match a {
+            "--sfx" => sfx = Some(flag_value(it.next(), "--sfx")?),
+            "--music" => music = Some(flag_value(it.next(), "--music")?),
+            "--seconds" => seconds = Some(flag_value(it.next(), "--seconds")?),
+            "--band-limited" => quality = OscQuality::BandLimited,
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos if out.is_none() => out = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
+        }
+    }
+    let (Some(input), Some(out)) = (input, out) else {
+        bail!(
+            "Usage: rico8 render-audio <dir|cart.png> <out.wav> \
+             [--sfx N | --music N] [--seconds S] [--band-limited]"
+        );
+    };
+    if sfx.is_some() && music.is_some() {
+        bail!("--sfx and --music are exclusive");
+    }
+    let seconds = match seconds {
+        Some(s) => s
+            .parse::<f32>()
+            .ok()
+            .filter(|s| *s > 0.0)
+            .ok_or_else(|| anyhow!("--seconds needs a positive number, got {s}"))?,
+        None => wav::DEFAULT_SECONDS,
+    };
+    let index = |v: &str, flag: &str| -> Result<usize> {
+        v.parse()
+            .map_err(|_| anyhow!("{flag} needs an index, got {v}"))
+    };
+    let input = Path::new(input);
+    let assets = if input.extension().is_some_and(|e| e == "png") {
+        cart::load_png(input)?.assets
     } else {
-        let project = Project::load(input)?;
-        let result = builder::run_build(input, Instant::now());
-        if !result.success {
-            for line in &result.errors {
-                eprintln!("{line}");
+        Project::load(input)?.assets
+    };
+    let samples = match sfx {
+        Some(n) => wav::render_sfx(&assets, index(n, "--sfx")?, seconds, quality)?,
+        None => {
+            let n = index(music.unwrap_or("0"), "--music")?;
+            wav::render_music(&assets, n, seconds, quality)?
+        }
+    };
+    wav::write(Path::new(out), &samples, wav::SAMPLE_RATE)?;
+    println!(
+        "Rendered {:.1}s to {out}",
+        samples.len() as f32 / wav::SAMPLE_RATE as f32
+    );
+    Ok(())
+}
+
+/// How a cart fared under `rico8 verify`.
+#[derive(Debug, Clone, PartialEq)]
+enum Verdict {
+    Pass,
+    /// Ran cleanly but never drew a non-black pixel.
+    Blank,
+    RuntimeError(String),
+    LoadError(String),
+}
+
+impl Verdict {
+    fn label(&self) -> &'static str {
+        match self {
+            Verdict::Pass => "pass",
+            Verdict::Blank => "blank screen",
+            Verdict::RuntimeError(_) => "runtime error",
+            Verdict::LoadError(_) => "load error",
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            Verdict::RuntimeError(m) | Verdict::LoadError(m) => m,
+            Verdict::Pass | Verdict::Blank => "",
+        }
+    }
+
+    fn failed(&self) -> bool {
+        matches!(self, Verdict::RuntimeError(_) | Verdict::LoadError(_))
+    }
+}
+
+const VERIFY_FRAMES: usize = 60;
+
+/// Load `png` and run a second of frames on a fresh VM. Returns the cart
+/// name (empty if it never loaded) and the verdict.
+fn verify_cart(png: &Path) -> (String, Verdict) {
+    use rico8_runtime::{audio::AudioHandle, vm::GameVm};
+    let cart = match cart::load_png(png) {
+        Ok(cart) => cart,
+        Err(e) => return (String::new(), Verdict::LoadError(format!("{e:#}"))),
+    };
+    let name = cart.assets.meta.name.clone();
+    let mut vm = match GameVm::load(&cart.wasm, &cart.assets, AudioHandle::dummy()) {
+        Ok(vm) => vm,
+        Err(e) => {
+            return (
+                name,
+                Verdict::LoadError(format!("Loading cart into the VM: {e:#}")),
+            )
+        }
+    };
+    for frame in 0..VERIFY_FRAMES {
+        if let Err(e) = vm.call_update().and_then(|()| vm.call_draw()) {
+            return (name, Verdict::RuntimeError(format!("Frame {frame}: {e}")));
+        }
+    }
+    let drew_something = vm.state().fb.pixels().iter().any(|&p| p != 0);
+    (
+        name,
+        if drew_something {
+            Verdict::Pass
+        } else {
+            Verdict::Blank
+        },
+    )
+}
+
+/// One row of a directory verify.
+#[derive(Debug)]
+struct VerifyRow {
+    file: String,
+    name: String,
+    verdict: Verdict,
+}
+
+/// Verify every cart PNG in `dir`, spreading carts over worker threads that
+/// each run their own `GameVm`. PNGs without cart data (screenshots, labels)
+/// are skipped and counted. Rows come back sorted by file name.
+fn verify_dir(dir: &Path) -> Result<(Vec<VerifyRow>, usize)> {
+    let mut paths = Vec::new();
+    let mut skipped = 0;
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Reading {}", dir.display()))? {
+        let path = entry?.path();
+        if !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"))
+        {
+            continue;
+        }
+        // A cart that is not even a readable PNG still gets a row; only
+        // well-formed PNGs without a cart chunk are skipped.
+        match std::fs::read(&path) {
+            Ok(bytes) if !cart::is_cart(&bytes) && bytes.starts_with(b"\x89PNG") => skipped += 1,
+            _ => paths.push(path),
+        }
+    }
+    paths.sort();
+
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let results = std::sync::Mutex::new(Vec::with_capacity(paths.len()));
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(paths.len().max(1));
+    std::thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some(path) = paths.get(i) else { break };
+                let (name, verdict) = verify_cart(path);
+                let file = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                results.lock().unwrap().push(VerifyRow {
+                    file,
+                    name,
+                    verdict,
+                });
+            });
+        }
+    });
+    let mut rows = results.into_inner().unwrap();
+    rows.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok((rows, skipped))
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Machine-readable verify report for CI dashboards.
+fn verify_json(rows: &[VerifyRow], skipped: usize) -> String {
+    let carts: Vec<String> = rows
+        .iter()
+        .map(|r| {
+            format!(
+                "    {{\"file\": {}, \"name\": {}, \"result\": {}, \"detail\": {}}}",
+                json_string(&r.file),
+                json_string(&r.name),
+                json_string(r.verdict.label()),
+                json_string(r.verdict.detail())
+            )
+        })
+        .collect();
+    let count = |label: &str| rows.iter().filter(|r| r.verdict.label() == label).count();
+    format!(
+        "{{\n  \"carts\": [\n{}\n  ],\n  \"summary\": {{\"pass\": {}, \"blank\": {}, \
+         \"runtime_error\": {}, \"load_error\": {}, \"skipped\": {}}}\n}}\n",
+        carts.join(",\n"),
+        count("pass"),
+        count("blank screen"),
+        count("runtime error"),
+        count("load error"),
+        skipped
+    )
+}
+
+fn print_verify_table(rows: &[VerifyRow], skipped: usize) {
+    let width = rows.iter().map(|r| r.file.len()).max().unwrap_or(0).max(4);
+    println!("{:width$}  {:13}  detail", "cart", "result");
+    for r in rows {
+        println!(
+            "{:width$}  {:13}  {}",
+            r.file,
+            r.verdict.label(),
+            r.verdict.detail()
+        );
+    }
+    let count = |label: &str| rows.iter().filter(|r| r.verdict.label() == label).count();
+    print!(
+        "{} carts: {} pass, {} blank screen, {} runtime error, {} load error",
+        rows.len(),
+        count("pass"),
+        count("blank screen"),
+        count("runtime error"),
+        count("load error")
+    );
+    if skipped > 0 {
+        print!(" ({skipped} non-cart PNGs skipped)");
+    }
+    println!();
+}
+
+/// Load a cart and run a second of frames without a window — a smoke
+/// test for carts and for the console itself (used by CI). Given a
+/// directory, verifies every cart in it in parallel and prints a summary
+/// table (or JSON with `--json`).
+fn headless_verify(args: &[&str]) -> Result<()> {
+    let mut input = None;
+    let mut json = false;
+    for &arg in args {
+        match arg {
+            "--json" => json = true,
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
+        }
+    }
+    let Some(input) = input else {
+        bail!("Usage: rico8 verify <cart.png|dir> [--json]");
+    };
+    let input = Path::new(input);
+
+    let (rows, skipped) = if input.is_dir() {
+        verify_dir(input)?
+    } else if json {
+        let (name, verdict) = verify_cart(input);
+        let file = input.to_string_lossy().into_owned();
+        (
+            vec![VerifyRow {
+                file,
+                name,
+                verdict,
+            }],
+            0,
+        )
+    } else {
+        // Single cart: keep the terse output and error exit CI relies on.
+        return match verify_cart(input) {
+            (name, Verdict::Pass) => {
+                println!("OK: {name} ran {VERIFY_FRAMES} frames");
+                Ok(())
+            }
+            (name, Verdict::Blank) => {
+                println!("OK: {name} ran {VERIFY_FRAMES} frames (blank screen)");
+                Ok(())
             }
-            bail!("Build failed");
+            (_, Verdict::RuntimeError(e) | Verdict::LoadError(e)) => Err(anyhow!(e)),
+        };
+    };
+
+    if json {
+        print!("{}", verify_json(&rows, skipped));
+    } else {
+        print_verify_table(&rows, skipped);
+    }
+    let failed = rows.iter().filter(|r| r.verdict.failed()).count();
+    if failed > 0 {
+        bail!("{failed} of {} carts failed", rows.len());
+    }
+    Ok(())
+}
+
+/// Run a test script against a cart headless and exit non-zero if any check
+/// fails. `<input> <script> [--update]`; `--update` (re)writes golden PNGs,
+/// which must exist otherwise.
+fn headless_test(args: &[&str]) -> Result<()> {
+    use rico8_runtime::{audio::AudioHandle, script::Script, vm::GameVm};
+    let (mut input, mut script, mut update) = (None, None, false);
+    for &a in args {
+        match a {
+            "--update" => update = true,
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos if script.is_none() => script = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
         }
-        let wasm = std::fs::read(project.wasm_path()).context("Reading built wasm")?;
-        Cart {
-            wasm,
-            assets: project.assets.clone(),
-            // Web players can't edit; keep the page lean.
-            source: None,
+    }
+    let (Some(input), Some(script_path)) = (input, script) else {
+        bail!("Usage: rico8 test <dir|cart.png> <script> [--update]");
+    };
+    let script_path = Path::new(script_path);
+    let src = std::fs::read_to_string(script_path)
+        .with_context(|| format!("Reading {}", script_path.display()))?;
+    let script =
+        Script::parse(&src).with_context(|| format!("Parsing {}", script_path.display()))?;
+    let cart = load_or_build(Path::new(input))?;
+    let mut vm = GameVm::load(&cart.wasm, &cart.assets, AudioHandle::dummy())
+        .context("Loading cart into the VM")?;
+    let base = script_path.parent().unwrap_or(Path::new("."));
+    let report = script.run(&mut vm, base, update)?;
+    for name in &report.goldens_written {
+        println!("Wrote golden {name}");
+    }
+    for failure in &report.failures {
+        eprintln!("FAIL {} {failure}", script_path.display());
+    }
+    if !report.passed() {
+        bail!(
+            "{} of {} checks failed after {} frames",
+            report.failures.len(),
+            report.checks,
+            report.frames
+        );
+    }
+    println!(
+        "OK: {} checks passed over {} frames",
+        report.checks, report.frames
+    );
+    Ok(())
+}
+
+/// Read an input recording — a test script, whose `wait`/`press`/`hold`
+/// lines are replayed and whose checks are ignored — as per-frame button masks.
+fn read_input_recording(path: &str) -> Result<Vec<u8>> {
+    let src = std::fs::read_to_string(path).with_context(|| format!("Reading {path}"))?;
+    let script =
+        rico8_runtime::script::Script::parse(&src).with_context(|| format!("Parsing {path}"))?;
+    Ok(script.input_frames())
+}
+
+/// Parse a positive count for a flag like `--frames`.
+fn count_flag(v: &str, flag: &str) -> Result<usize> {
+    v.parse()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| anyhow!("{flag} needs a positive number, got {v}"))
+}
+
+/// Dump a headless run as numbered PNG frames plus one WAV rendered by the
+/// same synth, sample-exact with the cart's 30/60 fps, for assembling
+/// trailers with external tools. `<input> --out <dir> [--frames N]
+/// [--input script] [--scale K] [--band-limited]`; `--frames` defaults to
+/// the recording's length.
+fn headless_render(args: &[&str]) -> Result<()> {
+    use rico8_runtime::{
+        audio::{AudioHandle, OscQuality, Synth},
+        script,
+        vm::GameVm,
+        wav,
+    };
+    let (mut input, mut out, mut frames, mut recording) = (None, None, None, None);
+    let (mut scale, mut quality) = (1, OscQuality::Authentic);
+    let mut it = args.iter();
+    while let Some(&a) = it.next() {
+        match a {
+            "--out" => out = Some(flag_value(it.next(), "--out")?),
+            "--frames" => {
+                frames = Some(count_flag(flag_value(it.next(), "--frames")?, "--frames")?)
+            }
+            "--input" => recording = Some(flag_value(it.next(), "--input")?),
+            "--scale" => scale = count_flag(flag_value(it.next(), "--scale")?, "--scale")?,
+            "--band-limited" => quality = OscQuality::BandLimited,
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
         }
+    }
+    let (Some(input), Some(out)) = (input, out) else {
+        bail!(
+            "Usage: rico8 render <dir|cart.png> --out <dir> [--frames N] \
+             [--input script] [--scale K] [--band-limited]"
+        );
     };
-    webexport::export_html(&cart, out, &webexport::web_crate_dir(&sdk_path()))?;
-    println!("Exported {}", out.display());
+    let inputs = recording
+        .map(read_input_recording)
+        .transpose()?
+        .unwrap_or_default();
+    let frames = match frames {
+        Some(n) => n,
+        None if !inputs.is_empty() => inputs.len(),
+        None => bail!("--frames is required without an --input recording"),
+    };
+    let cart = load_or_build(Path::new(input))?;
+    let mut synth = Synth::new(wav::SAMPLE_RATE as f32);
+    synth.set_quality(quality);
+    let audio = AudioHandle::new(Arc::new(std::sync::Mutex::new(synth)));
+    let mut vm = GameVm::load(&cart.wasm, &cart.assets, audio.clone())
+        .context("Loading cart into the VM")?;
+    let out = Path::new(out);
+    std::fs::create_dir_all(out).with_context(|| format!("Creating {}", out.display()))?;
+    let fps = vm.fps() as usize;
+    let rate = wav::SAMPLE_RATE as usize;
+    let mut samples = Vec::with_capacity(frames * rate / fps + 1);
+    for frame in 0..frames {
+        script::set_buttons(&mut vm, inputs.get(frame).copied().unwrap_or(0));
+        vm.call_update()
+            .and_then(|()| vm.call_draw())
+            .map_err(|e| anyhow!("Frame {frame}: {e}"))?;
+        let png = cart::encode_screen_png(&vm.state().fb, scale);
+        std::fs::write(out.join(format!("frame_{frame:05}.png")), png)?;
+        // Exactly this frame's share of the timeline, so audio never drifts
+        // from the pictures however long the dump runs.
+        let len = samples.len();
+        samples.resize(len + (frame + 1) * rate / fps - frame * rate / fps, 0.0);
+        audio.render_block(&mut samples[len..]);
+    }
+    wav::write(&out.join("audio.wav"), &samples, wav::SAMPLE_RATE)?;
+    println!(
+        "Wrote {frames} frames at {fps} fps and audio.wav to {dir}\n\
+         Assemble with: ffmpeg -framerate {fps} -i {dir}/frame_%05d.png \
+         -i {dir}/audio.wav -c:v libx264 -pix_fmt yuv420p -c:a aac out.mp4",
+        dir = out.display()
+    );
     Ok(())
 }
 
-/// Load a cart and run a second of frames without a window — a smoke
-/// test for carts and for the console itself (used by CI).
-fn headless_verify(png: &Path) -> Result<()> {
-    use rico8_runtime::{audio::AudioHandle, vm::GameVm};
-    let cart = cart::load_png(png)?;
+/// Random but reproducible button input: runs of random combinations held
+/// for 1-20 frames, weighted toward single buttons and odd chords.
+fn fuzz_inputs(seed: u64, frames: usize) -> Vec<u8> {
+    // xorshift64*, seeded away from the all-zero fixed point.
+    let mut state = seed ^ 0x9e37_79b9_7f4a_7c15;
+    let mut next = move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32
+    };
+    let mut masks = Vec::with_capacity(frames);
+    while masks.len() < frames {
+        let mask = match next() % 4 {
+            0 => 0,
+            1 => 1 << (next() % 6),
+            _ => (next() % 64) as u8,
+        };
+        let hold = 1 + next() as usize % 20;
+        masks.extend(std::iter::repeat_n(mask, hold.min(frames - masks.len())));
+    }
+    masks
+}
+
+/// Drive a cart with random button sequences, one fresh VM per run, and
+/// report every runtime error (panic, fuel, memory) with the run's seed and
+/// frame. Each crash also leaves `fuzz_<seed>.txt`, an input script that
+/// replays it with `test` or `--input`. `<input> [--frames N] [--runs M]
+/// [--seed S] [--out dir]`; run `r` uses seed `S + r`.
+fn headless_fuzz(args: &[&str]) -> Result<()> {
+    use rico8_runtime::{audio::AudioHandle, script, vm::GameVm};
+    let (mut input, mut seed, mut out) = (None, None, ".");
+    let (mut frames, mut runs) = (1800, 20);
+    let mut it = args.iter();
+    while let Some(&a) = it.next() {
+        match a {
+            "--frames" => frames = count_flag(flag_value(it.next(), "--frames")?, "--frames")?,
+            "--runs" => runs = count_flag(flag_value(it.next(), "--runs")?, "--runs")?,
+            "--seed" => {
+                let v = flag_value(it.next(), "--seed")?;
+                seed = Some(
+                    v.parse::<u64>()
+                        .map_err(|_| anyhow!("--seed needs a number, got {v}"))?,
+                );
+            }
+            "--out" => out = flag_value(it.next(), "--out")?,
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
+        }
+    }
+    let Some(input) = input else {
+        bail!("Usage: rico8 fuzz <dir|cart.png> [--frames N] [--runs M] [--seed S] [--out dir]");
+    };
+    let seed = seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    });
+    let cart = load_or_build(Path::new(input))?;
+    println!("Fuzzing {runs} runs of {frames} frames from seed {seed}");
+    let mut crashes = 0;
+    for run in 0..runs as u64 {
+        let run_seed = seed.wrapping_add(run);
+        let inputs = fuzz_inputs(run_seed, frames);
+        let mut vm = GameVm::load(&cart.wasm, &cart.assets, AudioHandle::dummy())
+            .context("Loading cart into the VM")?;
+        for (frame, &mask) in inputs.iter().enumerate() {
+            script::set_buttons(&mut vm, mask);
+            let result = vm.call_update().and_then(|()| vm.call_draw());
+            vm.state_mut().logs.clear();
+            let Err(e) = result else { continue };
+            crashes += 1;
+            let repro = Path::new(out).join(format!("fuzz_{run_seed}.txt"));
+            let script = format!(
+                "# rico8 fuzz --seed {run_seed} --runs 1: {} at frame {frame}\n{}",
+                e.message.lines().next().unwrap_or(e.phase),
+                script::write_inputs(&inputs[..=frame])
+            );
+            std::fs::write(&repro, script)
+                .with_context(|| format!("Writing {}", repro.display()))?;
+            eprintln!("CRASH seed {run_seed} frame {frame}: {e}");
+            eprintln!("  replay: rico8 test {input} {}", repro.display());
+            break;
+        }
+    }
+    if crashes > 0 {
+        bail!("{crashes} of {runs} runs crashed");
+    }
+    println!("OK: no crashes in {runs} runs");
+    Ok(())
+}
+
+/// Summary of one phase's per-frame fuel.
+#[derive(Debug, PartialEq)]
+struct FuelStats {
+    min: u64,
+    mean: u64,
+    p95: u64,
+    p99: u64,
+    max: u64,
+}
+
+impl FuelStats {
+    /// Nearest-rank percentiles over a non-empty set of samples.
+    fn of(samples: &[u64]) -> FuelStats {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let rank = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        FuelStats {
+            min: sorted[0],
+            mean: sorted.iter().sum::<u64>() / sorted.len() as u64,
+            p95: rank(95),
+            p99: rank(99),
+            max: sorted[sorted.len() - 1],
+        }
+    }
+}
+
+/// Run a cart headless and report its fuel use per phase against the
+/// per-call budget, its memory high-water, and the frames that came closest
+/// to the budget. `<input> [--frames N] [--input script] [--csv out.csv]`;
+/// the CSV has one row per frame for charting across commits.
+fn headless_bench(args: &[&str]) -> Result<()> {
+    use rico8_runtime::{
+        audio::AudioHandle,
+        script,
+        vm::{GameVm, FUEL_PER_CALL},
+    };
+    let (mut input, mut frames, mut recording, mut csv) = (None, None, None, None);
+    let mut it = args.iter();
+    while let Some(&a) = it.next() {
+        match a {
+            "--frames" => {
+                frames = Some(count_flag(flag_value(it.next(), "--frames")?, "--frames")?)
+            }
+            "--input" => recording = Some(flag_value(it.next(), "--input")?),
+            "--csv" => csv = Some(flag_value(it.next(), "--csv")?),
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
+        }
+    }
+    let Some(input) = input else {
+        bail!("Usage: rico8 bench <dir|cart.png> [--frames N] [--input script] [--csv out.csv]");
+    };
+    let inputs = recording
+        .map(read_input_recording)
+        .transpose()?
+        .unwrap_or_default();
+    let frames = frames.unwrap_or(if inputs.is_empty() { 600 } else { inputs.len() });
+    let cart = load_or_build(Path::new(input))?;
     let mut vm = GameVm::load(&cart.wasm, &cart.assets, AudioHandle::dummy())
         .context("Loading cart into the VM")?;
-    for frame in 0..60 {
+
+    let (mut update, mut draw, mut mem) = (Vec::new(), Vec::new(), Vec::new());
+    for frame in 0..frames {
+        script::set_buttons(&mut vm, inputs.get(frame).copied().unwrap_or(0));
         vm.call_update()
             .and_then(|()| vm.call_draw())
             .map_err(|e| anyhow!("Frame {frame}: {e}"))?;
+        vm.state_mut().logs.clear();
+        update.push(vm.fuel_update());
+        draw.push(vm.fuel_draw());
+        mem.push(vm.mem_used_bytes());
     }
-    let drew_something = vm.state().fb.pixels().iter().any(|&p| p != 0);
+
+    let pct = |fuel: u64| fuel as f64 * 100.0 / FUEL_PER_CALL as f64;
     println!(
-        "OK: {} ran 60 frames{}",
+        "{}: {frames} frames at {} fps, budget {FUEL_PER_CALL} fuel per call\n",
         cart.assets.meta.name,
-        if drew_something {
-            ""
-        } else {
-            " (blank screen)"
-        }
+        vm.fps()
     );
+    println!(
+        "{:8}{:>9}{:>9}{:>9}{:>9}{:>9}",
+        "phase", "min", "mean", "p95", "p99", "max"
+    );
+    for (name, samples) in [("update", &update), ("draw", &draw)] {
+        let s = FuelStats::of(samples);
+        println!(
+            "{name:8}{:>9}{:>9}{:>9}{:>9}{:>9}  ({:.1}% peak)",
+            s.min,
+            s.mean,
+            s.p95,
+            s.p99,
+            s.max,
+            pct(s.max)
+        );
+    }
+    let high = mem.iter().copied().max().unwrap_or(0);
+    if high > 0 {
+        println!(
+            "\nmemory high-water {:.1}K of 128K ({:.1}%)",
+            high as f64 / 1024.0,
+            high as f64 * 100.0 / cart::MEMORY_CAP as f64
+        );
+    } else {
+        println!("\nmemory high-water unknown (no rico8_mem_used export)");
+    }
+    let mut worst: Vec<(usize, &str, u64)> = (0..frames)
+        .flat_map(|f| [(f, "update", update[f]), (f, "draw", draw[f])])
+        .collect();
+    worst.sort_by_key(|&(f, _, fuel)| (std::cmp::Reverse(fuel), f));
+    println!("\nclosest to the budget:");
+    for (frame, phase, fuel) in worst.iter().take(5) {
+        println!(
+            "  frame {frame:>6} {phase:6} {fuel:>7} ({:.1}%)",
+            pct(*fuel)
+        );
+    }
+
+    if let Some(csv) = csv {
+        let mut out = String::from("frame,update_fuel,draw_fuel,mem_used\n");
+        for f in 0..frames {
+            out.push_str(&format!("{f},{},{},{}\n", update[f], draw[f], mem[f]));
+        }
+        std::fs::write(csv, out).with_context(|| format!("Writing {csv}"))?;
+        println!("Wrote {csv}");
+    }
     Ok(())
 }
 
+/// Serve the gym stepping protocol (see `gym.rs`) on stdin/stdout.
+/// `<input> [--screen raw|png|none] [--max-frames N]`.
+fn headless_gym(args: &[&str]) -> Result<()> {
+    let (mut input, mut screen, mut max_frames) = (None, gym::ScreenFormat::Raw, None);
+    let mut it = args.iter();
+    while let Some(&a) = it.next() {
+        match a {
+            "--screen" => screen = gym::ScreenFormat::parse(flag_value(it.next(), "--screen")?)?,
+            "--max-frames" => {
+                let v = flag_value(it.next(), "--max-frames")?;
+                max_frames = Some(count_flag(v, "--max-frames")? as u64);
+            }
+            flag if flag.starts_with("--") => bail!("unknown flag {flag}"),
+            pos if input.is_none() => input = Some(pos),
+            pos => bail!("unexpected argument {pos}"),
+        }
+    }
+    let Some(input) = input else {
+        bail!("Usage: rico8 gym <dir|cart.png> [--screen raw|png|none] [--max-frames N]");
+    };
+    let cart = load_or_build(Path::new(input))?;
+    let stdin = std::io::stdin().lock();
+    let stdout = std::io::stdout().lock();
+    gym::Gym::new(&cart, screen, max_frames)?.serve(stdin, stdout)
+}
+
 /// Render the console and each editor headless and save screenshots.
 /// Undocumented helper for docs and visual checks.
 fn headless_snap(project: &Path, outdir: &Path) -> Result<()> {
     use rico8_runtime::{audio::AudioHandle, cart::encode_screen_png};
     std::fs::create_dir_all(outdir)?;
-    let mut shell = Shell::new(AudioHandle::dummy(), sdk_path());
+    let mut shell = Shell::new(AudioHandle::dummy(), AudioHandle::dummy(), sdk_path());
     shell.startup_load(&project.to_string_lossy());
     let shots = [
         (shell::Mode::Console, "console"),
@@ -348,17 +1067,26 @@ fn headless_snap(project: &Path, outdir: &Path) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn run_windowed(load: Option<String>, auto_run: bool) -> Result<()> {
+    use rico8_runtime::audio::AudioHandle;
     #[cfg(feature = "audio")]
     let audio_out = rico8_runtime::audio::AudioOutput::start();
     #[cfg(feature = "audio")]
-    let audio = audio_out
-        .as_ref()
-        .map(|a| a.handle())
-        .unwrap_or_else(rico8_runtime::audio::AudioHandle::dummy);
+    let (audio, preview) = audio_out.as_ref().map_or_else(
+        || (AudioHandle::dummy(), AudioHandle::dummy()),
+        |a| (a.handle(), a.preview()),
+    );
     #[cfg(not(feature = "audio"))]
-    let audio = rico8_runtime::audio::AudioHandle::dummy();
+    let (audio, preview) = (AudioHandle::dummy(), AudioHandle::dummy());
+    let quality = rico8_runtime::audio::OscQuality::from_env();
+    audio.set_quality(quality);
+    preview.set_quality(quality);
 
-    let mut shell = Shell::new(audio, sdk_path());
+    let mut shell = Shell::new(audio, preview, sdk_path());
+    if let Some(port) = remote::port_from_env()? {
+        let server = remote::DebugServer::bind(port)?;
+        eprintln!("rico8: Debug protocol listening on {}", server.addr());
+        shell.serve_remote(server);
+    }
     if let Some(path) = load {
         shell.startup_load(&path);
         if auto_run {
@@ -426,6 +1154,14 @@ impl App {
             WKey::Named(NamedKey::Space) => Key::Char(' '),
             WKey::Named(NamedKey::F1) => Key::ToggleStats,
             WKey::Named(NamedKey::F6) => Key::CaptureLabel,
+            WKey::Named(NamedKey::F7) => Key::Screenshot,
+            WKey::Named(NamedKey::F8) => Key::Record,
+            WKey::Named(NamedKey::F9) => Key::Pause,
+            WKey::Named(NamedKey::F10) => Key::Step,
+            WKey::Named(NamedKey::F5) => Key::Resume,
+            WKey::Named(NamedKey::F2) => Key::Slower,
+            WKey::Named(NamedKey::F3) => Key::Faster,
+            WKey::Named(NamedKey::F4) => Key::Live,
             WKey::Character(s) => Key::Char(s.chars().next()?),
             _ => return None,
         })
@@ -568,6 +1304,294 @@ impl ApplicationHandler for App {
 mod tests {
     use super::*;
 
+    /// Moves a pixel right while Right is held and starts SFX 0 on the first
+    /// frame, so dumps have both pictures and sound to check.
+    const MOVER_CART: &str = r#"
+        (module
+          (import "rico8" "clear" (func $cls (param i32)))
+          (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+          (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+          (import "rico8" "sfx" (func $sfx (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $x (mut i32) (i32.const 0))
+          (global $started (mut i32) (i32.const 0))
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (if (i32.eqz (global.get $started))
+              (then (call $sfx (i32.const 0) (i32.const -1))
+                    (global.set $started (i32.const 1))))
+            (if (i32.ne (call $btn (i32.const 1)) (i32.const 0))
+              (then (global.set $x (i32.add (global.get $x) (i32.const 1))))))
+          (func (export "rico8_draw")
+            (call $cls (i32.const 1))
+            (call $pset (global.get $x) (i32.const 0) (i32.const 8)))
+        )
+    "#;
+
+    /// Write `wat` as a playable cart with an audible SFX 0 into `dir`.
+    fn wat_cart(dir: &Path, wat: &str) -> PathBuf {
+        let mut assets = rico8_runtime::assets::Assets::default();
+        for n in assets.sfx[0].notes.iter_mut() {
+            n.pitch = 30;
+            n.volume = 5;
+        }
+        let cart = Cart {
+            wasm: wat::parse_str(wat).unwrap(),
+            assets,
+            source: None,
+        };
+        let path = dir.join("cart.png");
+        cart::save_png(&cart, &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn render_audio_rejects_both_sources() {
+        let err = headless_render_audio(&["p", "o.wav", "--sfx", "1", "--music", "0"]).unwrap_err();
+        assert!(err.to_string().contains("exclusive"), "got: {err}");
+    }
+
+    #[test]
+    fn render_audio_writes_a_project_song() {
+        let dir = std::env::temp_dir().join(format!("rico8_render_audio_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut project = Project::create(&dir, "tune").unwrap();
+        project.assets.sfx[3].notes[0].volume = 5;
+        project.assets.music[0].channels[0] = Some(3);
+        project.assets.music[0].stop_at_end = true;
+        project.save().unwrap();
+        let out = dir.join("song.wav");
+        let (d, o) = (dir.to_string_lossy(), out.to_string_lossy());
+        headless_render_audio(&[&d, &o, "--seconds", "5"]).unwrap();
+        let bytes = std::fs::read(&out).unwrap();
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert!(bytes.len() > 44, "has samples");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_needs_a_cart_and_a_script() {
+        let err = headless_test(&["cart.png"]).unwrap_err();
+        assert!(err.to_string().contains("Usage"), "got: {err}");
+        let err = headless_test(&["cart.png", "t.txt", "--bless"]).unwrap_err();
+        assert!(err.to_string().contains("unknown flag"), "got: {err}");
+    }
+
+    #[test]
+    fn test_reports_script_errors_before_loading_the_cart() {
+        let dir = std::env::temp_dir().join(format!("rico8_test_cli_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("smoke.txt");
+        std::fs::write(&script, "wait 10\njump 3\n").unwrap();
+        let err = headless_test(&["missing.png", &script.to_string_lossy()]).unwrap_err();
+        assert!(format!("{err:#}").contains("line 2"), "got: {err:#}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn render_dumps_frames_and_sample_exact_audio() {
+        let dir = std::env::temp_dir().join(format!("rico8_render_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cart = wat_cart(&dir, MOVER_CART);
+        let rec = dir.join("rec.txt");
+        std::fs::write(&rec, "wait 2\nhold right 3\n").unwrap();
+        let out = dir.join("out");
+        let (c, r, o) = (
+            cart.to_string_lossy(),
+            rec.to_string_lossy(),
+            out.to_string_lossy(),
+        );
+        headless_render(&[&c, "--input", &r, "--out", &o, "--scale", "2"]).unwrap();
+
+        let last = std::fs::read(out.join("frame_00004.png")).unwrap();
+        assert!(
+            !out.join("frame_00005.png").exists(),
+            "frames follow the recording"
+        );
+        let (w, _, rgba) = cart::decode_png_rgba(&last).unwrap();
+        assert_eq!(w, 256);
+        let red = rico8_runtime::palette::rgba(8);
+        assert_eq!(rgba[3 * 2 * 4..3 * 2 * 4 + 4], red, "moved 3 pixels right");
+
+        let wav = std::fs::read(out.join("audio.wav")).unwrap();
+        let data_len = u32::from_le_bytes(wav[40..44].try_into().unwrap());
+        assert_eq!(data_len as usize, 5 * 735 * 2, "5 frames at 60 fps, 16-bit");
+        assert!(wav[44..]
+            .chunks(2)
+            .any(|s| i16::from_le_bytes([s[0], s[1]]).abs() > 300));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn render_needs_a_length() {
+        let err = headless_render(&["cart.png", "--out", "x"]).unwrap_err();
+        assert!(err.to_string().contains("--frames"), "got: {err}");
+    }
+
+    #[test]
+    fn fuzz_inputs_are_reproducible_and_varied() {
+        let a = fuzz_inputs(7, 600);
+        assert_eq!(a.len(), 600);
+        assert_eq!(a, fuzz_inputs(7, 600), "same seed, same input");
+        assert_ne!(a, fuzz_inputs(8, 600));
+        let distinct: std::collections::HashSet<u8> = a.iter().copied().collect();
+        assert!(distinct.len() > 10, "many combinations: {distinct:?}");
+    }
+
+    #[test]
+    fn fuzz_finds_a_chord_crash_and_writes_a_replay() {
+        // Traps only while Left and X are held together.
+        const CHORD_CRASH_CART: &str = r#"
+            (module
+              (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (if (i32.and (call $btn (i32.const 0)) (call $btn (i32.const 5)))
+                  (then unreachable)))
+              (func (export "rico8_draw"))
+            )
+        "#;
+        let dir = std::env::temp_dir().join(format!("rico8_fuzz_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cart = wat_cart(&dir, CHORD_CRASH_CART);
+        let (c, d) = (cart.to_string_lossy(), dir.to_string_lossy());
+        let args = [
+            &c, "--runs", "3", "--frames", "600", "--seed", "1", "--out", &d,
+        ];
+        let err = headless_fuzz(&args).unwrap_err();
+        assert!(err.to_string().contains("crashed"), "got: {err}");
+
+        let repro = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .find(|p| p.to_string_lossy().contains("fuzz_"))
+            .expect("a replay script");
+        let script = std::fs::read_to_string(&repro).unwrap();
+        assert!(script.starts_with("# rico8 fuzz --seed"), "{script}");
+        let last = script.lines().last().unwrap();
+        assert!(
+            last.contains("left") && last.contains('x'),
+            "ends on the chord: {last}"
+        );
+        let err = headless_test(&[&c, &repro.to_string_lossy()]).unwrap_err();
+        assert!(
+            err.to_string().contains("failed"),
+            "replay crashes too: {err}"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn verify_dir_reports_each_cart() {
+        let dir = std::env::temp_dir().join(format!("rico8_verify_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let carts = [
+            ("a_pass.png", MOVER_CART),
+            (
+                "b_blank.png",
+                r#"(module (memory (export "memory") 1)
+                     (func (export "rico8_init")) (func (export "rico8_update"))
+                     (func (export "rico8_draw")))"#,
+            ),
+            (
+                "c_crash.png",
+                r#"(module (memory (export "memory") 1)
+                     (func (export "rico8_init")) (func (export "rico8_update") unreachable)
+                     (func (export "rico8_draw")))"#,
+            ),
+        ];
+        for (file, wat) in carts {
+            std::fs::rename(wat_cart(&dir, wat), dir.join(file)).unwrap();
+        }
+        std::fs::write(dir.join("d_corrupt.png"), b"not a png").unwrap();
+        let shot = cart::save_screenshot(&rico8_runtime::fb::Framebuffer::new(), &dir).unwrap();
+
+        let (rows, skipped) = verify_dir(&dir).unwrap();
+        let got: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|r| (r.file.as_str(), r.verdict.label()))
+            .collect();
+        assert_eq!(
+            got,
+            [
+                ("a_pass.png", "pass"),
+                ("b_blank.png", "blank screen"),
+                ("c_crash.png", "runtime error"),
+                ("d_corrupt.png", "load error"),
+            ]
+        );
+        assert_eq!(skipped, 1, "screenshot {} is not a cart", shot.display());
+        assert!(rows[2].verdict.detail().starts_with("Frame 0:"));
+
+        let json = verify_json(&rows, skipped);
+        assert!(json.contains(r#""file": "d_corrupt.png", "name": "", "result": "load error""#));
+        assert!(json.contains(r#""summary": {"pass": 1, "blank": 1, "runtime_error": 1, "load_error": 1, "skipped": 1}"#));
+        let d = dir.to_string_lossy();
+        let err = headless_verify(&[&d, "--json"]).unwrap_err();
+        assert_eq!(err.to_string(), "2 of 4 carts failed");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn fuel_stats_use_nearest_rank_percentiles() {
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        let s = FuelStats::of(&samples);
+        assert_eq!(
+            s,
+            FuelStats {
+                min: 1,
+                mean: 50,
+                p95: 95,
+                p99: 99,
+                max: 100
+            }
+        );
+        assert_eq!(FuelStats::of(&[7]).p99, 7);
+    }
+
+    #[test]
+    fn bench_writes_a_row_per_frame() {
+        // Update work grows with the frame count; memory use is reported.
+        const GROWING_CART: &str = r#"
+            (module
+              (memory (export "memory") 1)
+              (global $n (mut i32) (i32.const 0))
+              (func (export "rico8_init"))
+              (func (export "rico8_update") (local $i i32)
+                (global.set $n (i32.add (global.get $n) (i32.const 100)))
+                (loop $l
+                  (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                  (br_if $l (i32.lt_u (local.get $i) (global.get $n)))))
+              (func (export "rico8_draw"))
+              (func (export "rico8_mem_used") (result i32) (i32.const 40960)))
+        "#;
+        let dir = std::env::temp_dir().join(format!("rico8_bench_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let cart = wat_cart(&dir, GROWING_CART);
+        let csv = dir.join("fuel.csv");
+        let (c, o) = (cart.to_string_lossy(), csv.to_string_lossy());
+        headless_bench(&[&c, "--frames", "10", "--csv", &o]).unwrap();
+
+        let text = std::fs::read_to_string(&csv).unwrap();
+        let rows: Vec<Vec<u64>> = text
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 10);
+        assert!(
+            rows.windows(2).all(|w| w[1][1] > w[0][1]),
+            "update fuel grows"
+        );
+        assert!(rows.iter().all(|r| r[3] == 40960));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn into_rejects_positional_dir() {
         // `--into` supplies the destination, so a positional dir is ambiguous.
@@ -0,0 +1,520 @@
+//! The debug protocol: lets editor integrations and dashboards drive the
+//! cart running in the console from outside its 128x128 screen.
+//!
+//! Opt-in: with `RICO8_DEBUG_PORT=<port>` set, the console listens on
+//! `127.0.0.1:<port>` (loopback only). Each side sends one JSON object per
+//! line. Requests are flat objects with a `cmd`, plus an optional `id`
+//! that the reply echoes:
+//!
+//! ```text
+//! {"cmd": "status"}                         running, paused, frame, fps, speed
+//! {"cmd": "pause"}  {"cmd": "resume"}
+//! {"cmd": "step", "frames": 1}              pause, run N (<= 300) -> frame
+//! {"cmd": "read", "addr": 4096, "len": 16}  guest memory          -> data (hex)
+//! {"cmd": "write", "addr": 4096, "data": "2a00"}
+//! {"cmd": "screen"}                         -> width, height, pixels
+//! {"cmd": "buttons", "mask": 16}            hold player 1's buttons
+//! {"cmd": "logs"}                           tail the cart's logs on this connection
+//! {"cmd": "reload"}                         reboot the latest build, keeping state
+//! ```
+//!
+//! Replies are `{"id": .., "ok": true, ...results}` or `{"id": .., "ok":
+//! false, "error": "..."}`. A line that isn't a well-formed request gets the
+//! error and the connection is closed. `pixels` is 16384 hex digits of palette
+//! indices, row by row, as in `rico8 gym`; `mask` is the `rico8 test`
+//! bitmask (bit 0 left .. bit 5 X). After `logs`, the connection also gets
+//! `{"event": "log", "level": ..., "frame": ..., "text": ...}` for every cart
+//! log line and `{"event": "error", "text": ...}` when the cart stops on an
+//! error.
+//!
+//! Connections are served on their own threads; the shell answers the
+//! requests between frames, on the main thread, so the VM never crosses
+//! one.
+
+use crate::json_string;
+use anyhow::{anyhow, bail, Context, Result};
+use rico8_runtime::log::LogLine;
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    ops::Range,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// Largest `read`, so a reply stays a sensible line.
+pub const MAX_READ: u32 = 64 * 1024;
+
+/// Most frames one `step` runs: five seconds at 60 fps. The shell runs them
+/// back to back on the UI thread, so a bigger count would freeze it.
+pub const MAX_STEP: u32 = 300;
+
+/// A request, parsed.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Status,
+    Pause,
+    Resume,
+    Step { frames: u32 },
+    Read { addr: u32, len: u32 },
+    Write { addr: u32, data: Vec<u8> },
+    Screen,
+    Buttons { mask: u32 },
+    Logs,
+    Reload,
+}
+
+/// A request waiting for the shell, with the way back to its connection.
+pub struct Request {
+    /// The request's `id`, already JSON-encoded.
+    id: Option<String>,
+    pub command: Command,
+    out: Sender<String>,
+}
+
+impl Request {
+    /// Answer with `fields`, the results as JSON members (may be empty).
+    pub fn reply(&self, fields: &str) {
+        let _ = self.out.send(reply_line(self.id.as_deref(), fields));
+    }
+
+    pub fn fail(&self, message: &str) {
+        let _ = self.out.send(error_line(self.id.as_deref(), message));
+    }
+
+    /// Where to send events for this request's connection. Sending fails
+    /// once the tool hangs up.
+    pub fn events(&self) -> Sender<String> {
+        self.out.clone()
+    }
+}
+
+/// An event line, e.g. `{"event": "error", "text": "..."}`.
+pub fn event(kind: &str, text: &str) -> String {
+    format!(
+        "{{\"event\": {}, \"text\": {}}}",
+        json_string(kind),
+        json_string(text)
+    )
+}
+
+/// A cart log line as an event: `{"event": "log", "level": "warn", "frame":
+/// 12, "text": "low hp"}`.
+pub fn log_event(line: &LogLine) -> String {
+    format!(
+        "{{\"event\": \"log\", \"level\": {}, \"frame\": {}, \"text\": {}}}",
+        json_string(line.level.name()),
+        line.frame,
+        json_string(&line.text)
+    )
+}
+
+/// The port from `RICO8_DEBUG_PORT`, if the protocol is wanted.
+pub fn port_from_env() -> Result<Option<u16>> {
+    match std::env::var("RICO8_DEBUG_PORT") {
+        Ok(port) => port
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow!("RICO8_DEBUG_PORT must be a port number, got {port}")),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The listening side: accepts connections and queues their requests.
+pub struct DebugServer {
+    addr: SocketAddr,
+    requests: Receiver<Request>,
+}
+
+impl DebugServer {
+    /// Listen on the loopback interface; port 0 picks a free one.
+    pub fn bind(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .with_context(|| format!("Listening for debug connections on port {port}"))?;
+        let addr = listener.local_addr()?;
+        let (tx, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let tx = tx.clone();
+                thread::spawn(move || serve(stream, tx));
+            }
+        });
+        Ok(Self { addr, requests })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The next request waiting, if any.
+    pub fn poll(&self) -> Option<Request> {
+        self.requests.try_recv().ok()
+    }
+}
+
+/// Read one connection's requests until it closes. The rest go to the
+/// shell, but a malformed line is answered here and ends the connection: a
+/// web page can POST to the port too, and once its HTTP header lines were
+/// turned away, JSON lines in its body would otherwise run.
+fn serve(stream: TcpStream, requests: Sender<Request>) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    let (out, outbox) = mpsc::channel::<String>();
+    thread::spawn(move || write_lines(writer, outbox));
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_request(&line) {
+            Ok((id, command)) => {
+                let request = Request {
+                    id,
+                    command,
+                    out: out.clone(),
+                };
+                if requests.send(request).is_err() {
+                    break;
+                }
+            }
+            Err(e) => {
+                let _ = out.send(error_line(None, &format!("{e:#}")));
+                break;
+            }
+        }
+    }
+}
+
+fn write_lines(mut stream: TcpStream, outbox: Receiver<String>) {
+    for line in outbox {
+        if writeln!(stream, "{line}").is_err() {
+            break;
+        }
+    }
+}
+
+fn reply_line(id: Option<&str>, fields: &str) -> String {
+    let mut line = String::from("{");
+    if let Some(id) = id {
+        line.push_str(&format!("\"id\": {id}, "));
+    }
+    line.push_str("\"ok\": true");
+    if !fields.is_empty() {
+        line.push_str(", ");
+        line.push_str(fields);
+    }
+    line.push('}');
+    line
+}
+
+fn error_line(id: Option<&str>, message: &str) -> String {
+    let mut line = String::from("{");
+    if let Some(id) = id {
+        line.push_str(&format!("\"id\": {id}, "));
+    }
+    line.push_str(&format!(
+        "\"ok\": false, \"error\": {}}}",
+        json_string(message)
+    ));
+    line
+}
+
+/// The span of memory `addr..addr + len`, if it fits in `size` bytes.
+pub fn span(size: usize, addr: u32, len: u32) -> Result<Range<usize>> {
+    let start = addr as usize;
+    let end = start + len as usize;
+    if end > size {
+        bail!("{addr:#x}+{len} is past the end of memory ({size:#x})");
+    }
+    Ok(start..end)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        bail!("data must be an even number of hex digits");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| anyhow!("data must be hex, got {hex}"))
+        })
+        .collect()
+}
+
+fn parse_request(line: &str) -> Result<(Option<String>, Command)> {
+    let fields = parse_object(line)?;
+    let get = |name: &str| fields.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+    let id = match get("id") {
+        None | Some(Value::Null) => None,
+        Some(Value::Int(n)) => Some(n.to_string()),
+        Some(Value::Str(s)) => Some(json_string(s)),
+    };
+    let int = |name: &str, default: Option<u32>| -> Result<u32> {
+        match get(name) {
+            Some(Value::Int(n)) => u32::try_from(*n).map_err(|_| anyhow!("{name} out of range")),
+            None => default.ok_or_else(|| anyhow!("missing {name}")),
+            Some(_) => bail!("{name} must be an integer"),
+        }
+    };
+    let Some(Value::Str(cmd)) = get("cmd") else {
+        bail!("missing cmd");
+    };
+    let command = match cmd.as_str() {
+        "status" => Command::Status,
+        "pause" => Command::Pause,
+        "resume" => Command::Resume,
+        "step" => {
+            let frames = int("frames", Some(1))?;
+            if frames > MAX_STEP {
+                bail!("frames must be at most {MAX_STEP}");
+            }
+            Command::Step { frames }
+        }
+        "read" => Command::Read {
+            addr: int("addr", None)?,
+            len: int("len", None)?,
+        },
+        "write" => {
+            let Some(Value::Str(hex)) = get("data") else {
+                bail!("write needs data, a hex string");
+            };
+            Command::Write {
+                addr: int("addr", None)?,
+                data: from_hex(hex)?,
+            }
+        }
+        "screen" => Command::Screen,
+        "buttons" => Command::Buttons {
+            mask: int("mask", None)?,
+        },
+        "logs" => Command::Logs,
+        "reload" => Command::Reload,
+        other => bail!("unknown cmd {other}"),
+    };
+    Ok((id, command))
+}
+
+/// A member value of a request. Requests are flat, so nothing nests.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Null,
+}
+
+/// Parse one flat JSON object: string, integer and null members, which is
+/// all any request needs.
+fn parse_object(text: &str) -> Result<Vec<(String, Value)>> {
+    let mut p = Parser {
+        chars: text.chars().peekable(),
+    };
+    p.expect('{')?;
+    let mut fields = Vec::new();
+    p.skip_ws();
+    if p.chars.next_if_eq(&'}').is_none() {
+        loop {
+            let key = p.string()?;
+            p.expect(':')?;
+            fields.push((key, p.value()?));
+            p.skip_ws();
+            match p.chars.next() {
+                Some(',') => {}
+                Some('}') => break,
+                _ => bail!("expected ',' or '}}'"),
+            }
+        }
+    }
+    p.skip_ws();
+    if p.chars.next().is_some() {
+        bail!("trailing characters after the object");
+    }
+    Ok(fields)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, want: char) -> Result<()> {
+        self.skip_ws();
+        match self.chars.next() {
+            Some(c) if c == want => Ok(()),
+            Some(c) => bail!("expected '{want}', found '{c}'"),
+            None => bail!("expected '{want}', found the end"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let c = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| anyhow!("bad \\u escape"))?;
+                        s.push(c);
+                    }
+                    Some(c @ ('"' | '\\' | '/')) => s.push(c),
+                    _ => bail!("bad escape in a string"),
+                },
+                Some(c) => s.push(c),
+                None => bail!("unterminated string"),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_ws();
+        match self.chars.peek() {
+            Some('"') => Ok(Value::Str(self.string()?)),
+            Some(c) if c.is_ascii_digit() || *c == '-' => {
+                let mut n = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '-') {
+                    n.push(c);
+                }
+                n.parse()
+                    .map(Value::Int)
+                    .map_err(|_| anyhow!("bad number {n}"))
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null" => Ok(Value::Null),
+                    _ => bail!("values must be strings, integers or null"),
+                }
+            }
+            None => bail!("expected a value"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rico8_runtime::log::LogLevel;
+
+    #[test]
+    fn parses_requests() {
+        let (id, command) =
+            parse_request(r#"{"id": 7, "cmd": "read", "addr": 4096, "len": 16}"#).unwrap();
+        assert_eq!(id.as_deref(), Some("7"));
+        assert_eq!(
+            command,
+            Command::Read {
+                addr: 4096,
+                len: 16
+            }
+        );
+        let (id, command) =
+            parse_request(r#" {"cmd":"write","addr":0,"data":"2aFF","id":"a\"b"} "#).unwrap();
+        assert_eq!(id.as_deref(), Some(r#""a\"b""#));
+        assert_eq!(
+            command,
+            Command::Write {
+                addr: 0,
+                data: vec![0x2a, 0xff]
+            }
+        );
+        assert_eq!(
+            parse_request(r#"{"cmd": "step"}"#).unwrap().1,
+            Command::Step { frames: 1 }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        for (line, why) in [
+            ("cmd status", "expected '{'"),
+            (r#"{"cmd": "fly"}"#, "unknown cmd fly"),
+            (r#"{"cmd": "step", "frames": 100000}"#, "at most 300"),
+            (r#"{"cmd": "read", "addr": 1}"#, "missing len"),
+            (
+                r#"{"cmd": "read", "addr": -1, "len": 1}"#,
+                "addr out of range",
+            ),
+            (
+                r#"{"cmd": "write", "addr": 0, "data": "abc"}"#,
+                "even number",
+            ),
+            (r#"{"cmd": "screen", "x": [1]}"#, "values must be"),
+            (r#"{"cmd": "screen"} x"#, "trailing"),
+        ] {
+            let e = format!("{:#}", parse_request(line).unwrap_err());
+            assert!(e.contains(why), "{line}: {e}");
+        }
+    }
+
+    #[test]
+    fn connections_queue_requests_and_get_replies() {
+        let server = DebugServer::bind(0).unwrap();
+        let connect = || {
+            let stream = TcpStream::connect(server.addr()).unwrap();
+            let lines = BufReader::new(stream.try_clone().unwrap()).lines();
+            (stream, lines)
+        };
+
+        // An HTTP request is turned away at its request line, before any
+        // JSON in its body is read.
+        let (mut stream, mut lines) = connect();
+        write!(stream, "POST / HTTP/1.1\r\n").unwrap();
+        let line = lines.next().unwrap().unwrap();
+        assert!(line.starts_with(r#"{"ok": false, "error": "#), "{line}");
+        assert!(lines.next().is_none(), "closed");
+
+        let (mut stream, mut lines) = connect();
+        writeln!(stream, r#"{{"id": 1, "cmd": "status"}}"#).unwrap();
+        let request = loop {
+            if let Some(request) = server.poll() {
+                break request;
+            }
+            thread::yield_now();
+        };
+        assert_eq!(request.command, Command::Status);
+        request.reply("\"frame\": 3");
+        let line = LogLine {
+            frame: 7,
+            level: LogLevel::Warn,
+            text: "hi".into(),
+        };
+        request.events().send(log_event(&line)).unwrap();
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            r#"{"id": 1, "ok": true, "frame": 3}"#
+        );
+        assert_eq!(
+            lines.next().unwrap().unwrap(),
+            r#"{"event": "log", "level": "warn", "frame": 7, "text": "hi"}"#
+        );
+    }
+
+    #[test]
+    fn spans_stay_inside_memory() {
+        assert_eq!(span(0x10000, 0xfff0, 16).unwrap(), 0xfff0..0x10000);
+        assert!(span(0x10000, 0xfff0, 17).is_err());
+        assert_eq!(to_hex(&[0, 0xab]), "00ab");
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::{
     builder::{spawn_build, BuildJob},
+    debugger::Debugger,
     editor::{
         code::CodeEditor,
         file_picker::{FilePicker, PickerAction},
@@ -12,24 +13,37 @@ use crate::{
         sfx::SfxEditor,
         sprite::SpriteEditor,
     },
+    gym::hex_screen,
+    inspector::MemoryInspector,
+    json_string,
+    logview::LogView,
+    remote::{self, Command, DebugServer, Request},
     ui::{self, Mouse},
     watch::{FileChange, FileWatch, SourceTreeWatch},
 };
 use anyhow::{anyhow, bail, Result};
 use rico8_runtime::{
-    assets::Assets,
+    assets::{Assets, MapData, SpriteSheet},
     audio::AudioHandle,
-    cart::{self, Cart},
+    cart::{self, Cart, GifRecorder},
     clipboard::Pasted,
     fb::Framebuffer,
     font,
+    input::BUTTON_COUNT,
+    live::LiveAssets,
+    log::{LogLevel, LogLine},
+    netplay::Session,
     palette::col,
+    perf::{fps_color, stat_color, PerfHistory, PerfSample, StatsMode},
     project::{decode_assets, encode_assets, Project},
-    vm::{GameVm, RuntimeError, UI_FPS},
+    speed::{Speed, SpeedClock},
+    vm::{GameVm, PanicLocation, RuntimeError, UI_FPS},
+    wav,
 };
 use std::{
     collections::VecDeque,
     path::PathBuf,
+    sync::mpsc::Sender,
     time::{Duration, Instant, SystemTime},
 };
 
@@ -54,8 +68,25 @@ pub enum Key {
     PageDown,
     /// F6: capture the screen as the cart label while running.
     CaptureLabel,
+    /// F7: save a screenshot of whatever is on screen, in any mode.
+    Screenshot,
+    /// F8: start/stop recording an animated GIF while running (shift: 4x).
+    Record,
     /// F1: toggle the resource-usage overlay (CPU, memory, fps).
     ToggleStats,
+    /// F9: freeze the running cart in the frame-step debugger.
+    Pause,
+    /// F10: run exactly one frame of a paused cart.
+    Step,
+    /// F5: let a paused cart run again.
+    Resume,
+    /// F2: run the cart slower, down to 1/4x.
+    Slower,
+    /// F3: run the cart faster, up to unthrottled.
+    Faster,
+    /// F4: flip between the running cart and the editors without stopping
+    /// it (shift: hand the cart the editors' sheet and map whole).
+    Live,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -181,34 +212,6 @@ impl CartWatch {
     }
 }
 
-/// Color a budget fraction: green with headroom, yellow from 70%, red above
-/// 90%.
-fn stat_color(frac: f32) -> u8 {
-    if frac < 0.7 {
-        col::GREEN
-    } else if frac <= 0.9 {
-        col::YELLOW
-    } else {
-        col::RED
-    }
-}
-
-/// Color the fps reading by how close it is to the cart's target rate.
-fn fps_color(measured: f32, target: u32) -> u8 {
-    let ratio = if target > 0 {
-        measured / target as f32
-    } else {
-        1.0
-    };
-    if ratio >= 0.95 {
-        col::GREEN
-    } else if ratio >= 0.8 {
-        col::YELLOW
-    } else {
-        col::RED
-    }
-}
-
 /// Draw the resource-usage overlay in the top-right: CPU (update and draw),
 /// memory and measured fps, color-coded by how close each is to its budget.
 /// `used` is the cart's committed-memory high-water in bytes; it shows as KB
@@ -254,6 +257,22 @@ fn capture_flash_overlay(fb: &mut Framebuffer) {
     fb.cls(col::WHITE);
 }
 
+/// The blinking "REC" dot shown in the corner while an F8 recording runs.
+/// Drawn after the frame is captured, so it never appears in the GIF.
+fn recording_dot_overlay(fb: &mut Framebuffer) {
+    fb.rectfill(1, 1, 3, 3, col::RED);
+}
+
+/// The speed a cart runs at when it is not 1x, in the bottom-right corner.
+/// Like the REC dot, drawn after the frame is captured.
+fn speed_overlay(fb: &mut Framebuffer, speed: Speed) {
+    let label = speed.label();
+    let x0 = 127 - label.len() as i32 * font::GLYPH_W - 1;
+    let y0 = 127 - font::GLYPH_H;
+    fb.rectfill(x0, y0, 127, 127, col::BLACK);
+    fb.print(label, x0 + 1, y0 + 1, col::YELLOW);
+}
+
 enum ConsoleLine {
     Text {
         text: String,
@@ -269,6 +288,8 @@ pub struct Shell {
     loaded: Loaded,
     vm: Option<GameVm>,
     audio: AudioHandle,
+    /// The synth editor previews play on, apart from the cart's.
+    preview: AudioHandle,
     fb: Framebuffer,
     frame: u64,
 
@@ -313,8 +334,12 @@ pub struct Shell {
     cwd: PathBuf,
     sdk_path: PathBuf,
 
-    /// F1 toggles the CPU/memory/fps resource overlay.
-    show_stats: bool,
+    /// F1 steps the CPU/memory/fps resource overlay through numbers,
+    /// graphs and off.
+    stats: StatsMode,
+    /// The running cart's recent frames, for the graphs; sampled only while
+    /// the overlay is on.
+    perf: PerfHistory,
     // Wall-clock fps, measured over a moving window, shown in the overlay.
     fps_frames: u32,
     fps_t0: Instant,
@@ -322,19 +347,59 @@ pub struct Shell {
 
     /// Frames remaining of the camera-flash overlay shown after an F6 capture.
     capture_flash: u32,
+    /// The F8 GIF recording in progress, saved to the working dir when it stops.
+    recording: Option<GifRecorder>,
+    /// Where the last run panicked, when that is a file of the loaded
+    /// project: Tab in the console opens the code editor there.
+    error_location: Option<PanicLocation>,
+    /// F9/F10/F5 pause, step and resume the running cart.
+    debugger: Debugger,
+    /// F2/F3 slow the running cart down or speed it up.
+    speed: SpeedClock,
+    /// The memory inspector, when open; it takes every key.
+    inspector: Option<MemoryInspector>,
+    /// The last cart to trap, as its memory stood then, for `mem`.
+    trapped_memory: Option<MemoryInspector>,
+    /// The opt-in debug protocol (`RICO8_DEBUG_PORT`), when listening.
+    remote: Option<DebugServer>,
+    /// Debug connections tailing the cart's logs.
+    log_tails: Vec<Sender<String>>,
+    /// The current (or last) run's log lines, oldest dropped past
+    /// [`RUN_LOG_LINES`], for `log` and `savelog`.
+    run_log: VecDeque<LogLine>,
+    /// The log viewer, when open; it takes every key.
+    log_view: Option<LogView>,
+    /// The loaded assets as the running cart last got them: edits made
+    /// since, in editors opened over it with F4, are pushed in each tick.
+    live: Option<LiveAssets>,
+    /// `(player, bind, peer)` from `netplay`, for the cart it is booting.
+    netplay_to: Option<(u8, String, String)>,
+    /// The netplay session the running cart is one side of.
+    netplay: Option<Netplay>,
+}
+
+/// A running cart's netplay session. The session sets the VM's pads each
+/// frame, so the local player's buttons are kept here instead.
+struct Netplay {
+    session: Session,
+    buttons: u8,
 }
 
+/// Log lines a run keeps for the log viewer.
+const RUN_LOG_LINES: usize = 10_000;
+
 const TEXT_COLS: usize = 31;
 const PROMPT_COL: u8 = col::WHITE;
 
 impl Shell {
-    pub fn new(audio: AudioHandle, sdk_path: PathBuf) -> Self {
+    pub fn new(audio: AudioHandle, preview: AudioHandle, sdk_path: PathBuf) -> Self {
         let mut shell = Self {
             mode: Mode::Console,
             last_editor: Mode::Code,
             loaded: Loaded::None,
             vm: None,
             audio,
+            preview,
             fb: Framebuffer::new(),
             frame: 0,
             lines: VecDeque::new(),
@@ -360,11 +425,25 @@ impl Shell {
             want_exit: false,
             cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             sdk_path,
-            show_stats: false,
+            stats: StatsMode::Off,
+            perf: PerfHistory::default(),
             fps_frames: 0,
             fps_t0: Instant::now(),
             fps_val: 0.0,
             capture_flash: 0,
+            recording: None,
+            error_location: None,
+            debugger: Debugger::default(),
+            speed: SpeedClock::default(),
+            inspector: None,
+            trapped_memory: None,
+            remote: None,
+            log_tails: Vec::new(),
+            run_log: VecDeque::new(),
+            log_view: None,
+            live: None,
+            netplay_to: None,
+            netplay: None,
         };
         shell.boot();
         shell
@@ -580,12 +659,45 @@ impl Shell {
 
     /// Feed a game button (host already mapped keys to buttons 0..6).
     pub fn set_button(&mut self, b: usize, down: bool) {
+        // Arrows and Z/X belong to the editors while they are over the cart.
+        if self.mode != Mode::Run {
+            return;
+        }
+        if let Some(np) = &mut self.netplay {
+            if down {
+                np.buttons |= 1 << b;
+            } else {
+                np.buttons &= !(1 << b);
+            }
+            return;
+        }
         if let Some(vm) = &mut self.vm {
-            vm.state_mut().input.set_button(b, down);
+            let input = &mut vm.state_mut().input;
+            // Key repeat resends the press; only a fresh one latches.
+            if down && !input.btn(b as u32) {
+                self.debugger.button_pressed(b);
+            }
+            input.set_button(b, down);
         }
     }
 
     pub fn key(&mut self, key: Key, mods: Mods) {
+        if key == Key::Screenshot {
+            self.screenshot();
+            return;
+        }
+        if let Some(inspector) = &mut self.inspector {
+            if !inspector.key(key) {
+                self.inspector = None;
+            }
+            return;
+        }
+        if let Some(view) = &mut self.log_view {
+            if !view.key(key) {
+                self.log_view = None;
+            }
+            return;
+        }
         // The file picker, when open, captures all keys.
         if self.file_picker.is_open() {
             let files = self.project_file_names();
@@ -597,7 +709,11 @@ impl Shell {
         }
         // Global shortcuts.
         if key == Key::ToggleStats {
-            self.show_stats = !self.show_stats;
+            self.stats = self.stats.next();
+            return;
+        }
+        if key == Key::Live {
+            self.flip_live(mods.shift);
             return;
         }
         if mods.ctrl {
@@ -624,8 +740,27 @@ impl Shell {
             Mode::Run => {
                 if key == Key::Escape {
                     self.stop_run("");
+                    self.log_hint();
                 } else if key == Key::CaptureLabel {
                     self.capture_label();
+                } else if key == Key::Record {
+                    self.toggle_recording(if mods.shift { 4 } else { 2 });
+                } else if key == Key::Pause {
+                    self.debugger.pause();
+                } else if key == Key::Step {
+                    self.debugger.step();
+                } else if key == Key::Resume {
+                    self.debugger.resume();
+                } else if matches!(key, Key::Slower | Key::Faster) && self.netplay.is_some() {
+                    self.toast("Netplay runs at 1x", col::ORANGE, 1.5);
+                } else if key == Key::Slower {
+                    self.set_speed(self.speed.speed().slower());
+                } else if key == Key::Faster {
+                    self.set_speed(self.speed.speed().faster());
+                } else if key == Key::Tab && self.debugger.is_paused() {
+                    self.inspector = self.vm.as_ref().map(|vm| {
+                        MemoryInspector::new(vm.memory().to_vec(), vm.memory_map().clone())
+                    });
                 }
             }
             Mode::Console => self.console_key(key, mods),
@@ -635,7 +770,12 @@ impl Shell {
 
     fn editor_key(&mut self, key: Key, mods: Mods) {
         if key == Key::Escape {
-            self.mode = Mode::Console;
+            // Back to the cart the editors were opened over, if any.
+            self.mode = if self.vm.is_some() {
+                Mode::Run
+            } else {
+                Mode::Console
+            };
             return;
         }
         // Alt+Left/Right cycles editor tabs.
@@ -690,7 +830,7 @@ impl Shell {
         if self.loaded_none() {
             return;
         }
-        let audio = self.audio.clone();
+        let audio = self.preview.clone();
         match self.mode {
             Mode::Code => {
                 let mut code = self.code().unwrap_or_default().to_string();
@@ -807,7 +947,17 @@ impl Shell {
                     self.switch_editor(self.last_editor);
                 }
             }
-            Key::Tab | Key::CaptureLabel | Key::ToggleStats => {}
+            Key::Tab => self.open_error_location(),
+            Key::CaptureLabel
+            | Key::Screenshot
+            | Key::Record
+            | Key::ToggleStats
+            | Key::Pause
+            | Key::Step
+            | Key::Resume
+            | Key::Slower
+            | Key::Faster
+            | Key::Live => {}
         }
     }
 
@@ -840,6 +990,7 @@ impl Shell {
                 self.cmd_run();
                 Ok(())
             }
+            "netplay" => self.cmd_netplay(args),
             "export" => self.cmd_export(args),
             "import" => self.cmd_import(args),
             "import-pico8" | "importp8" => self.cmd_import_pico8(args),
@@ -878,9 +1029,13 @@ impl Shell {
                 self.cmd_keys();
                 Ok(())
             }
+            "mem" => self.cmd_mem(args),
+            "log" | "logs" => self.cmd_log(args),
+            "savelog" => self.cmd_savelog(args),
             "reboot" => {
                 self.vm = None;
                 self.audio.stop_all();
+                self.preview.stop_all();
                 self.loaded = Loaded::None;
                 self.lines.clear();
                 self.boot();
@@ -908,7 +1063,9 @@ impl Shell {
             ("reload", "Re-read from disk, drop edits"),
             ("save", "Save project to disk"),
             ("run", "Build + run (esc stops)"),
+            ("netplay <1|2> <me> <peer>", "Run against a peer"),
             ("export <f.png|f.html>", "Export cart (PNG or web)"),
+            ("export <f.wav> [-sfx n]", "Render song (or sfx) to WAV"),
             ("import <f.png> <dir>", "Cart -> project"),
             ("import-pico8 <f> [dir]", "PICO-8 cart -> new project"),
             (
@@ -916,6 +1073,9 @@ impl Shell {
                 "Append assets to loaded project",
             ),
             ("info", "Cart metadata"),
+            ("mem [addr]", "Browse a trapped cart's memory"),
+            ("log [level]", "View the last run's log"),
+            ("savelog <file>", "Write that log to a file"),
             ("title/author <text>", "Set metadata"),
             ("code/sprite/map/sfx/music", "Editors (esc)"),
             ("ls, cls, keys, reboot, exit", ""),
@@ -930,18 +1090,80 @@ impl Shell {
     fn cmd_keys(&mut self) {
         for (k, d) in [
             ("esc", "Console <-> editor / stop"),
+            ("tab", "Go to the panic (console)"),
             ("ctrl+r", "Run cart"),
             ("ctrl+s", "Save + build check"),
             ("ctrl+z / ctrl+y", "Undo / redo (in editors)"),
             ("alt+left/right", "Switch editor"),
             ("arrows + z/x", "Game buttons"),
-            ("f1", "Toggle resource stats"),
+            ("f1", "Stats: numbers/graphs/off"),
             ("f6", "Capture label (running)"),
+            ("f7", "Screenshot"),
+            ("f8 / shift+f8", "Record gif 2x / 4x (running)"),
+            ("f9 / f10 / f5", "Pause / step / resume (running)"),
+            ("f2 / f3", "Slower / faster (running)"),
+            ("f4", "Edit while running / back"),
+            ("shift+f4", "Reset the cart's sheet + map"),
+            ("tab (paused)", "Browse the cart's memory"),
         ] {
             self.say(&format!("{k:14} {d}"), col::LIGHT_GREY);
         }
     }
 
+    /// Open the memory inspector on the last cart that trapped, at the hex
+    /// address given, if any.
+    fn cmd_mem(&mut self, args: &[&str]) -> Result<()> {
+        let Some(trapped) = &self.trapped_memory else {
+            bail!("No trapped cart to inspect");
+        };
+        let mut inspector = trapped.clone();
+        if let Some(addr) = args.first() {
+            let hex = addr.trim_start_matches("0x");
+            let addr =
+                usize::from_str_radix(hex, 16).map_err(|_| anyhow!("Not a hex address: {addr}"))?;
+            inspector.scroll_to(addr);
+        }
+        self.inspector = Some(inspector);
+        Ok(())
+    }
+
+    /// Open the log viewer on the last run's log, showing `level` and up
+    /// (everything by default).
+    fn cmd_log(&mut self, args: &[&str]) -> Result<()> {
+        let min = match args.first() {
+            Some(name) => LogLevel::parse(name)
+                .ok_or_else(|| anyhow!("Unknown level: {name}\nUse debug/info/warn/error"))?,
+            None => LogLevel::Debug,
+        };
+        if self.run_log.is_empty() {
+            bail!("No log lines yet");
+        }
+        self.log_view = Some(LogView::new(self.run_log.iter().cloned().collect(), min));
+        Ok(())
+    }
+
+    /// Write the last run's log, every level, one `frame level text` line each.
+    fn cmd_savelog(&mut self, args: &[&str]) -> Result<()> {
+        let Some(file) = args.first() else {
+            bail!("Usage: savelog <file>");
+        };
+        let text: String = self.run_log.iter().map(|l| format!("{l}\n")).collect();
+        std::fs::write(self.cwd.join(file), text).map_err(|e| anyhow!("Writing {file}: {e}"))?;
+        self.say(
+            &format!("Saved {file} ({} lines)", self.run_log.len()),
+            col::GREEN,
+        );
+        Ok(())
+    }
+
+    /// After a run, point at its log if it wrote one.
+    fn log_hint(&mut self) {
+        if !self.run_log.is_empty() {
+            let n = self.run_log.len();
+            self.say(&format!("{n} log lines: log to view"), col::LIGHT_GREY);
+        }
+    }
+
     fn cmd_new(&mut self, args: &[&str]) -> Result<()> {
         let Some(name) = args.first() else {
             bail!("Usage: new <name>");
@@ -1171,7 +1393,11 @@ impl Shell {
 
     pub fn cmd_run(&mut self) {
         self.audio.stop_all();
+        self.preview.stop_all();
         self.vm = None;
+        self.netplay = None;
+        self.error_location = None;
+        self.run_log.clear();
         match &self.loaded {
             Loaded::None => self.say("No cart loaded", col::RED),
             Loaded::Cart { .. } => match self.start_vm_from_loaded() {
@@ -1201,6 +1427,27 @@ impl Shell {
         }
     }
 
+    /// `netplay <1|2> <bind> <peer>`: run the cart as that player of a
+    /// two-player session, against a `rico8-player --netplay` or another
+    /// console at `peer`.
+    fn cmd_netplay(&mut self, args: &[&str]) -> Result<()> {
+        let [player, bind, peer] = args else {
+            bail!("Usage: netplay <1|2> <bind> <peer>");
+        };
+        let player = match player.parse() {
+            Ok(p @ (1 | 2)) => p,
+            _ => bail!("Player must be 1 or 2"),
+        };
+        self.netplay_to = Some((player, bind.to_string(), peer.to_string()));
+        self.cmd_run();
+        // A project boots once its build is done; anything else has booted,
+        // or failed to, by now.
+        if !self.run_after_build {
+            self.netplay_to = None;
+        }
+        Ok(())
+    }
+
     /// Reconcile a project's disk and in-memory copies in preparation for a
     /// build. Adopts clean external changes, flushes in-console edits to disk,
     /// and returns `false` (build should abort) on an unresolved conflict.
@@ -1298,7 +1545,22 @@ impl Shell {
             }
         };
         let vm = GameVm::load(&wasm, &assets, self.audio.clone())?;
+        // Free the last session's port before binding the next.
+        self.netplay = None;
+        if let Some((player, bind, peer)) = self.netplay_to.take() {
+            let session = Session::new(player, &*bind, &*peer, &wasm)?;
+            let me = session.local_addr()?;
+            self.say(&format!("Netplay: P{player} on {me}"), col::GREEN);
+            self.say(&format!("Peer {peer}"), col::LIGHT_GREY);
+            self.netplay = Some(Netplay {
+                session,
+                buttons: 0,
+            });
+        }
         self.vm = Some(vm);
+        self.live = Some(LiveAssets::new(&assets));
+        self.debugger.reset();
+        self.trapped_memory = None;
         self.mode = Mode::Run;
         Ok(())
     }
@@ -1306,16 +1568,31 @@ impl Shell {
     fn cmd_export(&mut self, args: &[&str]) -> Result<()> {
         let mut include_source = true;
         let mut file = None;
-        for a in args {
-            if *a == "-nosrc" {
-                include_source = false;
-            } else {
-                file = Some(*a);
+        let mut sfx = None;
+        let mut music = None;
+        let mut it = args.iter();
+        while let Some(&a) = it.next() {
+            match a {
+                "-nosrc" => include_source = false,
+                "-sfx" | "-music" => {
+                    let Some(n) = it.next().and_then(|n| n.parse().ok()) else {
+                        bail!("Usage: export <f.wav> [{a} <n>]");
+                    };
+                    if a == "-sfx" {
+                        sfx = Some(n);
+                    } else {
+                        music = Some(n);
+                    }
+                }
+                _ => file = Some(a),
             }
         }
         let file = file
             .map(|f| f.to_string())
             .unwrap_or_else(|| format!("{}.png", self.cart_name()));
+        if (sfx.is_some() || music.is_some()) && !file.ends_with(".wav") {
+            bail!("-sfx and -music only apply to a .wav export");
+        }
         let out = self.cwd.join(&file);
         if file.ends_with(".html") {
             // Web export: one self-contained playable page.
@@ -1323,6 +1600,19 @@ impl Shell {
             self.say("Exporting for web...", col::LIGHT_GREY);
             let web_dir = crate::webexport::web_crate_dir(&self.sdk_path);
             crate::webexport::export_html(&cart, &out, &web_dir)?;
+        } else if file.ends_with(".wav") {
+            // Audio export: the assets alone, no build needed.
+            // Render at the oscillator quality the console is playing at.
+            let quality = self.audio.with_synth(|s| s.quality());
+            let assets = self.assets().ok_or_else(|| anyhow!("No cart loaded"))?;
+            let samples = match sfx {
+                Some(n) => wav::render_sfx(assets, n, wav::DEFAULT_SECONDS, quality)?,
+                None => {
+                    let music = music.unwrap_or(0);
+                    wav::render_music(assets, music, wav::DEFAULT_SECONDS, quality)?
+                }
+            };
+            wav::write(&out, &samples, wav::SAMPLE_RATE)?;
         } else {
             let cart = self.make_cart(include_source)?;
             cart::save_png(&cart, &out)?;
@@ -1529,15 +1819,119 @@ impl Shell {
         self.say("Label captured", col::GREEN);
     }
 
+    /// Save what is on screen — the running cart's own frame, without the
+    /// stats, speed or REC overlays, an editor or the console — into the
+    /// screenshot dir (the working dir unless `RICO8_SCREENSHOT_DIR` says
+    /// otherwise).
+    fn screenshot(&mut self) {
+        let fb = match (&self.vm, self.mode) {
+            (Some(vm), Mode::Run) => &vm.state().fb,
+            _ => &self.fb,
+        };
+        match cart::save_screenshot(fb, &cart::screenshot_dir(&self.cwd)) {
+            Ok(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let msg = format!("Saved {name}");
+                self.say(&msg, col::GREEN);
+                self.toast(&msg, col::GREEN, 2.0);
+            }
+            Err(e) => {
+                self.say(&format!("{e:#}"), col::RED);
+                self.toast("Screenshot failed", col::RED, 3.0);
+            }
+        }
+    }
+
+    fn toggle_recording(&mut self, scale: usize) {
+        if self.recording.is_some() {
+            self.finish_recording();
+            return;
+        }
+        let Some(vm) = &self.vm else { return };
+        self.recording = Some(GifRecorder::new(vm.fps(), scale));
+        self.say(
+            &format!("Recording gif at {scale}x (f8 stops)"),
+            col::LIGHT_GREY,
+        );
+    }
+
+    /// Save the GIF recording, if one is running, into the working dir.
+    fn finish_recording(&mut self) {
+        let Some(rec) = self.recording.take() else {
+            return;
+        };
+        let stem: String = self
+            .cart_name()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        match rec.save_in(&self.cwd, &stem) {
+            Ok(path) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                self.say(&format!("Saved {name} ({:.1}s)", rec.seconds()), col::GREEN);
+            }
+            Err(e) => self.say(&format!("{e:#}"), col::RED),
+        }
+    }
+
     fn stop_run(&mut self, message: &str) {
+        self.finish_recording();
+        self.debugger.reset();
+        self.set_speed(Speed::Normal);
+        self.perf.clear();
+        self.inspector = None;
         self.vm = None;
+        self.live = None;
+        self.netplay = None;
+        self.netplay_to = None;
         self.audio.stop_all();
+        self.preview.stop_all();
         self.mode = Mode::Console;
         if !message.is_empty() {
             self.say(message, col::RED);
         }
     }
 
+    /// F4: open the editors over the running cart, which keeps running
+    /// behind them with its buttons released, or go back to it. Editor changes reach
+    /// the cart as they are made. With shift, the cart gets the editors'
+    /// sheet and map whole, dropping its own `sset`/`mset` edits.
+    fn flip_live(&mut self, reset: bool) {
+        let Some(vm) = self.vm.as_mut() else {
+            return;
+        };
+        if reset {
+            if let (Some(live), Some(assets)) = (&mut self.live, assets_ref(&self.loaded)) {
+                live.reset(assets, vm.state_mut());
+            }
+            self.toast("Cart's sheet + map reset", col::GREEN, 1.5);
+            return;
+        }
+        if self.mode == Mode::Run {
+            // A button held now would stay down until the cart is back.
+            let input = &mut vm.state_mut().input;
+            for b in 0..BUTTON_COUNT {
+                input.set_button(b, false);
+            }
+            if let Some(np) = &mut self.netplay {
+                np.buttons = 0;
+            }
+            self.switch_editor(self.last_editor);
+            self.toast("F4: back to the cart", col::LIGHT_GREY, 2.0);
+        } else {
+            self.mode = Mode::Run;
+        }
+    }
+
+    /// Hand the running cart whatever the editors changed since last tick.
+    fn push_live(&mut self) {
+        if let (Some(live), Some(vm), Some(assets)) =
+            (&mut self.live, &mut self.vm, assets_ref(&self.loaded))
+        {
+            live.push(assets, vm.state_mut());
+        }
+    }
+
     fn show_error(&mut self, phase: &str, message: &str) {
         self.stop_run("");
         self.say("", col::WHITE);
@@ -1548,20 +1942,170 @@ impl Shell {
     }
 
     fn runtime_error(&mut self, e: RuntimeError) {
+        self.trapped_memory = self
+            .vm
+            .as_ref()
+            .map(|vm| MemoryInspector::new(vm.memory().to_vec(), vm.memory_map().clone()));
+        self.tail(remote::event("error", &format!("{e:#}")));
         self.show_error(e.phase, &e.message);
+        for line in e.backtrace_lines() {
+            self.say(&line, col::LIGHT_GREY);
+        }
+        let files = self.project_file_names();
+        self.error_location = e.location.filter(|at| {
+            at.project_file()
+                .is_some_and(|name| files.iter().any(|f| f == name))
+        });
+        if let Some(at) = &self.error_location {
+            let name = at.project_file().unwrap_or_default();
+            self.say(&format!("tab: open {name}:{}", at.line), col::WHITE);
+        }
+        if self.trapped_memory.is_some() {
+            self.say("mem: browse its memory", col::LIGHT_GREY);
+        }
+        self.log_hint();
+    }
+
+    /// Open the code editor on the line [`Shell::runtime_error`] remembered,
+    /// switching files first when the panic was in another one.
+    fn open_error_location(&mut self) {
+        let Some(at) = self.error_location.clone() else {
+            return;
+        };
+        let name = at.project_file().unwrap_or_default();
+        self.select_file(name);
+        if self.current_file_name() != name {
+            // `select_file` already said why.
+            return;
+        }
+        self.switch_editor(Mode::Code);
+        self.code_ed.goto(
+            (at.line as usize).saturating_sub(1),
+            (at.column as usize).saturating_sub(1),
+        );
     }
 
     // -----------------------------------------------------------------
     // Per-frame logic
     // -----------------------------------------------------------------
 
+    /// One frame of the running cart: update and draw, its logs kept for
+    /// the log viewer, a recording fed, an error shown.
+    fn run_frame(&mut self) {
+        let fps_val = self.fps_val;
+        let latched = self.debugger.take_latched();
+        let (logs, result, full) = {
+            let vm = self.vm.as_mut().unwrap();
+            let r = match &mut self.netplay {
+                // Both sides must simulate identically, so neither the
+                // measured fps nor latched buttons are fed in. `Ok(false)`
+                // is a tick spent waiting on the peer.
+                Some(np) => np.session.tick(vm, np.buttons),
+                None => {
+                    vm.state_mut().set_measured_fps(fps_val);
+                    // Latched buttons are down for this frame only.
+                    let held = vm.state().input.btn_mask();
+                    let input = &mut vm.state_mut().input;
+                    for b in 0..BUTTON_COUNT {
+                        if latched & (1 << b) != 0 {
+                            input.set_button(b, true);
+                        }
+                    }
+                    let r = vm.call_update().and_then(|()| vm.call_draw());
+                    let input = &mut vm.state_mut().input;
+                    for b in 0..BUTTON_COUNT {
+                        if latched & !held & (1 << b) != 0 {
+                            input.set_button(b, false);
+                        }
+                    }
+                    r.map(|()| true)
+                }
+            };
+            // Taken after the frame, so the lines leading up to an error
+            // make it into the log too.
+            let logs = std::mem::take(&mut vm.state_mut().logs);
+            // Record the cart's own frame, before any host overlay.
+            let full = match (&r, &mut self.recording) {
+                (Ok(true), Some(rec)) => !rec.capture(&vm.state().fb),
+                _ => false,
+            };
+            (logs, r, full)
+        };
+        if full {
+            self.finish_recording();
+        }
+        if matches!(result, Ok(true)) && self.stats != StatsMode::Off {
+            let vm = self.vm.as_mut().unwrap();
+            self.perf.push(PerfSample {
+                frame: vm.state().frame,
+                cpu_update: vm.cpu_update(),
+                cpu_draw: vm.cpu_draw(),
+                fps: self.fps_val,
+                mem: vm.mem_used_bytes() as f32 / cart::MEMORY_CAP as f32,
+            });
+        }
+        for line in logs {
+            self.debugger.log(&line);
+            self.tail(remote::log_event(&line));
+            if self.run_log.len() == RUN_LOG_LINES {
+                self.run_log.pop_front();
+            }
+            self.run_log.push_back(line);
+        }
+        if let Err(e) = result {
+            self.runtime_error(e);
+        }
+    }
+
+    /// Run the cart at `speed` from the next tick, with the synth following
+    /// it: pitched to match, or muted when unthrottled.
+    fn set_speed(&mut self, speed: Speed) {
+        self.speed.set_speed(speed);
+        self.audio.set_rate(speed.audio_rate());
+    }
+
+    /// Run this tick's frames, or the frame the debugger lets through
+    /// while paused.
+    fn advance_cart(&mut self) {
+        if self.debugger.is_paused() {
+            if self.debugger.should_run() {
+                self.run_frame();
+            }
+        } else {
+            self.run_frames();
+        }
+    }
+
+    /// Run this tick's frames at the current speed. Unthrottled runs frames
+    /// until the tick's share of wall-clock time is spent.
+    fn run_frames(&mut self) {
+        let running = |shell: &Shell| shell.vm.is_some();
+        match self.speed.tick() {
+            Some(frames) => {
+                for _ in 0..frames {
+                    if !running(self) {
+                        break;
+                    }
+                    self.run_frame();
+                }
+            }
+            None => {
+                let started = Instant::now();
+                let budget = Duration::from_secs_f32(1.0 / self.tick_fps() as f32);
+                while running(self) && started.elapsed() < budget {
+                    self.run_frame();
+                }
+            }
+        }
+    }
+
     /// The rate the host should tick at: a running cart's frame rate (30 or
-    /// 60), else 30. Running the whole Run-mode tick at the cart's rate is
-    /// what gets the display to refresh at 60 too.
+    /// 60), in front of or behind the editors, else 30. Running the whole
+    /// tick at the cart's rate is what gets the display to refresh at 60 too.
     pub fn tick_fps(&self) -> u32 {
-        match (self.mode, &self.vm) {
-            (Mode::Run, Some(vm)) => vm.fps(),
-            _ => UI_FPS,
+        match &self.vm {
+            Some(vm) => vm.fps(),
+            None => UI_FPS,
         }
     }
 
@@ -1614,31 +2158,24 @@ impl Shell {
 
         self.poll_project_watch();
         self.poll_cart_watch();
+        self.poll_remote();
         self.check_hot_reload();
+        self.push_live();
 
         match self.mode {
             Mode::Run => {
                 if self.vm.is_some() {
-                    let fps_val = self.fps_val;
-                    let (logs, result) = {
-                        let vm = self.vm.as_mut().unwrap();
-                        vm.state_mut().set_measured_fps(fps_val);
-                        let logs = std::mem::take(&mut vm.state_mut().logs);
-                        let r = vm.call_update().and_then(|()| vm.call_draw());
-                        (logs, r)
-                    };
-                    for l in logs {
-                        self.say(&l, col::LIGHT_GREY);
-                    }
-                    if let Err(e) = result {
-                        self.runtime_error(e);
-                    }
+                    self.advance_cart();
                 } else {
                     self.mode = Mode::Console;
                 }
             }
             Mode::Console => {}
             _ => {
+                // A cart behind the editors keeps playing, without input.
+                if self.vm.is_some() {
+                    self.advance_cart();
+                }
                 // Tab bar clicks work in every editor (but not while the picker is open).
                 if !self.file_picker.is_open() {
                     if let Some(target) = ui::tab_bar_click(&self.mouse) {
@@ -1646,7 +2183,7 @@ impl Shell {
                     }
                 }
                 let mouse = self.mouse;
-                let audio = self.audio.clone();
+                let audio = self.preview.clone();
                 match self.mode {
                     Mode::Code => {
                         if self.file_picker.is_open() {
@@ -1714,14 +2251,16 @@ impl Shell {
         };
         match self.wasm_mtime {
             Some(prev) if mtime > prev => {
+                // A cart running behind the editors picks the build up once
+                // it is back on screen.
+                if self.vm.is_some() && self.mode != Mode::Run {
+                    return;
+                }
                 self.wasm_mtime = Some(mtime);
                 // Only swap the running VM; in other modes the fresh wasm is
                 // simply ready for the next run.
                 if self.mode == Mode::Run {
-                    match self.start_vm_from_loaded() {
-                        Ok(()) => self.say("Hot reloaded", col::GREEN),
-                        Err(e) => self.show_error("reload", &e.to_string()),
-                    }
+                    self.hot_reload();
                 }
             }
             Some(_) => {}
@@ -1729,6 +2268,47 @@ impl Shell {
         }
     }
 
+    /// Swap the running VM for the latest build, handing over its game
+    /// state when the cart has the hooks for it.
+    fn hot_reload(&mut self) {
+        let kept = self.vm.as_mut().and_then(|vm| {
+            let state = vm.save_state()?;
+            let st = vm.state();
+            Some((state, st.sprites.clone(), st.map.clone(), st.frame))
+        });
+        if let Err(e) = self.start_vm_from_loaded() {
+            self.show_error("reload", &e.to_string());
+            return;
+        }
+        match kept.map(|kept| self.resume_hot_state(kept)) {
+            Some(true) => self.say("Hot reloaded, state kept", col::GREEN),
+            // Rejected state may have half-loaded: boot clean.
+            Some(false) => match self.start_vm_from_loaded() {
+                Ok(()) => self.say("Hot reloaded (cold boot)", col::GREEN),
+                Err(e) => self.show_error("reload", &e.to_string()),
+            },
+            None => self.say("Hot reloaded", col::GREEN),
+        }
+    }
+
+    /// Hand the previous build's saved game state to the fresh VM, along with
+    /// the sprite/map RAM edits and frame count it ran up. False when the
+    /// cart has no state hooks or refused this state (a layout change).
+    fn resume_hot_state(&mut self, kept: (Vec<u8>, SpriteSheet, MapData, u64)) -> bool {
+        let (state, sprites, map, frame) = kept;
+        let Some(vm) = self.vm.as_mut() else {
+            return false;
+        };
+        if !vm.load_state(&state) {
+            return false;
+        }
+        let st = vm.state_mut();
+        st.sprites = sprites;
+        st.map = map;
+        st.frame = frame;
+        true
+    }
+
     /// Poll project watchers and react to external edits: adopt clean changes,
     /// warn on conflicts, and kick off a rebuild (code/source) or VM reload
     /// (assets). Runs on a 30-frame cadence; skipped while a build is in flight.
@@ -1814,6 +2394,125 @@ impl Shell {
         }
     }
 
+    /// Answer the debug protocol's waiting requests, between frames.
+    fn poll_remote(&mut self) {
+        while let Some(request) = self.remote.as_ref().and_then(DebugServer::poll) {
+            match self.remote_command(&request) {
+                Ok(fields) => request.reply(&fields),
+                Err(e) => request.fail(&format!("{e:#}")),
+            }
+        }
+    }
+
+    /// Carry out one debug request; the results as JSON members.
+    fn remote_command(&mut self, request: &Request) -> Result<String> {
+        match &request.command {
+            Command::Status => {
+                let (frame, fps) = self
+                    .vm
+                    .as_ref()
+                    .map_or((0, 0), |vm| (vm.state().frame, vm.fps()));
+                return Ok(format!(
+                    "\"running\": {}, \"paused\": {}, \"frame\": {frame}, \"fps\": {fps}, \
+                     \"speed\": {}",
+                    self.running_vm().is_ok(),
+                    self.debugger.is_paused(),
+                    json_string(self.speed.speed().label())
+                ));
+            }
+            Command::Logs => {
+                self.log_tails.push(request.events());
+                return Ok(String::new());
+            }
+            Command::Reload => {
+                if self.mode == Mode::Run {
+                    self.hot_reload();
+                } else if let Err(e) = self.start_vm_from_loaded() {
+                    self.show_error("reload", &e.to_string());
+                }
+                self.running_vm()
+                    .map_err(|_| anyhow!("Reload failed, see the console"))?;
+                return Ok(String::new());
+            }
+            Command::Step { frames } => {
+                self.running_vm()?;
+                self.debugger.pause();
+                for _ in 0..*frames {
+                    if self.vm.is_none() {
+                        break;
+                    }
+                    self.run_frame();
+                }
+                let vm = self
+                    .running_vm()
+                    .map_err(|_| anyhow!("The cart stopped, see the console"))?;
+                return Ok(format!("\"frame\": {}", vm.state().frame));
+            }
+            Command::Pause => {
+                self.running_vm()?;
+                self.debugger.pause();
+                return Ok(String::new());
+            }
+            Command::Resume => {
+                self.running_vm()?;
+                self.debugger.resume();
+                return Ok(String::new());
+            }
+            _ => {}
+        }
+        let vm = self.running_vm()?;
+        match &request.command {
+            Command::Read { addr, len } => {
+                let memory = vm.memory();
+                let span = remote::span(memory.len(), *addr, (*len).min(remote::MAX_READ))?;
+                return Ok(format!("\"data\": \"{}\"", remote::to_hex(&memory[span])));
+            }
+            Command::Write { addr, data } => {
+                let memory = vm.memory_mut();
+                let span = remote::span(memory.len(), *addr, data.len() as u32)?;
+                memory[span].copy_from_slice(data);
+            }
+            Command::Screen => {
+                return Ok(format!(
+                    "\"width\": 128, \"height\": 128, \"pixels\": \"{}\"",
+                    hex_screen(&vm.state().fb)
+                ));
+            }
+            Command::Buttons { mask } => {
+                let input = &mut vm.state_mut().input;
+                for b in 0..BUTTON_COUNT {
+                    input.set_button(b, mask & (1 << b) != 0);
+                }
+            }
+            Command::Status
+            | Command::Logs
+            | Command::Reload
+            | Command::Step { .. }
+            | Command::Pause
+            | Command::Resume => {}
+        }
+        Ok(String::new())
+    }
+
+    /// The cart that is running, paused or not.
+    fn running_vm(&mut self) -> Result<&mut GameVm> {
+        match (self.mode, self.vm.as_mut()) {
+            (Mode::Run, Some(vm)) => Ok(vm),
+            _ => bail!("No cart running"),
+        }
+    }
+
+    /// Send an event to every debug connection tailing logs, dropping the
+    /// ones that hung up.
+    fn tail(&mut self, line: String) {
+        self.log_tails.retain(|t| t.send(line.clone()).is_ok());
+    }
+
+    /// Take debug protocol requests from `server` (see `remote.rs`).
+    pub fn serve_remote(&mut self, server: DebugServer) {
+        self.remote = Some(server);
+    }
+
     /// Poll a loaded PNG cart's file: on external change re-parse and adopt it
     /// (when there are no in-console asset edits), else warn about a conflict.
     fn poll_cart_watch(&mut self) {
@@ -1893,26 +2592,46 @@ impl Shell {
 
     pub fn draw(&mut self) -> &Framebuffer {
         self.meter_fps();
+        if let Some(inspector) = &self.inspector {
+            inspector.draw(&mut self.fb);
+            return &self.fb;
+        }
+        if let Some(view) = &self.log_view {
+            view.draw(&mut self.fb);
+            return &self.fb;
+        }
         match self.mode {
             Mode::Run => {
-                if self.show_stats {
-                    let fps = self.fps_val;
-                    if let Some(vm) = self.vm.as_mut() {
-                        let target = vm.fps();
-                        let cpu_u = vm.cpu_update();
-                        let cpu_d = vm.cpu_draw();
+                let Some(vm) = self.vm.as_mut() else {
+                    return &self.fb;
+                };
+                // Overlays go on a copy, so the cart's own screen — what it
+                // reads back and what F7 saves — stays as it drew it.
+                self.fb.clone_from(&vm.state().fb);
+                match self.stats {
+                    StatsMode::Numbers => {
+                        let (cpu_u, cpu_d) = (vm.cpu_update(), vm.cpu_draw());
                         let used = vm.mem_used_bytes();
-                        stats_overlay(&mut vm.state_mut().fb, cpu_u, cpu_d, used, fps, target);
+                        stats_overlay(&mut self.fb, cpu_u, cpu_d, used, self.fps_val, vm.fps());
                     }
+                    StatsMode::Graphs => self.perf.draw(&mut self.fb, 0, vm.fps()),
+                    StatsMode::Off => {}
+                }
+                if self.recording.is_some() && (self.frame / 15).is_multiple_of(2) {
+                    recording_dot_overlay(&mut self.fb);
+                }
+                if self.speed.speed() != Speed::Normal {
+                    speed_overlay(&mut self.fb, self.speed.speed());
                 }
                 if self.capture_flash > 0 {
-                    if let Some(vm) = self.vm.as_mut() {
-                        capture_flash_overlay(&mut vm.state_mut().fb);
-                    }
+                    capture_flash_overlay(&mut self.fb);
                     self.capture_flash -= 1;
                 }
-                if let Some(vm) = &self.vm {
-                    return &vm.state().fb;
+                if self.debugger.is_paused() {
+                    let fuel = (vm.fuel_update(), vm.fuel_draw());
+                    let held = vm.state().input.btn_mask();
+                    let frame = vm.state().frame;
+                    self.debugger.overlay(&mut self.fb, frame, fuel, held);
                 }
                 &self.fb
             }
@@ -1947,12 +2666,12 @@ impl Shell {
                     }
                     Mode::Sfx => {
                         if let Some(a) = assets_ref(&self.loaded) {
-                            self.sfx_ed.draw(&mut self.fb, a, &self.audio);
+                            self.sfx_ed.draw(&mut self.fb, a, &self.preview);
                         }
                     }
                     Mode::Music => {
                         if let Some(a) = assets_ref(&self.loaded) {
-                            self.music_ed.draw(&mut self.fb, a, &self.audio);
+                            self.music_ed.draw(&mut self.fb, a, &self.preview);
                         }
                     }
                     _ => {}
@@ -2103,7 +2822,7 @@ mod tests {
 
     fn test_shell() -> Shell {
         let sdk = Path::new(env!("CARGO_MANIFEST_DIR")).join("../rico8");
-        Shell::new(AudioHandle::dummy(), sdk)
+        Shell::new(AudioHandle::dummy(), AudioHandle::dummy(), sdk)
     }
 
     /// Rewrite a freshly-scaffolded project's `rico8` git dep to a path dep on the
@@ -2122,6 +2841,525 @@ mod tests {
         std::fs::write(&manifest_path, manifest).unwrap();
     }
 
+    /// Counts updates at address 0; `rico8_load_state` takes the count back
+    /// only if `$accept` is set.
+    fn stateful_cart(accept: bool) -> Vec<u8> {
+        let wat = r#"
+            (module
+              (import "rico8" "state_write" (func $save (param i32 i32)))
+              (import "rico8" "state_read" (func $load (param i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1))))
+              (func (export "rico8_draw"))
+              (func (export "rico8_save_state") (call $save (i32.const 0) (i32.const 4)))
+              (func (export "rico8_load_state") (result i32)
+                (drop (call $load (i32.const 0) (i32.const 4)))
+                (i32.const ACCEPT)))
+        "#;
+        wat::parse_str(wat.replace("ACCEPT", if accept { "1" } else { "0" })).unwrap()
+    }
+
+    #[test]
+    fn hot_reload_keeps_game_state_unless_the_cart_refuses() {
+        let dir = std::env::temp_dir().join(format!("rico8_hot_state_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut shell = test_shell();
+        let project_dir = dir.join("game");
+        Project::create(&project_dir, "game").unwrap();
+        shell
+            .cmd_load(&[project_dir.to_str().unwrap()])
+            .expect("load project");
+        let Loaded::Project(p) = &shell.loaded else {
+            unreachable!()
+        };
+        let wasm_path = p.wasm_path();
+        std::fs::create_dir_all(wasm_path.parent().unwrap()).unwrap();
+
+        let count = |shell: &mut Shell| {
+            let state = shell.vm.as_mut().unwrap().save_state().unwrap();
+            u32::from_le_bytes(state.try_into().unwrap())
+        };
+        let rebuild = |shell: &mut Shell, accept: bool| {
+            std::fs::write(&wasm_path, stateful_cart(accept)).unwrap();
+            shell.wasm_mtime = Some(std::time::UNIX_EPOCH);
+            shell.frame = 0;
+            shell.check_hot_reload();
+            match shell.lines.back() {
+                Some(ConsoleLine::Text { text, .. }) => text.clone(),
+                _ => String::new(),
+            }
+        };
+
+        std::fs::write(&wasm_path, stateful_cart(true)).unwrap();
+        shell.start_vm_from_loaded().unwrap();
+        for _ in 0..5 {
+            shell.vm.as_mut().unwrap().call_update().unwrap();
+        }
+        shell.vm.as_mut().unwrap().state_mut().map.set(3, 4, 9);
+        assert_eq!(rebuild(&mut shell, true), "Hot reloaded, state kept");
+        assert_eq!(count(&mut shell), 5);
+        assert_eq!(shell.vm.as_ref().unwrap().state().map.get(3, 4), 9);
+
+        assert_eq!(rebuild(&mut shell, false), "Hot reloaded (cold boot)");
+        assert_eq!(count(&mut shell), 0);
+        assert_eq!(shell.vm.as_ref().unwrap().state().map.get(3, 4), 0);
+
+        // Hooks that save nothing, like the SDK's defaults, boot just once.
+        let stateless = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "rico8_init")) (func (export "rico8_update"))
+                 (func (export "rico8_draw")) (func (export "rico8_save_state"))
+                 (func (export "rico8_load_state") (result i32) (i32.const 0)))"#,
+        )
+        .unwrap();
+        std::fs::write(&wasm_path, stateless).unwrap();
+        shell.start_vm_from_loaded().unwrap();
+        assert!(shell.vm.as_mut().unwrap().save_state().is_none());
+        assert_eq!(rebuild(&mut shell, true), "Hot reloaded");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paused_carts_advance_one_frame_per_step_with_latched_buttons() {
+        // Lights pixel (0, 0) while O is held.
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+              (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "rico8_init"))
+              (func (export "rico8_update"))
+              (func (export "rico8_draw")
+                (call $pset (i32.const 0) (i32.const 0)
+                  (i32.mul (call $btn (i32.const 4)) (i32.const 8)))))
+            "#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        let frame = |shell: &Shell| shell.vm.as_ref().unwrap().state().frame;
+        let lit = |shell: &Shell| shell.vm.as_ref().unwrap().state().fb.pget(0, 0) == 8;
+
+        shell.tick();
+        assert_eq!(frame(&shell), 1);
+        shell.key(Key::Pause, Mods::default());
+        shell.tick();
+        shell.tick();
+        assert_eq!(frame(&shell), 1, "paused");
+
+        // Tap O while paused: it holds for the next stepped frame only.
+        shell.set_button(4, true);
+        shell.set_button(4, false);
+        shell.key(Key::Step, Mods::default());
+        shell.tick();
+        shell.tick();
+        assert_eq!(frame(&shell), 2, "one frame per step");
+        assert!(lit(&shell));
+        shell.key(Key::Step, Mods::default());
+        shell.tick();
+        assert!(!lit(&shell));
+        let fb = shell.draw();
+        let red = (0..128).any(|x| (100..107).any(|y| fb.pget(x, y) == col::RED));
+        assert!(red, "the paused panel shows");
+        let cart = &shell.vm.as_ref().unwrap().state().fb;
+        assert!((0..128).all(|x| cart.pget(x, 100) != col::RED), "on a copy");
+
+        shell.key(Key::Resume, Mods::default());
+        shell.tick();
+        shell.tick();
+        assert_eq!(frame(&shell), 5);
+    }
+
+    #[test]
+    fn speed_keys_change_the_frames_each_tick_runs() {
+        let wasm = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "rico8_init")) (func (export "rico8_update"))
+                 (func (export "rico8_draw")))"#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        let frame = |shell: &Shell| shell.vm.as_ref().unwrap().state().frame;
+        let rate = |shell: &Shell| shell.audio.with_synth(|s| s.rate());
+
+        shell.key(Key::Faster, Mods::default());
+        shell.tick();
+        assert_eq!(frame(&shell), 2, "2x runs two frames a tick");
+        assert_eq!(rate(&shell), 2.0);
+        for _ in 0..2 {
+            shell.key(Key::Slower, Mods::default());
+        }
+        for _ in 0..4 {
+            shell.tick();
+        }
+        assert_eq!(frame(&shell), 4, "1/2x runs every other tick");
+        let fb = shell.draw();
+        let shown = (100..128).any(|x| (120..128).any(|y| fb.pget(x, y) == col::YELLOW));
+        assert!(shown, "the speed shows");
+        let cart = &shell.vm.as_ref().unwrap().state().fb;
+        let clean = (100..128).all(|x| (120..128).all(|y| cart.pget(x, y) != col::YELLOW));
+        assert!(clean, "F7 saves the cart's screen without it");
+
+        // Stepping a paused cart ignores the speed.
+        shell.key(Key::Pause, Mods::default());
+        shell.key(Key::Step, Mods::default());
+        shell.tick();
+        assert_eq!(frame(&shell), 5);
+
+        shell.stop_run("");
+        assert_eq!(shell.speed.speed(), Speed::Normal, "each run starts at 1x");
+        assert_eq!(rate(&shell), 1.0);
+    }
+
+    #[test]
+    fn netplay_plays_a_player_session_over_loopback() {
+        // Each player's Right lights their own pixel: P1's at (0, 0), P2's
+        // at (1, 0).
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+              (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (if (call $btn (i32.const 1))
+                  (then (call $pset (i32.const 0) (i32.const 0) (i32.const 8))))
+                (if (call $btn (i32.const 9))
+                  (then (call $pset (i32.const 1) (i32.const 0) (i32.const 8)))))
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut player = Session::new(2, "127.0.0.1:0", port, &wasm).unwrap();
+        let mut player_vm = GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap();
+        let mut shell = test_shell();
+        shell.loaded = Loaded::Cart {
+            cart: Cart {
+                wasm,
+                assets: Assets::default(),
+                source: None,
+            },
+            path: PathBuf::from("game.png"),
+        };
+        let peer = player.local_addr().unwrap().to_string();
+        shell.exec(&format!("netplay 1 {port} {peer}"));
+        assert_eq!(shell.mode, Mode::Run);
+
+        shell.set_button(1, true);
+        let lit = |vm: &GameVm, x: i32| vm.state().fb.pget(x, 0) == 8;
+        for _ in 0..400 {
+            shell.tick();
+            player.tick(&mut player_vm, 1 << 1).unwrap();
+            let frame = shell.netplay.as_ref().unwrap().session.rollback.frame();
+            if frame >= 30 && player.rollback.frame() >= 30 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(player.connected());
+        let vm = shell.vm.as_ref().unwrap();
+        assert!(lit(vm, 0), "the console's own buttons are P1's");
+        assert!(lit(vm, 1), "the player's buttons are P2's");
+        assert!(lit(&player_vm, 0), "P1's buttons reach the player");
+
+        shell.key(Key::Faster, Mods::default());
+        assert_eq!(shell.speed.speed(), Speed::Normal, "netplay runs at 1x");
+        shell.key(Key::Escape, Mods::default());
+        assert!(shell.netplay.is_none());
+    }
+
+    #[test]
+    fn f4_edits_reach_the_cart_running_behind_the_editors() {
+        // Puts tile 7 at (0, 0) of its map at boot.
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "set_map_tile" (func $mset (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (func (export "rico8_init")
+                (call $mset (i32.const 0) (i32.const 0) (i32.const 7)))
+              (func (export "rico8_update"))
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        shell.loaded = Loaded::Cart {
+            cart: Cart {
+                wasm,
+                assets: Assets::default(),
+                source: None,
+            },
+            path: PathBuf::from("game.png"),
+        };
+        shell.start_vm_from_loaded().unwrap();
+        let frame = |shell: &Shell| shell.vm.as_ref().unwrap().state().frame;
+        shell.tick();
+        assert_eq!(frame(&shell), 1);
+
+        shell.key(Key::Live, Mods::default());
+        assert_eq!(shell.mode, Mode::Code, "the last editor opens");
+        shell.switch_editor(Mode::Map);
+        let assets = shell.assets_mut().unwrap();
+        assets.map.set(5, 5, 3);
+        assets.sprites.set(1, 1, 9);
+        shell.tick();
+        assert_eq!(frame(&shell), 2, "the cart keeps running");
+        let st = shell.vm.as_ref().unwrap().state();
+        assert_eq!(st.map.get(5, 5), 3);
+        assert_eq!(st.sprites.get(1, 1), 9);
+        assert_eq!(st.map.get(0, 0), 7, "the cart's own mset stays");
+        shell.set_button(4, true);
+        let st = shell.vm.as_ref().unwrap().state();
+        assert!(!st.input.btn(4), "keys belong to the editors");
+
+        shell.key(Key::Escape, Mods::default());
+        assert_eq!(shell.mode, Mode::Run, "esc goes back to the cart");
+        shell.tick();
+        assert_eq!(frame(&shell), 3);
+        shell.key(
+            Key::Live,
+            Mods {
+                shift: true,
+                ..Mods::default()
+            },
+        );
+        assert_eq!(shell.mode, Mode::Run);
+        let st = shell.vm.as_ref().unwrap().state();
+        assert_eq!(st.map.get(0, 0), 0, "shift+f4 resets the map");
+        assert_eq!(st.map.get(5, 5), 3);
+    }
+
+    #[test]
+    fn editor_previews_leave_the_carts_synth_alone() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (func (export "rico8_init"))
+              (func (export "rico8_update"))
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        // Patterns 0 and 3 both play SFX 0, a single audible note.
+        let mut assets = Assets::default();
+        assets.sfx[0].notes[0].volume = 5;
+        assets.music[0].channels[0] = Some(0);
+        assets.music[3].channels[0] = Some(0);
+        let mut shell = test_shell();
+        shell.loaded = Loaded::Cart {
+            cart: Cart {
+                wasm,
+                assets,
+                source: None,
+            },
+            path: PathBuf::from("game.png"),
+        };
+        shell.start_vm_from_loaded().unwrap();
+        shell.audio.play_music(3, 0, 0, 0);
+        shell.key(Key::Live, Mods::default());
+        shell.switch_editor(Mode::Music);
+        shell.key(Key::Char(' '), Mods::default());
+        let playing = |audio: &AudioHandle| audio.with_synth(|s| s.playing_pattern());
+        assert_eq!(playing(&shell.preview), Some(0), "the preview plays");
+        assert_eq!(playing(&shell.audio), Some(3), "the cart's music plays on");
+    }
+
+    #[test]
+    fn f1_steps_the_stats_overlay_through_numbers_and_graphs() {
+        let wasm = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "rico8_init")) (func (export "rico8_update"))
+                 (func (export "rico8_draw")))"#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        shell.tick();
+        assert!(
+            shell.perf.worst().is_none(),
+            "no samples with the overlay off"
+        );
+
+        shell.key(Key::ToggleStats, Mods::default());
+        shell.key(Key::ToggleStats, Mods::default());
+        assert_eq!(shell.stats, StatsMode::Graphs);
+        for _ in 0..3 {
+            shell.tick();
+        }
+        assert_eq!(shell.perf.worst().map(|w| w.fps), Some(shell.fps_val));
+        let fb = shell.draw();
+        let labels = (0..14).any(|x| (0..8).any(|y| fb.pget(x, y) == col::LIGHT_GREY));
+        assert!(labels, "the graph rows are labelled");
+
+        shell.key(Key::ToggleStats, Mods::default());
+        assert_eq!(shell.stats, StatsMode::Off);
+        shell.stop_run("");
+        assert!(
+            shell.perf.worst().is_none(),
+            "each run starts a new history"
+        );
+    }
+
+    #[test]
+    fn mem_browses_the_memory_of_a_trapped_cart() {
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (data (i32.const 16) "boom")
+              (func (export "rico8_init"))
+              (func (export "rico8_update") unreachable)
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        assert!(shell.cmd_mem(&[]).is_err(), "nothing trapped yet");
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        shell.tick();
+        assert_eq!(shell.mode, Mode::Console);
+
+        shell.exec("mem 10");
+        let fb = shell.draw();
+        // The data segment's bytes show in its green.
+        let green = (0..128).any(|x| (9..16).any(|y| fb.pget(x, y) == col::GREEN));
+        assert!(green);
+        shell.key(Key::Escape, Mods::default());
+        assert!(shell.inspector.is_none());
+        assert_eq!(shell.mode, Mode::Console);
+    }
+
+    #[test]
+    fn debug_protocol_drives_a_running_cart() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpStream;
+
+        /// The next line from the connection, answering requests meanwhile.
+        fn next_line(shell: &mut Shell, reader: &mut impl BufRead) -> String {
+            let mut line = String::new();
+            loop {
+                shell.poll_remote();
+                if reader.read_line(&mut line).is_ok() && line.ends_with('\n') {
+                    return line.trim_end().to_string();
+                }
+            }
+        }
+
+        // Logs "hi" each frame X is held and draws the color stored at 16.
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+              (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+              (import "rico8" "log" (func $log (param i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "hi")
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (if (call $btn (i32.const 5))
+                  (then (call $log (i32.const 0) (i32.const 2)))))
+              (func (export "rico8_draw")
+                (call $pset (i32.const 0) (i32.const 0) (i32.load8_u (i32.const 16)))))
+            "#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        let server = DebugServer::bind(0).unwrap();
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(5)))
+            .unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        shell.serve_remote(server);
+
+        writeln!(stream, r#"{{"cmd": "pause"}}"#).unwrap();
+        let line = next_line(&mut shell, &mut reader);
+        assert_eq!(line, r#"{"ok": false, "error": "No cart running"}"#);
+
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        for request in [
+            r#"{"cmd": "logs"}"#,
+            r#"{"cmd": "write", "addr": 16, "data": "08"}"#,
+            r#"{"cmd": "buttons", "mask": 32}"#,
+        ] {
+            writeln!(stream, "{request}").unwrap();
+            assert_eq!(next_line(&mut shell, &mut reader), r#"{"ok": true}"#);
+        }
+        writeln!(stream, r#"{{"id": 3, "cmd": "step", "frames": 2}}"#).unwrap();
+        for _ in 0..2 {
+            let line = next_line(&mut shell, &mut reader);
+            assert!(line.starts_with(r#"{"event": "log", "level": "info", "frame": "#));
+            assert!(line.ends_with(r#""text": "hi"}"#), "{line}");
+        }
+        let line = next_line(&mut shell, &mut reader);
+        assert_eq!(line, r#"{"id": 3, "ok": true, "frame": 2}"#);
+        assert!(shell.debugger.is_paused());
+
+        writeln!(stream, r#"{{"cmd": "read", "addr": 0, "len": 2}}"#).unwrap();
+        let line = next_line(&mut shell, &mut reader);
+        assert_eq!(line, r#"{"ok": true, "data": "6869"}"#);
+        writeln!(stream, r#"{{"cmd": "screen"}}"#).unwrap();
+        let line = next_line(&mut shell, &mut reader);
+        assert!(line.contains(r#""pixels": "80"#), "{}", &line[..60]);
+    }
+
+    #[test]
+    fn run_logs_open_in_the_viewer_and_save_to_a_file() {
+        let dir = std::env::temp_dir().join(format!("rico8_savelog_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Warns "hp" every frame.
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "log_level" (func $log (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "hp")
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (call $log (i32.const 2) (i32.const 0) (i32.const 2)))
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        let mut shell = test_shell();
+        shell.cwd = dir.clone();
+        shell.exec("log");
+        assert!(shell.log_view.is_none(), "nothing logged yet");
+
+        shell.vm = Some(GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap());
+        shell.mode = Mode::Run;
+        shell.run_frame();
+        shell.run_frame();
+        shell.key(Key::Escape, Mods::default());
+        shell.exec("log warn");
+        assert!(shell.log_view.is_some());
+        shell.key(Key::Escape, Mods::default());
+        assert!(shell.log_view.is_none());
+
+        shell.exec("savelog run.log");
+        let saved = std::fs::read_to_string(dir.join("run.log")).unwrap();
+        assert_eq!(saved.lines().count(), 2);
+        assert!(saved.lines().all(|l| l.ends_with(" warn  hp")), "{saved}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn window_title_reflects_loaded_cart() {
         let dir = std::env::temp_dir().join(format!("rico8_title_{}", std::process::id()));
@@ -2584,6 +3822,45 @@ mod tests {
         std::fs::remove_dir_all(&dir).unwrap();
     }
 
+    #[test]
+    fn tab_opens_the_file_a_cart_panicked_in() {
+        let dir = std::env::temp_dir().join(format!("rico8_panic_at_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut shell = test_shell();
+        let project_dir = dir.join("game");
+        Project::create(&project_dir, "game").unwrap();
+        std::fs::write(project_dir.join("src/enemy.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        shell
+            .cmd_load(&[project_dir.to_str().unwrap()])
+            .expect("load project");
+        let error = |file: &str| RuntimeError {
+            phase: "update",
+            message: "boom".into(),
+            backtrace: Vec::new(),
+            location: Some(PanicLocation {
+                file: file.into(),
+                line: 2,
+                column: 4,
+            }),
+        };
+
+        // Not a file of this project: nothing to offer.
+        shell.runtime_error(error("/rust/lib/core/src/option.rs"));
+        shell.key(Key::Tab, Mods::default());
+        assert_eq!(shell.mode, Mode::Console);
+
+        shell.runtime_error(error("src/enemy.rs"));
+        let Some(ConsoleLine::Text { text, .. }) = shell.lines.back() else {
+            unreachable!()
+        };
+        assert_eq!(text, "tab: open enemy.rs:2");
+        shell.key(Key::Tab, Mods::default());
+        assert_eq!(shell.mode, Mode::Code);
+        assert_eq!(shell.current_file_name(), "enemy.rs");
+        assert_eq!(shell.code().unwrap(), "fn a() {}\nfn b() {}\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn clicking_the_filename_opens_the_picker() {
         let dir = std::env::temp_dir().join(format!("rico8_fnclick_{}", std::process::id()));
@@ -2787,4 +4064,52 @@ mod tests {
 
         std::fs::remove_dir_all(&dir).unwrap();
     }
+
+    #[test]
+    fn export_wav_renders_the_song_without_a_build() {
+        let dir = std::env::temp_dir().join(format!("rico8_export_wav_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut shell = test_shell();
+        shell.cwd = dir.clone();
+        shell.cmd_new(&["tune"]).expect("new");
+        let assets = shell.assets_mut().expect("loaded");
+        assets.sfx[2].notes[0].volume = 5;
+        assets.music[0].channels[1] = Some(2);
+        assets.music[0].stop_at_end = true;
+
+        shell.exec("export song.wav");
+        let bytes = std::fs::read(dir.join("song.wav")).expect("wav written");
+        assert_eq!(&bytes[8..12], b"WAVE");
+
+        shell.exec("export fx.wav -sfx 9");
+        assert!(!dir.join("fx.wav").exists(), "empty sfx is refused");
+
+        let err = shell.cmd_export(&["tune.html", "-music", "0"]).unwrap_err();
+        assert!(err.to_string().contains(".wav"), "{err}");
+        assert!(!dir.join("tune.html").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn f7_saves_a_screenshot_from_an_editor() {
+        let dir = std::env::temp_dir().join(format!("rico8_shell_shot_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut shell = test_shell();
+        shell.cwd = dir.clone();
+        shell.cmd_new(&["shots"]).expect("new");
+        shell.switch_editor(Mode::Sprite);
+        shell.tick();
+        shell.draw();
+        shell.key(Key::Screenshot, Mods::default());
+        let shots: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("rico8_"))
+            .collect();
+        assert_eq!(shots.len(), 1, "one screenshot written");
+        assert_eq!(shell.mode, Mode::Sprite, "the editor stays open");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -77,7 +77,7 @@ fn escape_html(s: &str) -> String {
 }
 
 /// Plain standard base64; small enough to not warrant a dependency.
-fn base64(data: &[u8]) -> String {
+pub(crate) fn base64(data: &[u8]) -> String {
     const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
     for chunk in data.chunks(3) {
@@ -211,6 +211,12 @@ let wasm = null;
 let audioCtx = null;
 let audioTime = 0;
 let last = 0, acc = 0;
+let errorLogged = false;
+
+function errorText() {
+  return new TextDecoder().decode(new Uint8Array(
+    wasm.memory.buffer, wasm.rico8_web_error_ptr(), wasm.rico8_web_error_len()));
+}
 
 async function boot() {
   document.getElementById("boot").style.display = "none";
@@ -224,9 +230,7 @@ async function boot() {
   const ptr = wasm.rico8_web_upload_begin(cart.length);
   new Uint8Array(wasm.memory.buffer, ptr, cart.length).set(cart);
   if (wasm.rico8_web_load() !== 0) {
-    const msg = new TextDecoder().decode(new Uint8Array(
-      wasm.memory.buffer, wasm.rico8_web_error_ptr(), wasm.rico8_web_error_len()));
-    document.getElementById("title").textContent = "cart error: " + msg;
+    document.getElementById("title").textContent = "cart error: " + errorText();
     return;
   }
   fps = wasm.rico8_web_fps();
@@ -318,7 +322,12 @@ function frame(now) {
   last = now;
   const step = 1000 / fps;
   while (acc >= step) {
-    wasm.rico8_web_tick();
+    // The screen shows the top of a crash; the full backtrace goes to the
+    // browser console, once.
+    if (wasm.rico8_web_tick() !== 0 && !errorLogged) {
+      errorLogged = true;
+      console.error(errorText());
+    }
     acc -= step;
   }
   const ptr = wasm.rico8_web_fb_ptr();
@@ -8,9 +8,12 @@ use crate::{
 use anyhow::Result;
 use rico8_runtime::{
     audio::AudioHandle,
-    cart,
+    cart::{self, GifRecorder},
     fb::{Framebuffer, HEIGHT},
+    netplay::Session,
     palette::col,
+    perf::{PerfHistory, PerfSample, StatsMode},
+    speed::{Speed, SpeedClock},
     ui,
     vm::{GameVm, UI_FPS},
 };
@@ -20,10 +23,15 @@ use std::{
 };
 
 /// Folds per-frame input snapshots into high-level actions: the universal hold-O+X exit,
-/// Select/Start handling, and the fps toggle. Shared by every backend, tested headless.
+/// Select/Start handling, the fps and recording toggles, speed changes and screenshots. Shared by
+/// every backend, tested headless.
 #[derive(Default)]
 pub struct Controls {
     combo_frames: u32,
+    /// Start+X was down last frame, so the screenshot combo fires once per press.
+    shot_combo: bool,
+    /// Start+Left / Start+Right were down last frame, so the speed combos step once per press.
+    speed_combo: [bool; 2],
 }
 
 /// What `Controls` decided this frame.
@@ -32,6 +40,10 @@ pub enum ControlAction {
     BackToPicker,
     Quit,
     ToggleFps,
+    ToggleRecord,
+    Screenshot,
+    Slower,
+    Faster,
 }
 
 impl Controls {
@@ -49,6 +61,27 @@ impl Controls {
         if snap.fps_toggle {
             return ControlAction::ToggleFps;
         }
+        if snap.record_toggle {
+            return ControlAction::ToggleRecord;
+        }
+        // Start+X is the pad screenshot combo (for devices without a keyboard).
+        let combo = snap.start && snap.buttons[5];
+        let combo_edge = combo && !self.shot_combo;
+        self.shot_combo = combo;
+        if snap.screenshot || combo_edge {
+            return ControlAction::Screenshot;
+        }
+        // Start+Left / Start+Right step the speed on pads, like F2 / F3.
+        let slower = snap.start && snap.buttons[0];
+        let faster = snap.start && snap.buttons[1];
+        let [was_slower, was_faster] = self.speed_combo;
+        self.speed_combo = [slower, faster];
+        if snap.slower || (slower && !was_slower) {
+            return ControlAction::Slower;
+        }
+        if snap.faster || (faster && !was_faster) {
+            return ControlAction::Faster;
+        }
         if snap.buttons[4] && snap.buttons[5] {
             self.combo_frames += 1;
             if self.combo_frames >= fps.max(1) {
@@ -60,6 +93,19 @@ impl Controls {
         }
         ControlAction::None
     }
+
+    /// The buttons the cart sees this frame: while Start is held, X and
+    /// Left/Right belong to the screenshot and speed chords rather than the
+    /// game.
+    pub fn cart_buttons(snap: &InputSnapshot) -> [bool; 6] {
+        let mut buttons = snap.buttons;
+        if snap.start {
+            for b in [0, 1, 5] {
+                buttons[b] = false;
+            }
+        }
+        buttons
+    }
 }
 
 /// What a finished game/picker loop wants to happen next.
@@ -68,11 +114,43 @@ pub enum Flow {
     BackToPicker,
 }
 
+/// `RICO8_GIF_SCALE=1..8` sets the F8 recording's zoom; unset/invalid means 2x.
+fn gif_scale_from_env() -> usize {
+    std::env::var("RICO8_GIF_SCALE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|s| (1..=8).contains(s))
+        .unwrap_or(2)
+}
+
+/// End the recording, if one is running, and write it next to the cart it came from.
+fn save_recording(recording: &mut Option<GifRecorder>, cart: &Path) {
+    let Some(rec) = recording.take() else { return };
+    let dir = cart.parent().unwrap_or(Path::new("."));
+    let stem = cart.file_stem().unwrap_or_default().to_string_lossy();
+    match rec.save_in(dir, &stem) {
+        Ok(p) => eprintln!(
+            "rico8-player: saved {} ({:.1}s)",
+            p.display(),
+            rec.seconds()
+        ),
+        Err(e) => eprintln!("rico8-player: {e:#}"),
+    }
+}
+
 /// One frame's wall-clock budget at a given logical frame rate.
 fn frame_duration(fps: u32) -> Duration {
     Duration::from_nanos(1_000_000_000 / fps.max(1) as u64)
 }
 
+/// Where to find the other player for a two-player netplay session.
+pub struct NetplayConfig {
+    /// This side's player: 1 or 2. The peer must be the other one.
+    pub player: u8,
+    pub bind: String,
+    pub peer: String,
+}
+
 pub struct App {
     platform: Box<dyn Platform>,
     /// The synth the running cart writes and the platform's audio thread reads. Held here so a
@@ -80,6 +158,8 @@ pub struct App {
     audio: AudioHandle,
     /// Run only this many frames, then exit (CI smoke mode).
     smoke: Option<u32>,
+    /// Play carts against a peer over UDP instead of alone.
+    netplay: Option<NetplayConfig>,
 }
 
 impl App {
@@ -88,9 +168,15 @@ impl App {
             platform,
             audio,
             smoke,
+            netplay: None,
         }
     }
 
+    /// Run carts as one side of a netplay session.
+    pub fn set_netplay(&mut self, config: NetplayConfig) {
+        self.netplay = Some(config);
+    }
+
     /// The cart shelf: list carts in a directory, pick one, play it.
     pub fn picker(&mut self, dir: &Path) -> Result<()> {
         loop {
@@ -118,65 +204,170 @@ impl App {
         };
         // Stop any audio from a previous cart before loading the new VM.
         self.audio.stop_all();
+        self.audio.set_rate(1.0);
         // The VM writes into the same synth the platform's audio thread reads.
         let mut vm = match GameVm::load(&cart.wasm, &cart.assets, self.audio.clone()) {
             Ok(vm) => Some(vm),
             Err(e) => return self.show_error(&format!("boot failed\n{e}")),
         };
+        let mut session = match &self.netplay {
+            Some(np) => match Session::new(np.player, &*np.bind, &*np.peer, &cart.wasm) {
+                Ok(s) => Some(s),
+                Err(e) => return self.show_error(&format!("netplay failed\n{e:#}")),
+            },
+            None => None,
+        };
         let fps = vm.as_ref().map(GameVm::fps).unwrap_or(UI_FPS);
         let frame = frame_duration(fps);
         eprintln!("rico8-player: running {}", path.display());
+        if let Some(np) = &self.netplay {
+            eprintln!(
+                "rico8-player: netplay as player {} on {}, peer {}",
+                np.player, np.bind, np.peer
+            );
+        }
 
         let mut controls = Controls::default();
         let mut error_fb: Option<Framebuffer> = None;
         let mut next = Instant::now();
         let mut frames = 0u32;
-        let mut show_fps = false;
+        let mut stats = StatsMode::Off;
+        let mut perf = PerfHistory::default();
         let mut fps_frames = 0u32;
         let mut fps_t0 = Instant::now();
         let mut fps_val = 0.0f32;
+        let mut recording: Option<GifRecorder> = None;
+        let mut clock = SpeedClock::default();
 
         loop {
             let snap = self.platform.poll();
-            match controls.update(&snap, fps) {
+            let action = controls.update(&snap, fps);
+            let buttons = Controls::cart_buttons(&snap);
+            match action {
                 ControlAction::Quit => {
                     self.audio.stop_all();
+                    save_recording(&mut recording, path);
                     return Ok(Flow::Quit);
                 }
                 ControlAction::BackToPicker => {
                     self.audio.stop_all();
+                    save_recording(&mut recording, path);
                     return Ok(Flow::BackToPicker);
                 }
-                ControlAction::ToggleFps => show_fps = !show_fps,
+                ControlAction::ToggleFps => stats = stats.next(),
+                ControlAction::Screenshot => {
+                    // The frame on screen: the last one presented.
+                    let shown = vm.as_ref().map(|v| &v.state().fb).or(error_fb.as_ref());
+                    if let Some(fb) = shown {
+                        let default = path.parent().unwrap_or(Path::new("."));
+                        match cart::save_screenshot(fb, &cart::screenshot_dir(default)) {
+                            Ok(p) => eprintln!("rico8-player: saved {}", p.display()),
+                            Err(e) => eprintln!("rico8-player: {e:#}"),
+                        }
+                    }
+                }
+                ControlAction::ToggleRecord => {
+                    if recording.is_some() {
+                        save_recording(&mut recording, path);
+                    } else if vm.is_some() {
+                        eprintln!("rico8-player: recording gif (f8 stops)");
+                        recording = Some(GifRecorder::new(fps, gif_scale_from_env()));
+                    }
+                }
+                ControlAction::Slower | ControlAction::Faster if session.is_some() => {
+                    eprintln!("rico8-player: netplay runs at 1x");
+                }
+                ControlAction::Slower | ControlAction::Faster => {
+                    let speed = if matches!(action, ControlAction::Slower) {
+                        clock.speed().slower()
+                    } else {
+                        clock.speed().faster()
+                    };
+                    clock.set_speed(speed);
+                    self.audio.set_rate(speed.audio_rate());
+                    eprintln!("rico8-player: speed {}", speed.label());
+                }
                 ControlAction::None => {}
             }
-            if let Some(v) = vm.as_mut() {
-                let input = &mut v.state_mut().input;
-                for (b, pressed) in snap.buttons.iter().enumerate() {
-                    input.set_button(b, *pressed);
+            // How many of the cart's frames this one runs: `None` runs them
+            // until the frame's time is up. Netplay stays in lockstep at 1x.
+            let due = if session.is_some() {
+                Some(1)
+            } else {
+                clock.tick()
+            };
+            let started = Instant::now();
+            let mut ran = 0u32;
+            while let Some(v) = vm.as_mut() {
+                match due {
+                    Some(n) if ran >= n => break,
+                    None if ran > 0 && started.elapsed() >= frame => break,
+                    _ => ran += 1,
                 }
-            }
-
-            if let Some(v) = vm.as_mut() {
-                v.state_mut().set_measured_fps(fps_val);
-            }
-            if let Some(v) = vm.as_mut() {
-                if let Err(e) = v.call_update().and_then(|()| v.call_draw()) {
-                    eprintln!("rico8-player: runtime error: {e}");
+                let result = match session.as_mut() {
+                    // Both sides must simulate identically, so the measured
+                    // fps (which differs per machine) is never fed in.
+                    Some(session) => {
+                        let mask = buttons
+                            .iter()
+                            .enumerate()
+                            .fold(0u8, |m, (b, &down)| m | (down as u8) << b);
+                        session.tick(v, mask).map(|_| ())
+                    }
+                    None => {
+                        let input = &mut v.state_mut().input;
+                        for (b, pressed) in buttons.iter().enumerate() {
+                            input.set_button(b, *pressed);
+                        }
+                        v.state_mut().set_measured_fps(fps_val);
+                        v.call_update().and_then(|()| v.call_draw())
+                    }
+                };
+                if let Err(e) = result {
+                    eprintln!("rico8-player: runtime error: {e:#}");
                     self.audio.stop_all();
                     let mut fb = ui::error_screen(&e.to_string());
                     fb.print("hold o+x to exit", 2, HEIGHT - 7, col::LIGHT_GREY);
                     error_fb = Some(fb);
                     vm = None;
+                    save_recording(&mut recording, path);
                 }
-            }
-            if show_fps {
-                if let Some(v) = vm.as_mut() {
-                    picker::draw_fps_overlay(&mut v.state_mut().fb, fps_val, fps);
+                // Record each of the cart's own frames, before the overlays.
+                if let (Some(v), Some(rec)) = (&vm, &mut recording) {
+                    if !rec.capture(&v.state().fb) {
+                        save_recording(&mut recording, path);
+                    }
+                }
+                // The graphs' history, kept only while an overlay is up.
+                if stats != StatsMode::Off {
+                    if let Some(v) = vm.as_mut() {
+                        perf.push(PerfSample {
+                            frame: v.state().frame,
+                            cpu_update: v.cpu_update(),
+                            cpu_draw: v.cpu_draw(),
+                            fps: fps_val,
+                            mem: v.mem_used_bytes() as f32 / cart::MEMORY_CAP as f32,
+                        });
+                    }
                 }
             }
             if let Some(v) = &vm {
-                self.platform.present(&v.state().fb)?;
+                if stats != StatsMode::Off || clock.speed() != Speed::Normal {
+                    // Drawn on a copy: the VM's screen is part of its
+                    // rollback state in netplay.
+                    let mut fb = v.state().fb.clone();
+                    match stats {
+                        StatsMode::Off => {}
+                        StatsMode::Numbers => picker::draw_fps_overlay(&mut fb, fps_val, fps),
+                        StatsMode::Graphs => picker::draw_perf_overlay(&mut fb, &perf, fps),
+                    }
+                    if clock.speed() != Speed::Normal {
+                        picker::draw_speed_overlay(&mut fb, clock.speed());
+                    }
+                    self.platform.present(&fb)?;
+                } else {
+                    self.platform.present(&v.state().fb)?;
+                }
             } else if let Some(fb) = &error_fb {
                 self.platform.present(fb)?;
             }
@@ -356,6 +547,69 @@ mod controls_tests {
         };
         assert!(matches!(c.update(&on, 60), ControlAction::ToggleFps));
     }
+
+    #[test]
+    fn start_plus_x_screenshots_once_per_press() {
+        let mut c = Controls::default();
+        let combo = InputSnapshot {
+            start: true,
+            buttons: [false, false, false, false, false, true],
+            ..Default::default()
+        };
+        assert!(matches!(c.update(&combo, 60), ControlAction::Screenshot));
+        assert!(matches!(c.update(&combo, 60), ControlAction::None));
+        assert!(matches!(
+            c.update(&snap([false; 6]), 60),
+            ControlAction::None
+        ));
+        assert!(matches!(c.update(&combo, 60), ControlAction::Screenshot));
+        assert!(!Controls::cart_buttons(&combo)[5], "the cart never sees X");
+        let f7 = InputSnapshot {
+            screenshot: true,
+            ..Default::default()
+        };
+        assert!(matches!(c.update(&f7, 60), ControlAction::Screenshot));
+    }
+
+    #[test]
+    fn start_plus_left_or_right_steps_the_speed_once_per_press() {
+        let mut c = Controls::default();
+        let start = |buttons| InputSnapshot {
+            start: true,
+            buttons,
+            ..Default::default()
+        };
+        let left = start([true, false, false, false, false, false]);
+        assert!(matches!(c.update(&left, 60), ControlAction::Slower));
+        assert!(matches!(c.update(&left, 60), ControlAction::None));
+        let right = start([false, true, false, false, false, false]);
+        assert!(matches!(c.update(&right, 60), ControlAction::Faster));
+        let both = start([true, true, true, false, false, false]);
+        assert_eq!(
+            Controls::cart_buttons(&both),
+            [false, false, true, false, false, false],
+            "only the chords' buttons are held back"
+        );
+        let f2 = InputSnapshot {
+            slower: true,
+            ..Default::default()
+        };
+        assert!(matches!(c.update(&f2, 60), ControlAction::Slower));
+    }
+
+    #[test]
+    fn record_toggle_is_an_edge() {
+        let mut c = Controls::default();
+        let on = InputSnapshot {
+            record_toggle: true,
+            ..Default::default()
+        };
+        assert!(matches!(c.update(&on, 60), ControlAction::ToggleRecord));
+        assert!(matches!(
+            c.update(&InputSnapshot::default(), 60),
+            ControlAction::None
+        ));
+    }
 }
 
 #[cfg(test)]
@@ -6,7 +6,7 @@ mod picker;
 mod platform;
 
 use anyhow::{anyhow, Result};
-use app::App;
+use app::{App, NetplayConfig};
 use platform::null::NullPlatform;
 use rico8_runtime::audio::AudioHandle;
 use std::path::PathBuf;
@@ -35,6 +35,20 @@ fn main() -> Result<()> {
         }
         _ => (None, args),
     };
+    let (netplay, args) = match args.split_first() {
+        Some((flag, rest)) if flag == "--netplay" => match rest {
+            [player, bind, peer, rest @ ..] => {
+                let config = NetplayConfig {
+                    player: player.parse()?,
+                    bind: bind.clone(),
+                    peer: peer.clone(),
+                };
+                (Some(config), rest.to_vec())
+            }
+            _ => return Err(anyhow!("--netplay <1|2> <bind-addr> <peer-addr> <cart>")),
+        },
+        _ => (None, args),
+    };
     let target = args
         .first()
         .map(PathBuf::from)
@@ -43,6 +57,12 @@ fn main() -> Result<()> {
     // The audio keepalive must outlive `app`; dropping it stops the cpal stream.
     let (platform, audio, _audio) = build_backend(smoke)?;
     let mut app = App::new(platform, audio, smoke);
+    if let Some(config) = netplay {
+        if !target.is_file() {
+            return Err(anyhow!("--netplay needs a cart file, not a directory"));
+        }
+        app.set_netplay(config);
+    }
     if target.is_file() {
         app.play(&target)?;
     } else {
@@ -66,7 +86,9 @@ fn build_backend(
             Default::default(),
         ));
     }
-    real_backend()
+    let (platform, audio, keepalive) = real_backend()?;
+    audio.set_quality(rico8_runtime::audio::OscQuality::from_env());
+    Ok((platform, audio, keepalive))
 }
 
 /// The non-smoke backend, selected at compile time by the enabled feature.
@@ -1,7 +1,13 @@
 //! The cart shelf: scanning a directory for carts and drawing the picker screen.
 
 use anyhow::{Context, Result};
-use rico8_runtime::{cart, fb::Framebuffer, palette::col};
+use rico8_runtime::{
+    cart,
+    fb::Framebuffer,
+    palette::col,
+    perf::{self, PerfHistory},
+    speed::Speed,
+};
 use std::path::{Path, PathBuf};
 
 /// All RICO-8 carts in a directory, sorted by file name.
@@ -93,6 +99,21 @@ pub fn draw_fps_overlay(fb: &mut Framebuffer, measured: f32, target: u32) {
     fb.print(&text, 1, 1, col::YELLOW);
 }
 
+/// Draw the performance graphs along the bottom: the last few seconds of
+/// CPU, fps and memory, with the worst frame marked. The bottom keeps the
+/// speed label in the top-right clear.
+pub fn draw_perf_overlay(fb: &mut Framebuffer, perf: &PerfHistory, target: u32) {
+    perf.draw(fb, 128 - perf::PANEL_H, target);
+}
+
+/// Draw the speed a cart runs at in the top-right when it is not 1x, e.g. `1/2x`.
+pub fn draw_speed_overlay(fb: &mut Framebuffer, speed: Speed) {
+    let text = speed.label();
+    let x0 = 127 - text.len() as i32 * 4 - 1;
+    fb.rectfill(x0, 0, 127, 6, col::BLACK);
+    fb.print(text, x0 + 1, 1, col::YELLOW);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,4 +152,15 @@ mod tests {
         // Second row has the selection bar (dark blue).
         assert_eq!(fb.pget(0, 29), col::DARK_BLUE);
     }
+
+    #[test]
+    fn perf_graphs_sit_along_the_bottom() {
+        let mut perf = PerfHistory::default();
+        perf.push(Default::default());
+        let mut fb = Framebuffer::new();
+        fb.cls(col::DARK_GREEN);
+        draw_perf_overlay(&mut fb, &perf, 60);
+        assert_eq!(fb.pget(64, 127 - perf::PANEL_H), col::DARK_GREEN);
+        assert_eq!(fb.pget(64, 128 - perf::PANEL_H), col::BLACK);
+    }
 }
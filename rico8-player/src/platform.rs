@@ -39,6 +39,14 @@ pub struct InputSnapshot {
     pub quit_requested: bool,
     /// The fps meter was toggled this frame (edge, F1).
     pub fps_toggle: bool,
+    /// GIF recording was toggled this frame (edge, F8).
+    pub record_toggle: bool,
+    /// A screenshot was requested this frame (edge, F7).
+    pub screenshot: bool,
+    /// Slow the cart down a step (edge, F2).
+    pub slower: bool,
+    /// Speed the cart up a step (edge, F3).
+    pub faster: bool,
 }
 
 /// Screen rotation applied during the blit, for panels mounted rotated.
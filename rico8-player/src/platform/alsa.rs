@@ -137,11 +137,7 @@ fn writer_loop(
     while !stop.load(Ordering::Relaxed) {
         // Fill from the synth; contain a panic across the pull like the old SDL callback did.
         let pulled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            audio.with_synth(|s| {
-                for o in mono.iter_mut() {
-                    *o = s.next_sample();
-                }
-            });
+            audio.render_block(&mut mono);
         }));
         if pulled.is_err() {
             mono.iter_mut().for_each(|o| *o = 0.0);
@@ -50,6 +50,10 @@ pub struct Input {
     select: bool,
     start: bool,
     fps_edge: bool,
+    record_edge: bool,
+    shot_edge: bool,
+    slower_edge: bool,
+    faster_edge: bool,
     sel_raw: Option<u16>,
     start_raw: Option<u16>,
 }
@@ -68,6 +72,10 @@ impl Input {
             select: false,
             start: false,
             fps_edge: false,
+            record_edge: false,
+            shot_edge: false,
+            slower_edge: false,
+            faster_edge: false,
             sel_raw: env_btn("RICO8_SELECT"),
             start_raw: env_btn("RICO8_START"),
         }
@@ -76,6 +84,10 @@ impl Input {
     /// Drain all pending events from every device and return a snapshot of current button state.
     pub fn poll(&mut self) -> InputSnapshot {
         self.fps_edge = false;
+        self.record_edge = false;
+        self.shot_edge = false;
+        self.slower_edge = false;
+        self.faster_edge = false;
         // Collect events first to avoid holding the &mut borrow across the match.
         let mut events = Vec::new();
         for dev in &mut self.devices {
@@ -88,6 +100,10 @@ impl Input {
         for ev in events {
             match ev.destructure() {
                 EventSummary::Key(_, KeyCode::KEY_F1, 1) => self.fps_edge = true,
+                EventSummary::Key(_, KeyCode::KEY_F2, 1) => self.slower_edge = true,
+                EventSummary::Key(_, KeyCode::KEY_F3, 1) => self.faster_edge = true,
+                EventSummary::Key(_, KeyCode::KEY_F7, 1) => self.shot_edge = true,
+                EventSummary::Key(_, KeyCode::KEY_F8, 1) => self.record_edge = true,
                 EventSummary::Key(_, code, val) if val != 2 => {
                     let down = val == 1;
                     if Some(code.code()) == self.sel_raw {
@@ -119,6 +135,10 @@ impl Input {
             start: self.start,
             quit_requested: false,
             fps_toggle: self.fps_edge,
+            record_toggle: self.record_edge,
+            screenshot: self.shot_edge,
+            slower: self.slower_edge,
+            faster: self.faster_edge,
         }
     }
 }
@@ -46,6 +46,10 @@ impl Platform for WindowPlatform {
     fn poll(&mut self) -> InputSnapshot {
         // The fps toggle is an edge, so clear it before draining this frame's events.
         self.handler.fps_edge = false;
+        self.handler.record_edge = false;
+        self.handler.shot_edge = false;
+        self.handler.slower_edge = false;
+        self.handler.faster_edge = false;
         let status = self
             .event_loop
             .pump_app_events(Some(std::time::Duration::ZERO), &mut self.handler);
@@ -58,21 +62,30 @@ impl Platform for WindowPlatform {
             start: self.handler.start,
             quit_requested: self.handler.quit,
             fps_toggle: self.handler.fps_edge,
+            record_toggle: self.handler.record_edge,
+            screenshot: self.handler.shot_edge,
+            slower: self.handler.slower_edge,
+            faster: self.handler.faster_edge,
         }
     }
 }
 
-/// Where a physical key lands: a console button (0..6), a named meta button, or the fps toggle.
+/// Where a physical key lands: a console button (0..6), a named meta button, or a toggle.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Key {
     Button(usize),
     Select,
     Start,
     Fps,
+    Record,
+    Screenshot,
+    Slower,
+    Faster,
 }
 
 /// Map a physical key to a console action. Arrows + Z/X (with C/V, N/M aliases) like the runtime;
-/// Esc backs out, Enter is Start, F1 toggles the fps meter.
+/// Esc backs out, Enter is Start, F1 toggles the fps meter, F2/F3 slow the cart down or speed it
+/// up, F7 takes a screenshot, F8 toggles GIF recording.
 pub fn map_key(code: KeyCode) -> Option<Key> {
     use Key::*;
     Some(match code {
@@ -85,6 +98,10 @@ pub fn map_key(code: KeyCode) -> Option<Key> {
         KeyCode::Escape => Select,
         KeyCode::Enter | KeyCode::NumpadEnter => Start,
         KeyCode::F1 => Fps,
+        KeyCode::F2 => Slower,
+        KeyCode::F3 => Faster,
+        KeyCode::F7 => Screenshot,
+        KeyCode::F8 => Record,
         _ => return None,
     })
 }
@@ -104,6 +121,13 @@ struct WinHandler {
     quit: bool,
     /// Set for a single frame when F1 is pressed (a rising edge, not a hold).
     fps_edge: bool,
+    /// Set for a single frame when F8 is pressed.
+    record_edge: bool,
+    /// Set for a single frame when F7 is pressed.
+    shot_edge: bool,
+    /// Set for a single frame when F2 / F3 is pressed.
+    slower_edge: bool,
+    faster_edge: bool,
 }
 
 /// The window and its softbuffer surface. The context is kept alive alongside the surface it
@@ -126,6 +150,10 @@ impl WinHandler {
             start: false,
             quit: false,
             fps_edge: false,
+            record_edge: false,
+            shot_edge: false,
+            slower_edge: false,
+            faster_edge: false,
         }
     }
 
@@ -215,12 +243,32 @@ impl ApplicationHandler for WinHandler {
                     Key::Button(i) => self.buttons[i] = pressed,
                     Key::Select => self.select = pressed,
                     Key::Start => self.start = pressed,
-                    // The fps toggle fires once on the rising edge, ignoring auto-repeat.
+                    // Toggles fire once on the rising edge, ignoring auto-repeat.
                     Key::Fps => {
                         if pressed && !event.repeat {
                             self.fps_edge = true;
                         }
                     }
+                    Key::Record => {
+                        if pressed && !event.repeat {
+                            self.record_edge = true;
+                        }
+                    }
+                    Key::Screenshot => {
+                        if pressed && !event.repeat {
+                            self.shot_edge = true;
+                        }
+                    }
+                    Key::Slower => {
+                        if pressed && !event.repeat {
+                            self.slower_edge = true;
+                        }
+                    }
+                    Key::Faster => {
+                        if pressed && !event.repeat {
+                            self.faster_edge = true;
+                        }
+                    }
                 }
             }
             _ => {}
@@ -256,6 +304,10 @@ mod tests {
         assert_eq!(map_key(KeyCode::Enter), Some(Key::Start));
         assert_eq!(map_key(KeyCode::NumpadEnter), Some(Key::Start));
         assert_eq!(map_key(KeyCode::F1), Some(Key::Fps));
+        assert_eq!(map_key(KeyCode::F2), Some(Key::Slower));
+        assert_eq!(map_key(KeyCode::F3), Some(Key::Faster));
+        assert_eq!(map_key(KeyCode::F7), Some(Key::Screenshot));
+        assert_eq!(map_key(KeyCode::F8), Some(Key::Record));
     }
 
     #[test]
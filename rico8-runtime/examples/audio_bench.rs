@@ -0,0 +1,88 @@
+//! Measure the synth's per-sample cost through `AudioHandle`, comparing one
+//! lock per sample (`next_sample`) against one lock per device buffer
+//! (`render_block`), the way the audio backends pull.
+//!
+//! Usage:
+//!   cargo run --release --no-default-features --example audio_bench -- [seconds]
+//!
+//! All four channels play a looping SFX with every filter on, the worst
+//! case the synth sees; each path renders the same audio.
+
+use rico8_runtime::{
+    assets::{MusicPattern, Note, Sfx, MUSIC_COUNT, SFX_COUNT},
+    audio::{AudioHandle, Synth},
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const SR: f32 = 44100.0;
+/// A typical device buffer (the ALSA period, a common cpal callback size).
+const BLOCK: usize = 1024;
+
+fn main() {
+    let secs: f32 = std::env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(20.0);
+    let total = (secs * SR) as usize;
+
+    let per_sample = bench(total, |audio, out| {
+        for o in out.iter_mut() {
+            *o = audio.with_synth(|s| s.next_sample());
+        }
+    });
+    let per_block = bench(total, |audio, out| audio.render_block(out));
+
+    let ns = |d: Duration| d.as_nanos() as f64 / total as f64;
+    println!("{secs}s of 4-channel audio at {SR} Hz, {BLOCK}-sample buffers:");
+    println!("  next_sample   {:7.1} ns/sample", ns(per_sample));
+    println!("  render_block  {:7.1} ns/sample", ns(per_block));
+    println!(
+        "  speedup       {:7.2}x",
+        per_sample.as_secs_f64() / per_block.as_secs_f64()
+    );
+}
+
+/// Time rendering `total` samples in `BLOCK`-sized buffers with `fill`.
+fn bench(total: usize, fill: impl Fn(&AudioHandle, &mut [f32])) -> Duration {
+    let audio = AudioHandle::new(Arc::new(Mutex::new(Synth::new(SR))));
+    audio.load(busy_sfx(), vec![MusicPattern::default(); MUSIC_COUNT]);
+    for ch in 0..4 {
+        audio.play_sfx(0, ch);
+    }
+    let mut buf = vec![0.0; BLOCK];
+    let mut sink = 0.0f32;
+    let t0 = Instant::now();
+    let mut done = 0;
+    while done < total {
+        fill(&audio, &mut buf);
+        sink += buf[0];
+        done += BLOCK;
+    }
+    let elapsed = t0.elapsed();
+    std::hint::black_box(sink);
+    elapsed
+}
+
+/// A looping SFX that cycles every waveform and effect with every filter on.
+fn busy_sfx() -> Vec<Sfx> {
+    let mut sfx = vec![Sfx::default(); SFX_COUNT];
+    for (i, note) in sfx[0].notes.iter_mut().enumerate() {
+        *note = Note {
+            pitch: 24 + (i as u8 % 24),
+            wave: (i % 8) as u8,
+            volume: 6,
+            effect: (i % 8) as u8,
+        };
+    }
+    sfx[0].speed = 8;
+    sfx[0].loop_start = 0;
+    sfx[0].loop_end = 32;
+    sfx[0].buzz = true;
+    sfx[0].detune = 1;
+    sfx[0].reverb = 1;
+    sfx[0].dampen = 1;
+    sfx
+}
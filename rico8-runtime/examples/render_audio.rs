@@ -3,14 +3,21 @@
 //!
 //! Usage:
 //!   cargo run --no-default-features --example render_audio -- <cart> <out.wav> [sfx_index|music]
+//!       [--band-limited]
 //!
 //! With `music`, the song (pattern 0 onward) is sequenced exactly as the
 //! runtime plays it — all channels of a pattern start together — which is the
 //! way to check that simultaneous tracks stay aligned. With an explicit
 //! `sfx_index` only that SFX is rendered; otherwise every non-empty SFX plays
 //! in sequence with a short gap. A manifest is printed to stderr.
+//! `--band-limited` renders with PolyBLEP oscillators instead of the naive
+//! ones, for before/after spectrum comparisons.
 
-use rico8_runtime::{assets::Assets, audio::Synth, pico8};
+use rico8_runtime::{
+    assets::Assets,
+    audio::{OscQuality, Synth},
+    pico8, wav,
+};
 use std::path::Path;
 
 const SR: f32 = 44100.0;
@@ -20,21 +27,28 @@ const GAP_SECS: f32 = 0.2;
 const MUSIC_SECS: f32 = 45.0;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let quality = match args.iter().position(|a| a == "--band-limited") {
+        Some(i) => {
+            args.remove(i);
+            OscQuality::BandLimited
+        }
+        None => OscQuality::Authentic,
+    };
     if args.len() < 3 {
-        eprintln!("usage: render_audio <cart> <out.wav> [sfx_index|music]");
+        eprintln!("usage: render_audio <cart> <out.wav> [sfx_index|music] [--band-limited]");
         std::process::exit(2);
     }
     let assets = pico8::parse_file(Path::new(&args[1])).expect("parse cart");
     let arg = args.get(3).map(String::as_str);
 
     let samples = if arg == Some("music") {
-        render_music(&assets)
+        render_music(&assets, quality)
     } else {
-        render_sfx(&assets, arg.and_then(|s| s.parse().ok()))
+        render_sfx(&assets, arg.and_then(|s| s.parse().ok()), quality)
     };
 
-    write_wav(Path::new(&args[2]), &samples, SR as u32).expect("write wav");
+    wav::write(Path::new(&args[2]), &samples, SR as u32).expect("write wav");
     eprintln!(
         "wrote {} ({:.2}s) to {}",
         samples.len(),
@@ -45,8 +59,9 @@ fn main() {
 
 /// Sequence the song the way the runtime does and capture it; logs each
 /// pattern boundary so timing can be checked.
-fn render_music(assets: &Assets) -> Vec<f32> {
+fn render_music(assets: &Assets, quality: OscQuality) -> Vec<f32> {
     let mut synth = Synth::new(SR);
+    synth.set_quality(quality);
     synth.load(assets.sfx.clone(), assets.music.clone());
     synth.play_music(0, 0, 0, 0);
     let mut samples = Vec::new();
@@ -71,7 +86,7 @@ fn render_music(assets: &Assets) -> Vec<f32> {
 }
 
 /// Render either one SFX (`only`) or every non-empty SFX back to back.
-fn render_sfx(assets: &Assets, only: Option<usize>) -> Vec<f32> {
+fn render_sfx(assets: &Assets, only: Option<usize>, quality: OscQuality) -> Vec<f32> {
     let mut samples: Vec<f32> = Vec::new();
     let gap = (GAP_SECS * SR) as usize;
     for (idx, sfx) in assets.sfx.iter().enumerate() {
@@ -93,6 +108,7 @@ fn render_sfx(assets: &Assets, only: Option<usize>) -> Vec<f32> {
             sfx.dampen
         );
         let mut synth = Synth::new(SR);
+        synth.set_quality(quality);
         synth.load(assets.sfx.clone(), assets.music.clone());
         synth.play_sfx(idx as i32, 0);
         for _ in 0..(MAX_SECS * SR) as usize {
@@ -105,28 +121,3 @@ fn render_sfx(assets: &Assets, only: Option<usize>) -> Vec<f32> {
     }
     samples
 }
-
-/// Minimal mono 16-bit PCM WAV writer (no external deps).
-fn write_wav(path: &Path, samples: &[f32], sr: u32) -> std::io::Result<()> {
-    let mut buf = Vec::with_capacity(44 + samples.len() * 2);
-    let data_len = (samples.len() * 2) as u32;
-    let byte_rate = sr * 2;
-    buf.extend_from_slice(b"RIFF");
-    buf.extend_from_slice(&(36 + data_len).to_le_bytes());
-    buf.extend_from_slice(b"WAVE");
-    buf.extend_from_slice(b"fmt ");
-    buf.extend_from_slice(&16u32.to_le_bytes());
-    buf.extend_from_slice(&1u16.to_le_bytes()); // PCM
-    buf.extend_from_slice(&1u16.to_le_bytes()); // mono
-    buf.extend_from_slice(&sr.to_le_bytes());
-    buf.extend_from_slice(&byte_rate.to_le_bytes());
-    buf.extend_from_slice(&2u16.to_le_bytes()); // block align
-    buf.extend_from_slice(&16u16.to_le_bytes()); // bits
-    buf.extend_from_slice(b"data");
-    buf.extend_from_slice(&data_len.to_le_bytes());
-    for &s in samples {
-        let v = (s.clamp(-1.0, 1.0) * 32767.0) as i16;
-        buf.extend_from_slice(&v.to_le_bytes());
-    }
-    std::fs::write(path, buf)
-}
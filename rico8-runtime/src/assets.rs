@@ -41,7 +41,7 @@ pub struct SfxId(pub u8);
 pub struct MusicId(pub u8);
 
 /// 128x128 indexed-color sprite sheet plus one flag byte per sprite.
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
 pub struct SpriteSheet {
     /// One palette index per pixel, row-major, `SHEET_W * SHEET_H` long.
     pub pixels: Vec<u8>,
@@ -49,6 +49,21 @@ pub struct SpriteSheet {
     pub flags: Vec<u8>,
 }
 
+impl Clone for SpriteSheet {
+    fn clone(&self) -> Self {
+        Self {
+            pixels: self.pixels.clone(),
+            flags: self.flags.clone(),
+        }
+    }
+
+    /// Reuses the buffers: the VM copies the sheet every frame.
+    fn clone_from(&mut self, source: &Self) {
+        self.pixels.clone_from(&source.pixels);
+        self.flags.clone_from(&source.flags);
+    }
+}
+
 impl Default for SpriteSheet {
     fn default() -> Self {
         Self {
@@ -101,11 +116,24 @@ impl SpriteSheet {
 }
 
 /// 128x64 tile map; each cell holds a sprite number (0 = empty).
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Serialize, Deserialize)]
 pub struct MapData {
     pub tiles: Vec<u8>,
 }
 
+impl Clone for MapData {
+    fn clone(&self) -> Self {
+        Self {
+            tiles: self.tiles.clone(),
+        }
+    }
+
+    /// Reuses the buffer: the VM copies the map every frame.
+    fn clone_from(&mut self, source: &Self) {
+        self.tiles.clone_from(&source.tiles);
+    }
+}
+
 impl Default for MapData {
     fn default() -> Self {
         Self {
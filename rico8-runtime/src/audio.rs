@@ -135,6 +135,71 @@ fn tonal_wave(wave: Waveform, t: f32, buzz: bool, t_phaser: f32) -> f32 {
     }
 }
 
+/// How the discontinuous waveforms (saw, square, pulse) are generated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OscQuality {
+    /// Naive waveforms, sample-exact to PICO-8: high notes alias, as they do
+    /// on the real thing.
+    #[default]
+    Authentic,
+    /// The same shapes with PolyBLEP-corrected edges, which cuts the aliasing
+    /// of high notes while leaving low ones virtually untouched.
+    BandLimited,
+}
+
+impl OscQuality {
+    /// `RICO8_AUDIO_QUALITY=bandlimited|authentic`; unset or unknown keeps
+    /// the authentic default.
+    pub fn from_env() -> OscQuality {
+        match std::env::var("RICO8_AUDIO_QUALITY").ok().as_deref() {
+            Some("bandlimited") => OscQuality::BandLimited,
+            _ => OscQuality::Authentic,
+        }
+    }
+}
+
+/// PolyBLEP residual for a unit upward step at phase 0: the correction that
+/// rounds a hard edge over the one sample either side of it. `t` is the
+/// phase in `[0, 1)` and `dt` the phase advance per sample.
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    if dt <= 0.0 {
+        0.0
+    } else if t < dt {
+        let x = t / dt;
+        x + x - x * x - 1.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt;
+        x * x + x + x + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// [`tonal_wave`] with its discontinuities band-limited. `dt` is the phase
+/// advance per sample; the continuous waveforms pass through unchanged.
+fn band_limited_wave(wave: Waveform, t: f32, buzz: bool, t_phaser: f32, dt: f32) -> f32 {
+    let naive = tonal_wave(wave, t, buzz, t_phaser);
+    match wave {
+        Waveform::Saw => {
+            // One falling edge of height 0.653 (x0.83 with buzz) at t = 0.5.
+            let height = 0.653 * if buzz { 0.83 } else { 1.0 };
+            naive - 0.5 * height * poly_blep((t + 0.5).fract(), dt)
+        }
+        Waveform::Square | Waveform::Pulse => {
+            // A rising edge at t = 0 and a falling one at the duty point, each
+            // of height 0.5.
+            let duty = match (wave, buzz) {
+                (Waveform::Square, false) => 0.5,
+                (Waveform::Square, true) => 0.4,
+                (_, false) => 0.316,
+                (_, true) => 0.255,
+            };
+            naive + 0.25 * (poly_blep(t, dt) - poly_blep((t - duty + 1.0).fract(), dt))
+        }
+        _ => naive,
+    }
+}
+
 /// One sample of a drawn waveform-instrument table at `phase` in `[0, 1)`,
 /// linearly interpolated. Samples are signed (`-16..=15`); normalized to
 /// roughly `[-1, 1)`.
@@ -170,6 +235,10 @@ struct Voice {
     prev_pitch: f32,
     /// True when this voice was started by the music sequencer.
     from_music: bool,
+    /// Stealing priority of an sfx voice (higher is kept longer).
+    priority: u8,
+    /// Start order of an sfx voice, for stealing the oldest among equals.
+    serial: u64,
     /// Noise generator state.
     noise: u32,
     noise_level: f32,
@@ -204,6 +273,8 @@ impl Voice {
             phase_b: 0.0,
             prev_pitch: first_pitch,
             from_music,
+            priority: 0,
+            serial: 0,
             noise: 0x1234_5678,
             noise_level: 0.0,
             lp: 0.0,
@@ -223,12 +294,14 @@ impl Voice {
     /// custom instrument plays through that waveform at its own pitch.
     /// `inst_drawn` carries those slots' drawn waveform tables, when any; a
     /// custom-instrument note whose slot has one plays it instead of a built-in.
+    /// `quality` picks naive or band-limited saw/square/pulse edges.
     fn sample(
         &mut self,
         dt: f32,
         total_t: f32,
         inst_waves: &[u8; 8],
         inst_drawn: &[Option<crate::assets::CustomWave>; 8],
+        quality: OscQuality,
     ) -> Option<f32> {
         if self.step >= SFX_LEN {
             return None;
@@ -296,13 +369,18 @@ impl Voice {
             }
             n
         } else {
-            let mut s = tonal_wave(wave, self.phase, self.sfx.buzz, self.phase_b);
+            let (buzz, phase_b) = (self.sfx.buzz, self.phase_b);
+            let osc = |t: f32, dphase: f32| match quality {
+                OscQuality::Authentic => tonal_wave(wave, t, buzz, phase_b),
+                OscQuality::BandLimited => band_limited_wave(wave, t, buzz, phase_b, dphase),
+            };
+            let mut s = osc(self.phase, freq * dt);
             // `detune` mixes in a second oscillator a little (or an octave)
             // off the first.
             if self.sfx.detune > 0 {
                 let ratio = if self.sfx.detune == 1 { 1.0073 } else { 2.0 };
                 self.phase2 = (self.phase2 + freq * ratio * dt).fract();
-                s = (s + tonal_wave(wave, self.phase2, self.sfx.buzz, self.phase_b)) * 0.5;
+                s = (s + osc(self.phase2, freq * ratio * dt)) * 0.5;
             }
             s
         };
@@ -356,6 +434,47 @@ impl Voice {
     }
 }
 
+/// How a music start treats a song that is already playing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MusicStart {
+    /// Refuse while a song plays (unless it is fading out).
+    Exclusive,
+    /// Fade the playing song out while the new one fades in, over the same
+    /// duration.
+    Crossfade,
+    /// Start when the playing song's current pattern ends.
+    Queue,
+}
+
+impl MusicStart {
+    /// Decode the ABI's start-mode value (`music`'s `token` on a start).
+    pub fn from_i32(v: i32) -> MusicStart {
+        match v {
+            1 => MusicStart::Crossfade,
+            2 => MusicStart::Queue,
+            _ => MusicStart::Exclusive,
+        }
+    }
+}
+
+/// A song waiting for the current pattern to end.
+#[derive(Clone, Copy)]
+struct QueuedSong {
+    pattern: usize,
+    fade_duration: i32,
+    channel_mask: i32,
+    /// The play-token it was started with; it becomes current on takeover.
+    token: i32,
+}
+
+/// A song's voices that a crossfade is retiring.
+struct Fading {
+    voices: [Option<Voice>; CHANNELS],
+    /// Gain and per-sample step; dropped when the gain reaches zero.
+    gain: f32,
+    step: f32,
+}
+
 /// Music sequencer state.
 struct MusicState {
     pattern: usize,
@@ -369,11 +488,17 @@ pub struct Synth {
     t: f32,
     sfx: Vec<Sfx>,
     music: Vec<MusicPattern>,
+    /// [`Synth::instruments`] of `sfx`, worked out once per `load`.
+    inst_waves: [u8; 8],
+    inst_drawn: [Option<crate::assets::CustomWave>; 8],
     voices: [Option<Voice>; CHANNELS],
     music_state: Option<MusicState>,
     /// Monotonic counter; each start mints the next play-token.
     token_counter: i32,
-    /// The current song's play-token (`0` when nothing is playing).
+    /// Monotonic counter stamping sfx starts, so the oldest can be stolen.
+    sfx_counter: u64,
+    /// The playing song's play-token (`0` when nothing is playing, or when a
+    /// queued song has taken its handle over).
     current_token: i32,
     /// Gain applied to music voices (`0.0`..=`1.0`), for fades.
     music_gain: f32,
@@ -385,6 +510,12 @@ pub struct Synth {
     stop_when_silent: bool,
     /// Channels reserved for music (bit i = channel i); auto-routed sfx skip them.
     reserved_channels: u8,
+    /// The song to start at the end of the current pattern.
+    queued: Option<QueuedSong>,
+    /// Earlier songs' voices while crossfades fade them out, oldest first;
+    /// they sound on top of the four channels and never advance the
+    /// sequencer.
+    outgoing: Vec<Fading>,
     /// Resampler position between `prev_internal` and `cur_internal`.
     resample_frac: f32,
     /// Previous and current internal-rate samples bracketing the output.
@@ -393,6 +524,11 @@ pub struct Synth {
     /// Two cascaded one-pole low-pass states for reconstruction filtering.
     lp1: f32,
     lp2: f32,
+    /// Naive (PICO-8-exact) or band-limited oscillators.
+    quality: OscQuality,
+    /// Playback rate: the whole synth, sequencer included, runs this many
+    /// times faster (and higher). `0.0` is silent and frozen.
+    rate: f32,
 }
 
 impl Synth {
@@ -402,28 +538,59 @@ impl Synth {
             t: 0.0,
             sfx: Vec::new(),
             music: Vec::new(),
+            inst_waves: [0; 8],
+            inst_drawn: Default::default(),
             voices: [None, None, None, None],
             music_state: None,
             token_counter: 0,
+            sfx_counter: 0,
             current_token: 0,
             music_gain: 1.0,
             music_gain_target: 1.0,
             music_gain_step: 0.0,
             stop_when_silent: false,
             reserved_channels: 0,
+            queued: None,
+            outgoing: Vec::new(),
             // Start at 1.0 so the first call renders an internal sample.
             resample_frac: 1.0,
             prev_internal: 0.0,
             cur_internal: 0.0,
             lp1: 0.0,
             lp2: 0.0,
+            quality: OscQuality::Authentic,
+            rate: 1.0,
         }
     }
 
+    /// Switch between the authentic naive oscillators and band-limited ones.
+    /// Takes effect from the next sample, including on playing voices.
+    pub fn set_quality(&mut self, quality: OscQuality) {
+        self.quality = quality;
+    }
+
+    pub fn quality(&self) -> OscQuality {
+        self.quality
+    }
+
+    /// Play everything `rate` times faster, pitched to match, so music keeps
+    /// pace with a cart run in slow motion or fast-forward. `0.0` mutes and
+    /// holds every voice where it is.
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate.max(0.0);
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
     /// Replace the audio data (called when a cart starts or assets change).
+    /// Voices keep playing, a crossfade's retiring ones included, so an
+    /// edit pushed to a running cart never cuts its music.
     pub fn load(&mut self, sfx: Vec<Sfx>, music: Vec<MusicPattern>) {
         self.sfx = sfx;
         self.music = music;
+        (self.inst_waves, self.inst_drawn) = self.instruments();
     }
 
     /// Stop all voices and the sequencer.
@@ -436,11 +603,26 @@ impl Synth {
         self.music_gain_step = 0.0;
         self.stop_when_silent = false;
         self.reserved_channels = 0;
+        self.queued = None;
+        self.outgoing.clear();
     }
 
-    /// Play SFX `n`. `channel < 0` picks a free channel (preferring ones not
-    /// used by music); `n < 0` with a valid channel stops that channel.
+    /// Play SFX `n` at priority 0. See [`Synth::play_sfx_priority`].
     pub fn play_sfx(&mut self, n: i32, channel: i32) {
+        self.play_sfx_priority(n, channel, 0);
+    }
+
+    /// Play SFX `n` at `priority` (higher is more important); `n < 0` with a
+    /// valid channel stops that channel. A valid `channel` always plays
+    /// there. `channel < 0` routes automatically, in this order:
+    ///
+    /// 1. an idle channel not reserved for music;
+    /// 2. else steal the unreserved channel whose sfx has the lowest
+    ///    priority, the oldest among equals — only if that priority is at
+    ///    most `priority`;
+    /// 3. else steal an unreserved channel playing music;
+    /// 4. else the sound is dropped. Reserved channels are never stolen.
+    pub fn play_sfx_priority(&mut self, n: i32, channel: i32, priority: u8) {
         if n < 0 {
             if (0..CHANNELS as i32).contains(&channel) {
                 self.voices[channel as usize] = None;
@@ -453,47 +635,127 @@ impl Synth {
         let ch = if (0..CHANNELS as i32).contains(&channel) {
             channel as usize
         } else {
-            // Prefer an idle non-reserved channel, then one playing a one-shot
-            // SFX, then any non-reserved channel; steal a reserved one only when
-            // every channel is reserved.
             let reserved = self.reserved_channels;
-            let free = |i: usize| reserved & (1 << i) == 0;
-            let idle = (0..CHANNELS).find(|&i| self.voices[i].is_none() && free(i));
-            let non_music = (0..CHANNELS)
-                .find(|&i| free(i) && self.voices[i].as_ref().is_some_and(|v| !v.from_music));
-            let any_free = (0..CHANNELS).rev().find(|&i| free(i));
-            idle.or(non_music).or(any_free).unwrap_or(CHANNELS - 1)
+            let free = |i: &usize| reserved & (1 << i) == 0;
+            let idle = (0..CHANNELS)
+                .filter(free)
+                .find(|&i| self.voices[i].is_none());
+            let sfx_victim = (0..CHANNELS)
+                .filter(free)
+                .filter_map(|i| self.voices[i].as_ref().map(|v| (i, v)))
+                .filter(|(_, v)| !v.from_music && v.priority <= priority)
+                .min_by_key(|(_, v)| (v.priority, v.serial))
+                .map(|(i, _)| i);
+            let music_victim = (0..CHANNELS)
+                .filter(free)
+                .find(|&i| self.voices[i].as_ref().is_some_and(|v| v.from_music));
+            let Some(ch) = idle.or(sfx_victim).or(music_victim) else {
+                return;
+            };
+            ch
         };
-        self.voices[ch] = Some(Voice::new(n as usize, sfx, false));
+        self.sfx_counter += 1;
+        let mut voice = Voice::new(n as usize, sfx, false);
+        voice.priority = priority;
+        voice.serial = self.sfx_counter;
+        self.voices[ch] = Some(voice);
     }
 
-    /// Start music at pattern `n` (mints and returns a nonzero play-token) or,
-    /// when `n < 0`, stop. A start is refused — returns `0` — while a song is
-    /// already playing and not fading out. A stop acts only when `token <= 0`
-    /// (unconditional) or `token` equals the current song's play-token.
-    /// `channel_mask` bits 0-3 mark which channels are reserved for music;
-    /// auto-routed sfx will skip those channels while music is playing.
+    /// The ABI's `music` call. With `n >= 0` it starts pattern `n` the way
+    /// `token` (decoded by [`MusicStart::from_i32`]) asks — see
+    /// [`Synth::start_music`]. With `n < 0` it stops: everything when
+    /// `token <= 0`, only the queued song when `token` is its play-token, and
+    /// the playing song when `token` is that one's.
     pub fn play_music(&mut self, n: i32, fade_duration: i32, channel_mask: i32, token: i32) -> i32 {
         if n < 0 {
-            let matches = token <= 0 || (self.current_token != 0 && token == self.current_token);
-            if matches {
+            if token <= 0 {
+                self.begin_stop(fade_duration);
+            } else if self.queued.is_some_and(|q| q.token == token) {
+                self.queued = None;
+            } else if self.current_token != 0 && token == self.current_token {
                 self.begin_stop(fade_duration);
             }
             return 0;
         }
-        // Refuse a second start only while a song is live (not already fading out).
-        if self.music_state.is_some() && !self.stop_when_silent {
-            return 0;
+        self.start_music(
+            n as usize,
+            fade_duration,
+            channel_mask,
+            MusicStart::from_i32(token),
+        )
+    }
+
+    /// Start music at pattern `n`, minting and returning a nonzero play-token.
+    /// `channel_mask` bits 0-3 mark which channels are reserved for music;
+    /// auto-routed sfx will skip those channels while music is playing.
+    ///
+    /// With nothing playing (or the song fading out) every mode starts now.
+    /// Otherwise an [`Exclusive`](MusicStart::Exclusive) start is refused —
+    /// returns `0` — while [`Crossfade`](MusicStart::Crossfade) and
+    /// [`Queue`](MusicStart::Queue) take over from the playing song, whose
+    /// token stops matching so stale stops can't cut the new one. A queued
+    /// song's own token only cancels it until it starts.
+    pub fn start_music(
+        &mut self,
+        n: usize,
+        fade_duration: i32,
+        channel_mask: i32,
+        how: MusicStart,
+    ) -> i32 {
+        let busy = self.music_state.is_some() && !self.stop_when_silent;
+        match how {
+            MusicStart::Exclusive if busy => return 0,
+            MusicStart::Queue if busy => {
+                // Waits in `advance_music`; the playing song's handle is
+                // retired right away.
+                let token = self.next_token();
+                self.queued = Some(QueuedSong {
+                    pattern: n,
+                    fade_duration,
+                    channel_mask,
+                    token,
+                });
+                self.current_token = 0;
+                return token;
+            }
+            MusicStart::Crossfade if busy => self.begin_crossfade(fade_duration),
+            _ => {}
         }
+        self.queued = None;
         self.reserved_channels = (channel_mask & 0x0F) as u8;
-        self.start_pattern(n as usize);
+        self.start_pattern(n);
         self.setup_fade_in(fade_duration);
+        self.current_token = self.next_token();
+        self.current_token
+    }
+
+    /// Mint the next play-token (never 0).
+    fn next_token(&mut self) -> i32 {
         self.token_counter = self.token_counter.wrapping_add(1);
         if self.token_counter == 0 {
             self.token_counter = 1;
         }
-        self.current_token = self.token_counter;
-        self.current_token
+        self.token_counter
+    }
+
+    /// Move the playing song's voices aside to fade out over `fade_duration`
+    /// ms (instantly when `<= 0`) and free the sequencer for the next song.
+    /// Songs an earlier crossfade is still retiring finish their own fades.
+    fn begin_crossfade(&mut self, fade_duration: i32) {
+        let gain = self.music_gain;
+        let mut voices = [None, None, None, None];
+        for (v, out) in self.voices.iter_mut().zip(&mut voices) {
+            if v.as_ref().is_some_and(|v| v.from_music) {
+                *out = v.take();
+            }
+        }
+        let step = if fade_duration <= 0 {
+            -gain
+        } else {
+            -gain / (fade_duration as f32 / 1000.0 * INTERNAL_RATE)
+        };
+        self.outgoing.push(Fading { voices, gain, step });
+        self.music_state = None;
     }
 
     /// Arm the fade-in (or instant full volume) for a freshly started song.
@@ -511,8 +773,10 @@ impl Synth {
         }
     }
 
-    /// Stop now, or ramp to silence over `fade_duration` ms then stop.
+    /// Stop now, or ramp to silence over `fade_duration` ms then stop. A
+    /// queued song is cancelled either way.
     fn begin_stop(&mut self, fade_duration: i32) {
+        self.queued = None;
         if self.music_state.is_none() {
             return;
         }
@@ -560,6 +824,13 @@ impl Synth {
         self.music_gain_step = 0.0;
         self.stop_when_silent = false;
         self.reserved_channels = 0;
+        self.queued = None;
+    }
+
+    /// The play-token of the newest song: the queued one, else the playing
+    /// one, else 0.
+    pub fn music_token(&self) -> i32 {
+        self.queued.map_or(self.current_token, |q| q.token)
     }
 
     /// Index of the playing music pattern, if any.
@@ -608,6 +879,15 @@ impl Synth {
             return;
         };
         let cur = state.pattern;
+        if let Some(q) = self.queued.take() {
+            // A queued song takes over at the pattern boundary, ahead of the
+            // current pattern's own flow flags.
+            self.reserved_channels = (q.channel_mask & 0x0F) as u8;
+            self.start_pattern(q.pattern);
+            self.setup_fade_in(q.fade_duration);
+            self.current_token = q.token;
+            return;
+        }
         let pat = self.music.get(cur).copied().unwrap_or_default();
         if pat.stop_at_end {
             self.stop_music();
@@ -630,37 +910,73 @@ impl Synth {
         }
     }
 
-    /// Render one mono sample at the device rate.
+    /// Render one mono sample at the device rate. See [`Synth::render_block`];
+    /// prefer that for whole device buffers.
+    pub fn next_sample(&mut self) -> f32 {
+        let mut out = [0.0];
+        self.render_block(&mut out);
+        out[0]
+    }
+
+    /// Fill `out` with mono samples at the device rate.
     ///
     /// The synth core runs at `INTERNAL_RATE`; this resamples up to the
     /// device rate with linear interpolation, then applies a two-pole
     /// reconstruction low-pass to suppress interpolation imaging and match
-    /// PICO-8's clean top end. Calling it N times advances device time by
-    /// `N / sample_rate` seconds.
-    pub fn next_sample(&mut self) -> f32 {
+    /// PICO-8's clean top end. Rendering N samples advances device time by
+    /// `N / sample_rate` seconds, in one call or many: the output is
+    /// identical however a stream is split into blocks. Per-block setup
+    /// (the filter coefficients) is paid once, so audio callbacks should
+    /// hand over their whole buffer.
+    pub fn render_block(&mut self, out: &mut [f32]) {
+        if self.rate == 0.0 {
+            out.fill(0.0);
+            return;
+        }
         // Internal samples consumed per output sample (< 1 when upsampling).
-        let ratio = INTERNAL_RATE / self.sample_rate;
-        self.resample_frac += ratio;
-        while self.resample_frac >= 1.0 {
-            self.prev_internal = self.cur_internal;
-            self.cur_internal = self.render_internal();
-            self.resample_frac -= 1.0;
-        }
-        let mut out =
-            self.prev_internal + (self.cur_internal - self.prev_internal) * self.resample_frac;
+        let ratio = INTERNAL_RATE * self.rate / self.sample_rate;
         // Two-pole reconstruction low-pass at ~11 kHz on the device-rate
-        // stream: lp1 filters `out`, then lp2 filters lp1.
+        // stream: lp1 filters the interpolated sample, then lp2 filters lp1.
         let fc = 11_000.0;
         let dt_dev = 1.0 / self.sample_rate;
         let alpha = dt_dev / (1.0 / (2.0 * std::f32::consts::PI * fc) + dt_dev);
-        self.lp1 += alpha * (out - self.lp1);
-        self.lp2 += alpha * (self.lp1 - self.lp2);
-        out = self.lp2;
-        out
+        // Copied out so the voices can borrow them while `self` renders.
+        let (inst_waves, inst_drawn) = (self.inst_waves, self.inst_drawn);
+        for o in out {
+            self.resample_frac += ratio;
+            while self.resample_frac >= 1.0 {
+                self.prev_internal = self.cur_internal;
+                self.cur_internal = self.render_internal(&inst_waves, &inst_drawn);
+                self.resample_frac -= 1.0;
+            }
+            let s =
+                self.prev_internal + (self.cur_internal - self.prev_internal) * self.resample_frac;
+            self.lp1 += alpha * (s - self.lp1);
+            self.lp2 += alpha * (self.lp1 - self.lp2);
+            *o = self.lp2;
+        }
+    }
+
+    /// Timbre of the eight SFX slots usable as custom instruments: each
+    /// slot's note-0 built-in waveform and its drawn waveform table (if any).
+    fn instruments(&self) -> ([u8; 8], [Option<crate::assets::CustomWave>; 8]) {
+        let mut inst_waves = [0u8; 8];
+        let mut inst_drawn: [Option<crate::assets::CustomWave>; 8] = Default::default();
+        for i in 0..8 {
+            if let Some(s) = self.sfx.get(i) {
+                inst_waves[i] = s.notes[0].wave_index();
+                inst_drawn[i] = s.custom_wave;
+            }
+        }
+        (inst_waves, inst_drawn)
     }
 
     /// Render one mono sample at the internal rate.
-    fn render_internal(&mut self) -> f32 {
+    fn render_internal(
+        &mut self,
+        inst_waves: &[u8; 8],
+        inst_drawn: &[Option<crate::assets::CustomWave>; 8],
+    ) -> f32 {
         let dt = 1.0 / INTERNAL_RATE;
         self.t += dt;
 
@@ -671,23 +987,12 @@ impl Synth {
             }
         }
 
-        // Timbre of the eight SFX slots usable as custom instruments: each
-        // slot's note-0 built-in waveform and its drawn waveform table (if any).
-        let mut inst_waves = [0u8; 8];
-        let mut inst_drawn: [Option<crate::assets::CustomWave>; 8] = Default::default();
-        for i in 0..8 {
-            if let Some(s) = self.sfx.get(i) {
-                inst_waves[i] = s.notes[0].wave_index();
-                inst_drawn[i] = s.custom_wave;
-            }
-        }
-
         let mut music_mix = 0.0;
         let mut sfx_mix = 0.0;
         for v in &mut self.voices {
             if let Some(voice) = v {
                 let from_music = voice.from_music;
-                match voice.sample(dt, self.t, &inst_waves, &inst_drawn) {
+                match voice.sample(dt, self.t, inst_waves, inst_drawn, self.quality) {
                     Some(s) => {
                         if from_music {
                             music_mix += s;
@@ -700,7 +1005,38 @@ impl Synth {
             }
         }
         self.advance_music_gain();
-        (sfx_mix + music_mix * self.music_gain).clamp(-1.0, 1.0)
+        let outgoing = self.render_outgoing(dt, inst_waves, inst_drawn);
+        (sfx_mix + music_mix * self.music_gain + outgoing).clamp(-1.0, 1.0)
+    }
+
+    /// Mix and fade the voices crossfades are retiring; drops each song's
+    /// at silence.
+    fn render_outgoing(
+        &mut self,
+        dt: f32,
+        inst_waves: &[u8; 8],
+        inst_drawn: &[Option<crate::assets::CustomWave>; 8],
+    ) -> f32 {
+        let (t, quality) = (self.t, self.quality);
+        let mut mix = 0.0;
+        self.outgoing.retain_mut(|fading| {
+            fading.gain += fading.step;
+            if fading.gain <= 0.0 {
+                return false;
+            }
+            let mut song = 0.0;
+            for v in &mut fading.voices {
+                if let Some(voice) = v {
+                    match voice.sample(dt, t, inst_waves, inst_drawn, quality) {
+                        Some(s) => song += s,
+                        None => *v = None,
+                    }
+                }
+            }
+            mix += song * fading.gain;
+            fading.voices.iter().any(Option::is_some)
+        });
+        mix
     }
 
     /// Which SFX index is playing on each channel (for editor UI).
@@ -753,6 +1089,10 @@ impl AudioHandle {
         self.with_synth(|s| s.play_sfx(n, channel));
     }
 
+    pub fn play_sfx_priority(&self, n: i32, channel: i32, priority: u8) {
+        self.with_synth(|s| s.play_sfx_priority(n, channel, priority));
+    }
+
     /// The step each channel's voice is sounding (for editor playheads).
     pub fn channel_step(&self) -> [Option<usize>; CHANNELS] {
         self.with_synth(|s| s.channel_step())
@@ -766,16 +1106,34 @@ impl AudioHandle {
         self.with_synth(|s| s.stop_all());
     }
 
+    pub fn set_rate(&self, rate: f32) {
+        self.with_synth(|s| s.set_rate(rate));
+    }
+
+    pub fn set_quality(&self, quality: OscQuality) {
+        self.with_synth(|s| s.set_quality(quality));
+    }
+
+    /// Fill `out` from the synth under a single lock.
+    pub fn render_block(&self, out: &mut [f32]) {
+        self.with_synth(|s| s.render_block(out));
+    }
+
     pub fn load(&self, sfx: Vec<Sfx>, music: Vec<MusicPattern>) {
         self.with_synth(|s| s.load(sfx, music));
     }
 }
 
 /// Real audio output via cpal. Owns the stream; dropping it stops audio.
+///
+/// Two synths are mixed into the stream: the one a cart plays through, and
+/// one the editors preview on, so auditioning a sound never touches the
+/// slots or music of a cart running behind them.
 #[cfg(feature = "audio")]
 pub struct AudioOutput {
     _stream: cpal::Stream,
     handle: AudioHandle,
+    preview: AudioHandle,
 }
 
 #[cfg(feature = "audio")]
@@ -789,17 +1147,24 @@ impl AudioOutput {
         let config = device.default_output_config().ok()?;
         let sample_rate = config.sample_rate() as f32;
         let channels = config.channels() as usize;
-        let synth = Arc::new(Mutex::new(Synth::new(sample_rate)));
-        let cb_synth = synth.clone();
+        let handle = AudioHandle::new(Arc::new(Mutex::new(Synth::new(sample_rate))));
+        let preview = AudioHandle::new(Arc::new(Mutex::new(Synth::new(sample_rate))));
+        let (cb_handle, cb_preview) = (handle.clone(), preview.clone());
+        // Mono scratch buffers, grown to the largest callback seen and reused.
+        let mut mono = Vec::new();
+        let mut extra = Vec::new();
         let stream = device
             .build_output_stream(
                 config.into(),
                 move |data: &mut [f32], _| {
-                    let mut synth = cb_synth.lock().unwrap();
-                    for frame in data.chunks_mut(channels) {
-                        let s = synth.next_sample();
+                    let frames = data.len() / channels.max(1);
+                    mono.resize(frames, 0.0);
+                    extra.resize(frames, 0.0);
+                    cb_handle.render_block(&mut mono);
+                    cb_preview.render_block(&mut extra);
+                    for ((frame, &s), &p) in data.chunks_mut(channels).zip(&mono).zip(&extra) {
                         for out in frame {
-                            *out = s;
+                            *out = (s + p).clamp(-1.0, 1.0);
                         }
                     }
                 },
@@ -810,13 +1175,19 @@ impl AudioOutput {
         stream.play().ok()?;
         Some(Self {
             _stream: stream,
-            handle: AudioHandle::new(synth),
+            handle,
+            preview,
         })
     }
 
     pub fn handle(&self) -> AudioHandle {
         self.handle.clone()
     }
+
+    /// The synth the editors audition sounds on.
+    pub fn preview(&self) -> AudioHandle {
+        self.preview.clone()
+    }
 }
 
 #[cfg(test)]
@@ -860,6 +1231,50 @@ mod tests {
         assert_eq!(synth.channel_sfx()[0], None, "voice should end");
     }
 
+    #[test]
+    fn rate_speeds_sfx_up_and_zero_mutes() {
+        let mut synth = Synth::new(44100.0);
+        synth.load(test_sfx(), vec![MusicPattern::default(); 64]);
+        synth.set_rate(2.0);
+        synth.play_sfx(0, 0);
+        // ~4.25 s at 1x, so done within 2.5 s at 2x.
+        for _ in 0..(44100 * 5 / 2) {
+            synth.next_sample();
+        }
+        assert_eq!(synth.channel_sfx()[0], None);
+
+        synth.set_rate(0.0);
+        synth.play_sfx(0, 0);
+        for _ in 0..(44100 * 5) {
+            assert_eq!(synth.next_sample(), 0.0);
+        }
+        assert_eq!(synth.channel_sfx()[0], Some(0), "muted voices hold");
+    }
+
+    #[test]
+    fn block_rendering_matches_per_sample() {
+        // Music plus an sfx over it, so the sequencer and the resampler both
+        // cross block boundaries; odd block sizes to misalign everything.
+        let mut music = vec![MusicPattern::default(); 64];
+        music[0].channels = [Some(0), None, Some(0), None];
+        let start = |synth: &mut Synth| {
+            synth.load(test_sfx(), music.clone());
+            synth.play_music(0, 0, 0, 0);
+            synth.play_sfx(0, 1);
+        };
+        let mut one = Synth::new(48000.0);
+        start(&mut one);
+        let per_sample: Vec<f32> = (0..20000).map(|_| one.next_sample()).collect();
+
+        let mut block = Synth::new(48000.0);
+        start(&mut block);
+        let mut blocks = vec![0.0; 20000];
+        for chunk in blocks.chunks_mut(333) {
+            block.render_block(chunk);
+        }
+        assert_eq!(per_sample, blocks);
+    }
+
     #[test]
     fn custom_instrument_borrows_its_waveform() {
         use crate::assets::NOTE_CUSTOM_FLAG;
@@ -1100,6 +1515,54 @@ mod tests {
         assert!(chans[1..].contains(&Some(1)), "sfx lands elsewhere");
     }
 
+    #[test]
+    fn stealing_takes_the_lowest_priority_then_the_oldest() {
+        let mut synth = Synth::new(44100.0);
+        synth.load(test_sfx(), vec![MusicPattern::default(); 64]);
+        // Fill every channel: 0 and 2 at priority 1, 1 and 3 at priority 5.
+        for (ch, p) in [(0, 1), (1, 5), (2, 1), (3, 5)] {
+            synth.play_sfx_priority(0, ch, p);
+        }
+        synth.play_sfx_priority(0, -1, 3);
+        assert_eq!(synth.voices[0].as_ref().unwrap().priority, 3, "oldest p1");
+        synth.play_sfx_priority(0, -1, 3);
+        assert_eq!(synth.voices[2].as_ref().unwrap().priority, 3, "other p1");
+        // Now the lowest is 3 on channels 0 and 2; a priority-2 sound loses.
+        let before: Vec<u64> = synth
+            .voices
+            .iter()
+            .map(|v| v.as_ref().unwrap().serial)
+            .collect();
+        synth.play_sfx_priority(0, -1, 2);
+        let after: Vec<u64> = synth
+            .voices
+            .iter()
+            .map(|v| v.as_ref().unwrap().serial)
+            .collect();
+        assert_eq!(before, after, "outranked sound is dropped");
+        // Equal priority steals the oldest of the equals.
+        synth.play_sfx_priority(0, -1, 3);
+        assert_eq!(synth.voices[0].as_ref().unwrap().serial, 7);
+    }
+
+    #[test]
+    fn stealing_never_takes_a_reserved_channel() {
+        let mut synth = Synth::new(44100.0);
+        let mut music = vec![MusicPattern::default(); 64];
+        music[0].channels = [Some(0), Some(0), Some(0), Some(0)];
+        synth.load(test_sfx(), music);
+        synth.play_music(0, 0, 0b1111, 0);
+        synth.play_sfx_priority(1, -1, 255);
+        assert_eq!(synth.channel_sfx(), [Some(0); 4], "music keeps all four");
+
+        // Unreserved music channels are fair game once sfx can't be stolen.
+        synth.stop_all();
+        synth.play_music(0, 0, 0b0011, 0);
+        synth.play_sfx(1, -1);
+        assert_eq!(synth.channel_sfx()[..2], [Some(0), Some(0)]);
+        assert!(synth.channel_sfx()[2..].contains(&Some(1)));
+    }
+
     #[test]
     fn drawn_waveform_instrument_drives_output() {
         use crate::assets::{CustomWave, Note, NOTE_CUSTOM_FLAG, SFX_COUNT, SFX_LEN};
@@ -1374,6 +1837,70 @@ mod tests {
         );
     }
 
+    /// Half a second of a sustained pitch-`pitch` `wave` at 48 kHz, past
+    /// the onset ramp, rendered at the given oscillator quality.
+    fn render_tone(wave: u8, pitch: u8, quality: OscQuality) -> Vec<f32> {
+        let mut all = vec![Sfx::default(); SFX_COUNT];
+        all[0].speed = 1;
+        for n in all[0].notes.iter_mut() {
+            *n = Note {
+                pitch,
+                wave,
+                volume: 7,
+                effect: 0,
+            };
+        }
+        let mut synth = Synth::new(48000.0);
+        synth.set_quality(quality);
+        synth.load(all, vec![MusicPattern::default(); 64]);
+        synth.play_sfx(0, 0);
+        (0..24000).map(|_| synth.next_sample()).skip(512).collect()
+    }
+
+    #[test]
+    fn band_limited_oscillators_cut_aliasing() {
+        // A pitch-63 (~2489 Hz) tone at the 22050 Hz internal rate: harmonics
+        // 5-7 fold back below Nyquist at ~9605/7116/4627 Hz, which are not
+        // harmonics of the note, so energy there is pure aliasing. Measured
+        // alias/fundamental ratios, naive -> PolyBLEP: saw 0.219 -> 0.034,
+        // square 0.148 -> 0.021, pulse 0.154 -> 0.025. Requiring a 3x cut
+        // leaves margin while still failing the naive path.
+        for wave in [2, 3, 4] {
+            let alias_ratio = |quality| {
+                let buf = render_tone(wave, 63, quality);
+                let fund = goertzel(&buf, 2489.0, 48000.0);
+                let alias: f32 = [9605.0, 7116.0, 4627.0]
+                    .iter()
+                    .map(|&f| goertzel(&buf, f, 48000.0))
+                    .sum();
+                alias / fund
+            };
+            let naive = alias_ratio(OscQuality::Authentic);
+            let blep = alias_ratio(OscQuality::BandLimited);
+            assert!(
+                blep < naive / 3.0,
+                "wave {wave}: aliasing {naive} -> {blep} should drop at least 3x"
+            );
+        }
+    }
+
+    #[test]
+    fn band_limiting_leaves_low_notes_alone() {
+        // Two octaves below A4 the edges span many samples; PolyBLEP only
+        // rounds the one sample either side, so the tone barely changes.
+        for wave in [2, 3, 4] {
+            let naive = render_tone(wave, 9, OscQuality::Authentic);
+            let blep = render_tone(wave, 9, OscQuality::BandLimited);
+            let diff = naive
+                .iter()
+                .zip(&blep)
+                .map(|(a, b)| (a - b).abs())
+                .sum::<f32>()
+                / naive.len() as f32;
+            assert!(diff < 0.01, "wave {wave}: mean difference {diff}");
+        }
+    }
+
     #[test]
     fn start_is_allowed_while_fading_out() {
         let mut synth = Synth::new(44100.0);
@@ -1393,6 +1920,132 @@ mod tests {
         );
     }
 
+    /// Two looping songs: A on patterns 0-1 (SFX 0 on channel 0) and B on
+    /// pattern 2 (SFX 1 on channel 1, an octave up).
+    fn two_songs() -> Synth {
+        let mut sfx = test_sfx();
+        sfx[0].speed = 1;
+        sfx[1] = sfx[0].clone();
+        for n in sfx[1].notes.iter_mut() {
+            n.pitch = 45;
+        }
+        let mut music = vec![MusicPattern::default(); 64];
+        music[0].channels[0] = Some(0);
+        music[0].loop_start = true;
+        music[1].channels[0] = Some(0);
+        music[1].loop_back = true;
+        music[2].channels[1] = Some(1);
+        music[2].loop_start = true;
+        music[2].loop_back = true;
+        let mut synth = Synth::new(44100.0);
+        synth.load(sfx, music);
+        synth
+    }
+
+    #[test]
+    fn crossfade_starts_now_and_retires_the_old_song() {
+        let mut synth = two_songs();
+        let a = synth.play_music(0, 0, 0, 0);
+        assert_eq!(synth.play_music(2, 0, 0, 0), 0, "exclusive is refused");
+        let b = synth.start_music(2, 200, 0, MusicStart::Crossfade);
+        assert!(b != 0 && b != a);
+        assert_eq!(synth.playing_pattern(), Some(2), "new song is live");
+        assert_eq!(synth.channel_sfx(), [None, Some(1), None, None]);
+        assert!(
+            synth.outgoing[0].voices[0].is_some(),
+            "old song still sounding"
+        );
+
+        // A stale stop from the old song's handle must not cut the new one.
+        synth.play_music(-1, 0, 0, a);
+        assert_eq!(synth.playing_pattern(), Some(2));
+
+        // 200 ms later the old voices are gone and the new song is at full.
+        for _ in 0..(44100 / 4) {
+            synth.next_sample();
+        }
+        assert!(synth.outgoing.is_empty());
+        assert_eq!(synth.music_gain, 1.0);
+    }
+
+    #[test]
+    fn a_second_crossfade_lets_the_first_finish() {
+        let mut synth = two_songs();
+        synth.play_music(0, 0, 0, 0);
+        synth.start_music(2, 200, 0, MusicStart::Crossfade);
+        for _ in 0..(44100 / 20) {
+            synth.next_sample();
+        }
+        // Back to A before the first fade is done: both songs fade out.
+        synth.start_music(0, 200, 0, MusicStart::Crossfade);
+        assert_eq!(synth.outgoing.len(), 2);
+        assert!(synth.outgoing[0].voices[0].is_some(), "A is still fading");
+        assert!(synth.outgoing[1].voices[1].is_some(), "B fades out too");
+        for _ in 0..(44100 / 4) {
+            synth.next_sample();
+        }
+        assert!(synth.outgoing.is_empty());
+
+        // Live edits reload the audio data; the fade plays on through them.
+        synth.start_music(2, 200, 0, MusicStart::Crossfade);
+        synth.load(synth.sfx.clone(), synth.music.clone());
+        assert_eq!(synth.outgoing.len(), 1);
+        assert!(synth.outgoing[0].voices[0].is_some(), "A is still fading");
+        synth.stop_all();
+        assert!(synth.outgoing.is_empty(), "stopping drops it");
+    }
+
+    #[test]
+    fn queued_song_waits_for_the_pattern_boundary() {
+        let mut synth = two_songs();
+        let a = synth.play_music(0, 0, 0, 0);
+        let b = synth.play_music(2, 0, 0, 2);
+        assert!(b != 0 && b != a, "queue accepted");
+        synth.play_music(-1, 0, 0, a);
+        assert_eq!(synth.playing_pattern(), Some(0), "A keeps playing");
+
+        // SFX 0 at speed 1 lasts 32 * 183 / 22050 ~= 0.27 s; cross it.
+        let mut seen = vec![];
+        for _ in 0..(44100 / 2) {
+            synth.next_sample();
+            if seen.last() != Some(&synth.playing_pattern()) {
+                seen.push(synth.playing_pattern());
+            }
+        }
+        assert_eq!(
+            seen,
+            [Some(0), Some(2)],
+            "B replaces A at its first boundary"
+        );
+    }
+
+    #[test]
+    fn stopping_cancels_a_queued_song() {
+        let mut synth = two_songs();
+        synth.play_music(0, 0, 0, 0);
+        let b = synth.start_music(2, 0, 0, MusicStart::Queue);
+        assert_eq!(synth.music_token(), b);
+        synth.play_music(-1, 0, 0, b);
+        assert_eq!(synth.playing_pattern(), Some(0), "the playing song goes on");
+        for _ in 0..(44100 / 2) {
+            synth.next_sample();
+        }
+        assert_ne!(
+            synth.playing_pattern(),
+            Some(2),
+            "the queued song never starts"
+        );
+
+        // Once it has taken over, its token stops it.
+        let c = synth.start_music(2, 0, 0, MusicStart::Queue);
+        for _ in 0..(44100 / 2) {
+            synth.next_sample();
+        }
+        assert_eq!(synth.playing_pattern(), Some(2));
+        synth.play_music(-1, 0, 0, c);
+        assert_eq!(synth.playing_pattern(), None);
+    }
+
     #[test]
     fn waveform_amplitudes_match_pico8() {
         // Each non-noise waveform must peak at PICO-8's per-waveform amplitude
@@ -0,0 +1,396 @@
+//! Call stacks for carts that trap.
+//!
+//! wasmi reports a trap without saying where in the cart it happened. When
+//! one does, the VM replays the failing call on a traced copy of the cart:
+//! every function is rewritten to tell the host when it is entered and left,
+//! so the host's stack is the cart's call stack at the moment it traps. The
+//! function indices are then named from the module's `name` section (Rust
+//! carts keep it unless stripped) and demangled.
+//!
+//! The traced copy is built only after a trap. What a healthy cart pays for
+//! is the snapshot each frame starts with, to replay from: a copy of its
+//! memory and screen into the same buffers every frame.
+
+use std::collections::HashMap;
+use wasm_encoder::{
+    reencode::{self, Reencode, RoundtripReencoder},
+    BlockType, EntityType, Instruction, SectionId,
+};
+use wasmparser::{KnownCustom, Name, Operator, Parser, Payload, ValType};
+
+/// The host module the traced copy imports `enter(func)` and `leave()` from.
+pub(crate) const TRACE_MODULE: &str = "rico8_trace";
+
+/// Frames listed by [`RuntimeError::backtrace_lines`](crate::vm::RuntimeError::backtrace_lines)
+/// before the rest are elided; a stack overflow can be thousands deep.
+pub const MAX_FRAMES: usize = 48;
+
+/// Function names from the module's `name` section, by function index.
+/// Empty for a stripped module.
+pub(crate) fn function_names(wasm: &[u8]) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        let Ok(Payload::CustomSection(section)) = payload else {
+            continue;
+        };
+        let KnownCustom::Name(reader) = section.as_known() else {
+            continue;
+        };
+        for sub in reader.into_iter().flatten() {
+            if let Name::Function(map) = sub {
+                for naming in map.into_iter().flatten() {
+                    names.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Name a call stack (outermost first, as the tracer records it) innermost
+/// first. A run of the same function, as in runaway recursion, becomes one
+/// frame with a count.
+pub(crate) fn symbolize(stack: &[u32], names: &HashMap<u32, String>) -> Vec<String> {
+    let mut frames: Vec<(u32, usize)> = Vec::new();
+    for &func in stack.iter().rev() {
+        match frames.last_mut() {
+            Some((last, n)) if *last == func => *n += 1,
+            _ => frames.push((func, 1)),
+        }
+    }
+    frames
+        .into_iter()
+        .map(|(func, n)| {
+            let name = match names.get(&func) {
+                Some(name) => demangle(name),
+                None => format!("wasm function {func}"),
+            };
+            if n > 1 {
+                format!("{name} (x{n})")
+            } else {
+                name
+            }
+        })
+        .collect()
+}
+
+/// Demangle a legacy Rust symbol (`_ZN...17h<hash>E`) into its path, without
+/// the hash. Anything else is returned as is.
+pub fn demangle(symbol: &str) -> String {
+    demangle_legacy(symbol).unwrap_or_else(|| symbol.to_string())
+}
+
+fn demangle_legacy(symbol: &str) -> Option<String> {
+    let mut rest = symbol.strip_prefix("_ZN")?;
+    let mut parts = Vec::new();
+    while !rest.starts_with('E') {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let len: usize = rest[..digits].parse().ok()?;
+        let ident = rest.get(digits..digits + len)?;
+        rest = &rest[digits + len..];
+        parts.push(ident);
+    }
+    // Drop the trailing hash segment.
+    if parts.last().is_some_and(|p| {
+        p.len() == 17 && p.starts_with('h') && p[1..].bytes().all(|b| b.is_ascii_hexdigit())
+    }) {
+        parts.pop();
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    let parts: Option<Vec<String>> = parts.into_iter().map(unescape).collect();
+    Some(parts?.join("::"))
+}
+
+/// Undo the legacy scheme's escapes: `$LT$` for `<`, `$u7b$` for `{`,
+/// `..` for `::` and so on.
+fn unescape(ident: &str) -> Option<String> {
+    let mut ident = ident;
+    if ident.starts_with("_$") {
+        ident = &ident[1..];
+    }
+    let mut out = String::new();
+    let mut rest = ident;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("..") {
+            out.push_str("::");
+            rest = after;
+        } else if c == '$' {
+            let end = rest[1..].find('$')? + 1;
+            out.push(match &rest[1..end] {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                code => {
+                    let hex = code.strip_prefix('u')?;
+                    char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+                }
+            });
+            rest = &rest[end + 1..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(out)
+}
+
+/// Rewrite a cart so each function calls `rico8_trace.enter(index)` on entry
+/// and `rico8_trace.leave()` on every way out, with `index` its function
+/// index in the original module. The two imports go after the cart's own,
+/// so every defined function moves up by two; custom sections are dropped.
+pub(crate) fn instrument(wasm: &[u8]) -> Result<Vec<u8>, String> {
+    let mut tracer = Tracer::scan(wasm).map_err(|e| e.to_string())?;
+    let mut module = wasm_encoder::Module::new();
+    tracer
+        .parse_core_module(&mut module, Parser::new(0), wasm)
+        .map_err(|e| e.to_string())?;
+    Ok(module.finish())
+}
+
+struct Tracer {
+    /// Function imports in the original module.
+    imported: u32,
+    /// Number of types in the original module; ours go after them.
+    types: u32,
+    /// Result types of every original type index.
+    results: Vec<Vec<ValType>>,
+    /// Type index of every defined function.
+    funcs: Vec<u32>,
+    /// Result lists of more than one value, each given a `() -> results`
+    /// type so a function body can be wrapped in a block of that type.
+    multi: Vec<Vec<ValType>>,
+    /// Defined functions rewritten so far.
+    next: u32,
+    types_done: bool,
+    imports_done: bool,
+}
+
+impl Tracer {
+    fn scan(wasm: &[u8]) -> wasmparser::Result<Self> {
+        let mut t = Tracer {
+            imported: 0,
+            types: 0,
+            results: Vec::new(),
+            funcs: Vec::new(),
+            multi: Vec::new(),
+            next: 0,
+            types_done: false,
+            imports_done: false,
+        };
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::TypeSection(reader) => {
+                    for ty in reader.into_iter_err_on_gc_types() {
+                        t.results.push(ty?.results().to_vec());
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports() {
+                        if let wasmparser::TypeRef::Func(_) = import?.ty {
+                            t.imported += 1;
+                        }
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for ty in reader {
+                        t.funcs.push(ty?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        t.types = t.results.len() as u32;
+        for &ty in &t.funcs {
+            let results = &t.results[ty as usize];
+            if results.len() > 1 && !t.multi.contains(results) {
+                t.multi.push(results.clone());
+            }
+        }
+        Ok(t)
+    }
+
+    /// `(i32) -> ()` for `enter`, `() -> ()` for `leave`, then the block types.
+    fn enter_type(&self) -> u32 {
+        self.types
+    }
+
+    fn leave_type(&self) -> u32 {
+        self.types + 1
+    }
+
+    fn enter_func(&self) -> u32 {
+        self.imported
+    }
+
+    fn leave_func(&self) -> u32 {
+        self.imported + 1
+    }
+
+    fn add_types(&mut self, types: &mut wasm_encoder::TypeSection) -> Result<(), reencode::Error> {
+        types.ty().function([wasm_encoder::ValType::I32], []);
+        types.ty().function([], []);
+        for results in &self.multi {
+            let results = results
+                .iter()
+                .map(|&v| RoundtripReencoder.val_type(v))
+                .collect::<Result<Vec<_>, _>>()?;
+            types.ty().function([], results);
+        }
+        self.types_done = true;
+        Ok(())
+    }
+
+    fn add_imports(&mut self, imports: &mut wasm_encoder::ImportSection) {
+        let enter = EntityType::Function(self.enter_type());
+        let leave = EntityType::Function(self.leave_type());
+        imports.import(TRACE_MODULE, "enter", enter);
+        imports.import(TRACE_MODULE, "leave", leave);
+        self.imports_done = true;
+    }
+
+    fn block_type(&mut self, ty: u32) -> Result<BlockType, reencode::Error> {
+        let results = &self.results[ty as usize];
+        Ok(match results.len() {
+            0 => BlockType::Empty,
+            1 => BlockType::Result(self.val_type(results[0])?),
+            _ => {
+                let i = self.multi.iter().position(|m| m == results).unwrap_or(0);
+                BlockType::FunctionType(self.types + 2 + i as u32)
+            }
+        })
+    }
+}
+
+impl Reencode for Tracer {
+    type Error = std::convert::Infallible;
+
+    fn function_index(&mut self, func: u32) -> Result<u32, reencode::Error> {
+        Ok(if func >= self.imported {
+            func + 2
+        } else {
+            func
+        })
+    }
+
+    fn parse_type_section(
+        &mut self,
+        types: &mut wasm_encoder::TypeSection,
+        section: wasmparser::TypeSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        reencode::utils::parse_type_section(self, types, section)?;
+        self.add_types(types)
+    }
+
+    fn parse_import_section(
+        &mut self,
+        imports: &mut wasm_encoder::ImportSection,
+        section: wasmparser::ImportSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        reencode::utils::parse_import_section(self, imports, section)?;
+        self.add_imports(imports);
+        Ok(())
+    }
+
+    /// A module without a type or import section still gets ours, in order.
+    fn intersperse_section_hook(
+        &mut self,
+        module: &mut wasm_encoder::Module,
+        _after: Option<SectionId>,
+        before: Option<SectionId>,
+    ) -> Result<(), reencode::Error> {
+        if !self.types_done && before != Some(SectionId::Type) {
+            let mut types = wasm_encoder::TypeSection::new();
+            self.add_types(&mut types)?;
+            module.section(&types);
+        }
+        if !self.imports_done && !matches!(before, Some(SectionId::Type | SectionId::Import)) {
+            let mut imports = wasm_encoder::ImportSection::new();
+            self.add_imports(&mut imports);
+            module.section(&imports);
+        }
+        Ok(())
+    }
+
+    fn parse_custom_section(
+        &mut self,
+        _module: &mut wasm_encoder::Module,
+        _section: wasmparser::CustomSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        Ok(())
+    }
+
+    /// `enter`, then the body in a block so branches out of it still pass
+    /// through `leave`; `return` and tail calls call `leave` first.
+    fn parse_function_body(
+        &mut self,
+        code: &mut wasm_encoder::CodeSection,
+        func: wasmparser::FunctionBody<'_>,
+    ) -> Result<(), reencode::Error> {
+        let index = self.imported + self.next;
+        let ty = self.funcs[self.next as usize];
+        self.next += 1;
+        let mut f = self.new_function_with_parsed_locals(&func)?;
+        f.instruction(&Instruction::I32Const(index as i32));
+        f.instruction(&Instruction::Call(self.enter_func()));
+        f.instruction(&Instruction::Block(self.block_type(ty)?));
+        let mut reader = func.get_operators_reader()?;
+        while !reader.eof() {
+            let op = reader.read()?;
+            if matches!(
+                op,
+                Operator::Return
+                    | Operator::ReturnCall { .. }
+                    | Operator::ReturnCallIndirect { .. }
+                    | Operator::ReturnCallRef { .. }
+            ) {
+                f.instruction(&Instruction::Call(self.leave_func()));
+            }
+            f.instruction(&self.instruction(op)?);
+        }
+        // The body's own `end` closed the block.
+        f.instruction(&Instruction::Call(self.leave_func()));
+        f.instruction(&Instruction::End);
+        code.function(&f);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_legacy_rust_symbols() {
+        assert_eq!(
+            demangle("_ZN4core9panicking5panic17h0123456789abcdefE"),
+            "core::panicking::panic"
+        );
+        assert_eq!(
+            demangle(
+                "_ZN44_$LT$game..Walker$u20$as$u20$rico8..Game$GT$6update17hfedcba9876543210E"
+            ),
+            "<game::Walker as rico8::Game>::update"
+        );
+        assert_eq!(demangle("rico8_update"), "rico8_update");
+        assert_eq!(demangle("_ZN3bad"), "_ZN3bad");
+    }
+
+    #[test]
+    fn symbolize_names_innermost_first_and_folds_recursion() {
+        let names = HashMap::from([
+            (1, "outer".to_string()),
+            (2, "_ZN4game4deep17h0000000000000000E".to_string()),
+        ]);
+        assert_eq!(
+            symbolize(&[1, 2, 2, 2, 7], &names),
+            ["wasm function 7", "game::deep (x3)", "outer"]
+        );
+    }
+}
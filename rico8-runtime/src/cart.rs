@@ -206,7 +206,8 @@ fn write_chunk(out: &mut Vec<u8>, ctype: [u8; 4], data: &[u8]) {
 }
 
 /// Encode the virtual screen as a standalone PNG (for screenshots and
-/// docs). `scale` is an integer zoom factor.
+/// docs), as displayed: the display palette is applied. `scale` is an
+/// integer zoom factor.
 pub fn encode_screen_png(fb: &crate::fb::Framebuffer, scale: usize) -> Vec<u8> {
     let scale = scale.max(1);
     let (w, h) = (128 * scale, 128 * scale);
@@ -218,12 +219,13 @@ pub fn encode_screen_png(fb: &crate::fb::Framebuffer, scale: usize) -> Vec<u8> {
     write_chunk(&mut png, *b"IHDR", &ihdr);
 
     let pixels = fb.pixels();
+    let shown = fb.display_palette();
     let mut raw = Vec::with_capacity(h * (1 + w * 4));
     for y in 0..h {
         raw.push(0);
         for x in 0..w {
             let c = pixels[(y / scale) * 128 + x / scale];
-            raw.extend(palette::rgba(c));
+            raw.extend(palette::rgba(shown[(c & 0x0f) as usize]));
         }
     }
     write_chunk(
@@ -235,6 +237,407 @@ pub fn encode_screen_png(fb: &crate::fb::Framebuffer, scale: usize) -> Vec<u8> {
     png
 }
 
+/// Zoom for screenshot hotkeys: 512x512, big enough to share as-is.
+pub const SCREENSHOT_SCALE: usize = 4;
+
+/// Where screenshot hotkeys write: `RICO8_SCREENSHOT_DIR` if set, otherwise
+/// the frontend's own `default`.
+pub fn screenshot_dir(default: &std::path::Path) -> std::path::PathBuf {
+    match std::env::var_os("RICO8_SCREENSHOT_DIR") {
+        Some(dir) if !dir.is_empty() => dir.into(),
+        _ => default.to_path_buf(),
+    }
+}
+
+/// Save the screen as a scaled PNG named by the UTC time it was taken,
+/// `rico8_YYYYMMDD_HHMMSS.png` (with `_2`, `_3`... for more in the same
+/// second), creating `dir` if needed. Returns the path written.
+pub fn save_screenshot(
+    fb: &crate::fb::Framebuffer,
+    dir: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    std::fs::create_dir_all(dir).map_err(|e| anyhow!("Creating {}: {e}", dir.display()))?;
+    let stem = format!("rico8_{}", utc_timestamp(std::time::SystemTime::now()));
+    let path = std::iter::once(dir.join(format!("{stem}.png")))
+        .chain((2..).map(|n| dir.join(format!("{stem}_{n}.png"))))
+        .find(|p| !p.exists())
+        .expect("some screenshot name is free");
+    std::fs::write(&path, encode_screen_png(fb, SCREENSHOT_SCALE))
+        .map_err(|e| anyhow!("Writing {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// `YYYYMMDD_HHMMSS` in UTC, without pulling in a date crate.
+fn utc_timestamp(t: std::time::SystemTime) -> String {
+    let secs = t
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}_{:02}{:02}{:02}",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+// ---------------------------------------------------------------------------
+// A small PNG decoder (8-bit RGBA, non-interlaced) for cart images and goldens
+// ---------------------------------------------------------------------------
+
+/// Decode an 8-bit RGBA, non-interlaced PNG to `(width, height, rgba)`.
+/// Just enough of the spec to read a PICO-8 cart image or a screenshot from
+/// [`encode_screen_png`]; richer PNGs are rejected with a clear message
+/// rather than mis-decoded.
+pub fn decode_png_rgba(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>)> {
+    if !bytes.starts_with(&PNG_SIG) {
+        bail!("not a png file");
+    }
+    let mut rest = &bytes[8..];
+    let (mut width, mut height) = (0usize, 0usize);
+    let mut idat = Vec::new();
+    let mut have_ihdr = false;
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[0..4].try_into().unwrap()) as usize;
+        let ctype = &rest[4..8];
+        if rest.len() < 12 + len {
+            bail!("truncated png chunk");
+        }
+        let data = &rest[8..8 + len];
+        match ctype {
+            b"IHDR" => {
+                if len < 13 {
+                    bail!("malformed png header");
+                }
+                width = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
+                height = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
+                let (bit_depth, color_type, interlace) = (data[8], data[9], data[12]);
+                if bit_depth != 8 || color_type != 6 {
+                    bail!("unsupported png: need 8-bit rgba (a pico-8 cart png is)");
+                }
+                if interlace != 0 {
+                    bail!("interlaced png is not supported");
+                }
+                have_ihdr = true;
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = &rest[12 + len..];
+    }
+    if !have_ihdr {
+        bail!("png has no header chunk");
+    }
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&idat, 64 * 1024 * 1024)
+        .map_err(|e| anyhow!("png image data is corrupted: {e:?}"))?;
+
+    const BPP: usize = 4;
+    let stride = width * BPP;
+    if raw.len() < height * (stride + 1) {
+        bail!("png image data is truncated");
+    }
+    let mut out = vec![0u8; height * stride];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..y * (stride + 1) + 1 + stride];
+        for i in 0..stride {
+            let a = if i >= BPP {
+                out[y * stride + i - BPP]
+            } else {
+                0
+            };
+            let b = if y > 0 { out[(y - 1) * stride + i] } else { 0 };
+            let c = if y > 0 && i >= BPP {
+                out[(y - 1) * stride + i - BPP]
+            } else {
+                0
+            };
+            out[y * stride + i] = match filter {
+                0 => line[i],
+                1 => line[i].wrapping_add(a),
+                2 => line[i].wrapping_add(b),
+                3 => line[i].wrapping_add(((a as u16 + b as u16) / 2) as u8),
+                4 => line[i].wrapping_add(paeth(a, b, c)),
+                f => bail!("unknown png filter type {f}"),
+            };
+        }
+    }
+    Ok((width, height, out))
+}
+
+/// The PNG Paeth predictor.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i32 + b as i32 - c as i32;
+    let (pa, pb, pc) = (
+        (p - a as i32).abs(),
+        (p - b as i32).abs(),
+        (p - c as i32).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Animated GIF recording (16-color, LZW) for sharing clips of running carts
+// ---------------------------------------------------------------------------
+
+/// Longest GIF recording; the recorder stops taking frames at this length.
+pub const GIF_MAX_SECONDS: u32 = 20;
+
+/// Carts faster than this are sampled down to it: browsers treat GIF delays
+/// under 2/100 s as "as slow as possible", so 60 fps clips would crawl.
+const GIF_MAX_FPS: u32 = 30;
+
+/// One recorded frame: the colors actually shown (display palette applied)
+/// and how long it stays up, in hundredths of a second.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GifFrame {
+    pub pixels: Vec<u8>,
+    pub delay_cs: u16,
+}
+
+/// Collects frames from a running cart for [`encode_gif`]. Feed it every
+/// presented frame; it samples down to at most 30 fps and keeps exact
+/// timing by spreading the rounding of centisecond delays across frames.
+pub struct GifRecorder {
+    frames: Vec<GifFrame>,
+    scale: usize,
+    /// Take one of every `step` frames offered.
+    step: u32,
+    /// Recorded frames per second after sampling, as a rational `fps / step`.
+    fps: u32,
+    offered: u32,
+}
+
+impl GifRecorder {
+    /// Start a recording of a cart running at `cart_fps`, encoded at an
+    /// integer `scale` (2 or 4 are the useful ones).
+    pub fn new(cart_fps: u32, scale: usize) -> Self {
+        let fps = cart_fps.max(1);
+        Self {
+            frames: Vec::new(),
+            scale: scale.clamp(1, 8),
+            step: fps.div_ceil(GIF_MAX_FPS),
+            fps,
+            offered: 0,
+        }
+    }
+
+    /// Offer a presented frame. Returns `false` once the length cap is hit
+    /// and the frame was not taken.
+    pub fn capture(&mut self, fb: &crate::fb::Framebuffer) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        let take = self.offered.is_multiple_of(self.step);
+        self.offered += 1;
+        if !take {
+            return true;
+        }
+        // Delays are whole centiseconds; derive each from the running total
+        // so a 30 fps clip alternates 3/4 instead of drifting slow.
+        let n = self.frames.len() as u64;
+        let at = |i: u64| (i * self.step as u64 * 100 + self.fps as u64 / 2) / self.fps as u64;
+        let delay_cs = (at(n + 1) - at(n)) as u16;
+        let shown = fb.display_palette();
+        let pixels = fb
+            .pixels()
+            .iter()
+            .map(|&c| shown[(c & 0x0f) as usize])
+            .collect();
+        self.frames.push(GifFrame { pixels, delay_cs });
+        true
+    }
+
+    /// Whether the recording has reached [`GIF_MAX_SECONDS`].
+    pub fn is_full(&self) -> bool {
+        self.seconds() >= GIF_MAX_SECONDS as f32
+    }
+
+    /// Recorded length so far.
+    pub fn seconds(&self) -> f32 {
+        self.frames.iter().map(|f| f.delay_cs as f32).sum::<f32>() / 100.0
+    }
+
+    pub fn frames(&self) -> &[GifFrame] {
+        &self.frames
+    }
+
+    /// Encode what has been recorded as a looping animated GIF.
+    pub fn encode(&self) -> Vec<u8> {
+        encode_gif(&self.frames, self.scale)
+    }
+
+    /// Encode and write the recording into `dir` as `<stem>_N.gif`, using the
+    /// first free `N`, and return the path written.
+    pub fn save_in(&self, dir: &std::path::Path, stem: &str) -> Result<std::path::PathBuf> {
+        let path = (1..)
+            .map(|n| dir.join(format!("{stem}_{n}.gif")))
+            .find(|p| !p.exists())
+            .expect("some recording number is free");
+        std::fs::write(&path, self.encode())
+            .map_err(|e| anyhow!("Writing {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Encode 128x128 frames of palette indices as a looping animated GIF at an
+/// integer `scale`, using the fixed 16-color palette. Each frame after the
+/// first stores only the rectangle that changed, and unchanged frames just
+/// extend the previous frame's delay, so clips of mostly-still games stay
+/// small.
+pub fn encode_gif(frames: &[GifFrame], scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let side = (128 * scale) as u16;
+    let mut out = b"GIF89a".to_vec();
+    out.extend(side.to_le_bytes());
+    out.extend(side.to_le_bytes());
+    // Global color table of 2^(3+1) = 16 entries, 8 bits per primary.
+    out.extend([0xf3, 0, 0]);
+    for c in 0..16 {
+        out.extend(&palette::rgba(c)[..3]);
+    }
+    // NETSCAPE2.0 application extension: loop forever.
+    out.extend([0x21, 0xff, 0x0b]);
+    out.extend(b"NETSCAPE2.0");
+    out.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    // Merge runs of identical frames, then emit each with its changed rect.
+    let mut merged: Vec<(&[u8], u32)> = Vec::new();
+    for f in frames {
+        match merged.last_mut() {
+            Some((px, delay)) if *px == f.pixels.as_slice() => *delay += f.delay_cs as u32,
+            _ => merged.push((&f.pixels, f.delay_cs as u32)),
+        }
+    }
+    let mut prev: Option<&[u8]> = None;
+    for (px, delay) in merged {
+        let (x0, y0, x1, y1) = match prev {
+            None => (0, 0, 128, 128),
+            Some(prev) => changed_rect(prev, px),
+        };
+        prev = Some(px);
+        // Graphic control: keep the previous frame underneath (disposal 1).
+        out.extend([0x21, 0xf9, 0x04, 0x04]);
+        out.extend((delay.min(u16::MAX as u32) as u16).to_le_bytes());
+        out.extend([0x00, 0x00]);
+        // Image descriptor, no local color table.
+        let (w, h) = ((x1 - x0) * scale, (y1 - y0) * scale);
+        out.push(0x2c);
+        for v in [x0 * scale, y0 * scale, w, h] {
+            out.extend((v as u16).to_le_bytes());
+        }
+        out.push(0);
+        let mut indices = Vec::with_capacity(w * h);
+        for y in y0 * scale..y1 * scale {
+            for x in x0 * scale..x1 * scale {
+                indices.push(px[(y / scale) * 128 + x / scale] & 0x0f);
+            }
+        }
+        out.push(4);
+        for block in lzw_encode(&indices).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend(block);
+        }
+        out.push(0);
+    }
+    out.push(0x3b);
+    out
+}
+
+/// The bounding box `(x0, y0, x1, y1)` of pixels that differ between two
+/// frames; a 1x1 box at the origin when nothing did.
+fn changed_rect(a: &[u8], b: &[u8]) -> (usize, usize, usize, usize) {
+    let (mut x0, mut y0, mut x1, mut y1) = (128, 128, 0, 0);
+    for (i, _) in a.iter().zip(b).enumerate().filter(|(_, (p, q))| p != q) {
+        let (x, y) = (i % 128, i / 128);
+        x0 = x0.min(x);
+        y0 = y0.min(y);
+        x1 = x1.max(x + 1);
+        y1 = y1.max(y + 1);
+    }
+    if x1 == 0 {
+        (0, 0, 1, 1)
+    } else {
+        (x0, y0, x1, y1)
+    }
+}
+
+/// GIF-flavored LZW over 4-bit indices (minimum code size 4), packed
+/// LSB-first. The dictionary is a dense child table: 16 possible next
+/// symbols per code.
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 16;
+    const END: u16 = 17;
+    const MAX_CODES: u16 = 4096;
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let mut emit = |code: u16, size: u32, out: &mut Vec<u8>| {
+        acc |= (code as u32) << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(acc as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    };
+    let mut children = vec![0u16; MAX_CODES as usize * 16];
+    let (mut next, mut size) = (END + 1, 5u32);
+    emit(CLEAR, size, &mut out);
+    let mut symbols = indices.iter().map(|&i| (i & 0x0f) as u16);
+    let Some(mut cur) = symbols.next() else {
+        emit(END, size, &mut out);
+        if bits > 0 {
+            out.push(acc as u8);
+        }
+        return out;
+    };
+    for k in symbols {
+        let slot = cur as usize * 16 + k as usize;
+        if children[slot] != 0 {
+            cur = children[slot];
+            continue;
+        }
+        emit(cur, size, &mut out);
+        if next < MAX_CODES {
+            children[slot] = next;
+            next += 1;
+            if next as u32 > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            emit(CLEAR, size, &mut out);
+            children.fill(0);
+            next = END + 1;
+            size = 5;
+        }
+        cur = k;
+    }
+    emit(cur, size, &mut out);
+    emit(END, size, &mut out);
+    if bits > 0 {
+        out.push(acc as u8);
+    }
+    out
+}
+
 // ---------------------------------------------------------------------------
 // Label art
 // ---------------------------------------------------------------------------
@@ -581,4 +984,172 @@ mod tests {
         // Simpler: just assert decode fails one way or another.
         assert!(decode(&png).is_err());
     }
+
+    /// A minimal GIF reader for the round-trip tests: global palette, frame
+    /// rectangles composited without disposal, LZW with early change.
+    fn decode_gif(bytes: &[u8]) -> (usize, Vec<(Vec<u8>, u16)>) {
+        assert_eq!(&bytes[..6], b"GIF89a");
+        let side = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let mut i = 13 + 16 * 3;
+        let mut canvas = vec![0u8; side * side];
+        let (mut frames, mut delay) = (Vec::new(), 0);
+        loop {
+            match bytes[i] {
+                0x21 => {
+                    if bytes[i + 1] == 0xf9 {
+                        delay = u16::from_le_bytes([bytes[i + 4], bytes[i + 5]]);
+                    }
+                    i += 2;
+                    while bytes[i] != 0 {
+                        i += bytes[i] as usize + 1;
+                    }
+                    i += 1;
+                }
+                0x2c => {
+                    let r =
+                        |o: usize| u16::from_le_bytes([bytes[i + o], bytes[i + o + 1]]) as usize;
+                    let (x0, y0, w, h) = (r(1), r(3), r(5), r(7));
+                    i += 10;
+                    let min = bytes[i] as u32;
+                    i += 1;
+                    let mut data = Vec::new();
+                    while bytes[i] != 0 {
+                        data.extend(&bytes[i + 1..i + 1 + bytes[i] as usize]);
+                        i += bytes[i] as usize + 1;
+                    }
+                    i += 1;
+                    let px = lzw_decode(&data, min);
+                    assert_eq!(px.len(), w * h);
+                    for y in 0..h {
+                        for x in 0..w {
+                            canvas[(y0 + y) * side + x0 + x] = px[y * w + x];
+                        }
+                    }
+                    frames.push((canvas.clone(), delay));
+                }
+                0x3b => return (side, frames),
+                b => panic!("unexpected block {b:#x}"),
+            }
+        }
+    }
+
+    fn lzw_decode(data: &[u8], min: u32) -> Vec<u8> {
+        let (clear, end) = (1u32 << min, (1u32 << min) + 1);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut size = min + 1;
+        let (mut pos, mut out, mut prev): (usize, Vec<u8>, Option<Vec<u8>>) = (0, Vec::new(), None);
+        loop {
+            let mut code = 0u32;
+            for b in 0..size {
+                let bit = (data[(pos + b as usize) / 8] >> ((pos + b as usize) % 8)) & 1;
+                code |= (bit as u32) << b;
+            }
+            pos += size as usize;
+            if code == clear {
+                table = (0..clear).map(|c| vec![c as u8]).collect();
+                table.extend([vec![], vec![]]);
+                size = min + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code as usize), &prev) {
+                (Some(e), _) => e.clone(),
+                (None, Some(p)) => [p.as_slice(), &p[..1]].concat(),
+                (None, None) => panic!("bad first code"),
+            };
+            if let Some(p) = prev {
+                if table.len() < 4096 {
+                    table.push([p.as_slice(), &entry[..1]].concat());
+                }
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            out.extend(&entry);
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_past_a_full_table() {
+        // Noise fills the 4096-entry table several times over.
+        let mut x = 12345u32;
+        let noise: Vec<u8> = (0..60_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                (x % 16) as u8
+            })
+            .collect();
+        assert_eq!(lzw_decode(&lzw_encode(&noise), 4), noise);
+        let flat = vec![7u8; 50_000];
+        assert_eq!(lzw_decode(&lzw_encode(&flat), 4), flat);
+        assert!(lzw_decode(&lzw_encode(&[]), 4).is_empty());
+    }
+
+    #[test]
+    fn gif_frames_round_trip_with_scale_and_merged_repeats() {
+        let mut fb = crate::fb::Framebuffer::new();
+        let mut rec = GifRecorder::new(30, 2);
+        fb.cls(1);
+        rec.capture(&fb);
+        rec.capture(&fb); // identical: extends the first frame's delay
+        fb.pset(10, 20, 8);
+        rec.capture(&fb);
+        let (side, frames) = decode_gif(&rec.encode());
+        assert_eq!(side, 256);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].1, 3 + 4, "30 fps alternates 3/4 centiseconds");
+        assert!(frames[0].0.iter().all(|&c| c == 1));
+        let second = &frames[1].0;
+        for (x, y) in [(20, 40), (21, 41)] {
+            assert_eq!(second[y * 256 + x], 8);
+        }
+        assert_eq!(second.iter().filter(|&&c| c == 8).count(), 4);
+    }
+
+    #[test]
+    fn gif_recorder_applies_display_palette_samples_and_caps() {
+        let mut fb = crate::fb::Framebuffer::new();
+        fb.cls(2);
+        fb.remap_display_color(2, 9);
+        let mut rec = GifRecorder::new(60, 1);
+        for _ in 0..4 {
+            assert!(rec.capture(&fb));
+        }
+        assert_eq!(rec.frames().len(), 2, "60 fps is sampled down to 30");
+        assert!(rec.frames()[0].pixels.iter().all(|&c| c == 9));
+        while rec.capture(&fb) {}
+        assert!(rec.is_full());
+        let secs = rec.seconds();
+        assert!((GIF_MAX_SECONDS as f32..GIF_MAX_SECONDS as f32 + 0.1).contains(&secs));
+    }
+
+    #[test]
+    fn utc_timestamps_format_civil_dates() {
+        let at = |secs| utc_timestamp(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+        assert_eq!(at(0), "19700101_000000");
+        assert_eq!(at(951_782_400), "20000229_000000", "leap day");
+        assert_eq!(at(1_792_334_096), "20261018_143456");
+    }
+
+    #[test]
+    fn screenshots_show_the_display_palette_and_never_overwrite() {
+        let dir = std::env::temp_dir().join(format!("rico8_shots_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut fb = crate::fb::Framebuffer::new();
+        fb.cls(3);
+        fb.remap_display_color(3, 12);
+        let a = save_screenshot(&fb, &dir).unwrap();
+        let b = save_screenshot(&fb, &dir).unwrap();
+        assert_ne!(a, b);
+        let (w, h, rgba) = decode_png_rgba(&std::fs::read(&a).unwrap()).unwrap();
+        assert_eq!((w, h), (128 * SCREENSHOT_SCALE, 128 * SCREENSHOT_SCALE));
+        assert_eq!(rgba[..4], palette::rgba(12));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -37,6 +37,22 @@ pub struct Framebuffer {
     cursor_y: i32,
 }
 
+impl Clone for Framebuffer {
+    fn clone(&self) -> Self {
+        Self {
+            pixels: self.pixels.clone(),
+            ..*self
+        }
+    }
+
+    /// Reuses the pixel buffer: the VM copies the screen every frame.
+    fn clone_from(&mut self, source: &Self) {
+        let mut pixels = std::mem::take(&mut self.pixels);
+        pixels.clone_from(&source.pixels);
+        *self = Self { pixels, ..*source };
+    }
+}
+
 impl Default for Framebuffer {
     fn default() -> Self {
         Self::new()
@@ -1,4 +1,4 @@
-//! Game controller state: the classic 6-button pad.
+//! Game controller state: two classic 6-button pads (the second for netplay).
 //!
 //! Carts see input only through `btn`/`btnp`. The host maps physical keys
 //! to these buttons (arrows + Z/X by default) and ticks this state once
@@ -20,65 +20,85 @@ pub enum Button {
 
 pub const BUTTON_COUNT: usize = 6;
 
+/// Pads the console reads: the local player plus a second one (netplay).
+pub const PLAYERS: usize = 2;
+
+/// ABI button indices are `player * PLAYER_STRIDE + button`, the classic
+/// `btn(b, p)` layout, so player 2's Left is 8.
+pub const PLAYER_STRIDE: u32 = 8;
+
 /// Frames a button must be held before `btnp` starts repeating.
 const REPEAT_DELAY: u32 = 15;
 /// Repeat interval in frames once repeating.
 const REPEAT_EVERY: u32 = 4;
 
 /// Per-frame button state with press/repeat tracking.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct InputState {
-    held: [bool; BUTTON_COUNT],
-    frames_held: [u32; BUTTON_COUNT],
+    held: [[bool; BUTTON_COUNT]; PLAYERS],
+    frames_held: [[u32; BUTTON_COUNT]; PLAYERS],
+}
+
+/// Split an ABI button index into `(player, button)`, if it names one.
+fn slot(b: u32) -> Option<(usize, usize)> {
+    let (p, i) = ((b / PLAYER_STRIDE) as usize, (b % PLAYER_STRIDE) as usize);
+    (p < PLAYERS && i < BUTTON_COUNT).then_some((p, i))
 }
 
 impl InputState {
-    /// Update the raw held state of a button (called on key events).
+    /// Update the raw held state of a player-1 button (called on key events).
     pub fn set_button(&mut self, b: usize, down: bool) {
         if b < BUTTON_COUNT {
-            self.held[b] = down;
+            self.held[0][b] = down;
+        }
+    }
+
+    /// Set every button of `player` (0-based) from a held mask (bit `i` ==
+    /// button `i`), as netplay does for both pads each frame.
+    pub fn set_player_mask(&mut self, player: usize, mask: u8) {
+        if let Some(held) = self.held.get_mut(player) {
+            for (i, h) in held.iter_mut().enumerate() {
+                *h = mask & (1 << i) != 0;
+            }
         }
     }
 
     /// Advance one logical frame. Must be called exactly once per update.
     pub fn tick(&mut self) {
-        for i in 0..BUTTON_COUNT {
-            if self.held[i] {
-                self.frames_held[i] = self.frames_held[i].saturating_add(1);
-            } else {
-                self.frames_held[i] = 0;
+        for (held, frames) in self.held.iter().zip(&mut self.frames_held) {
+            for (&h, f) in held.iter().zip(frames.iter_mut()) {
+                *f = if h { f.saturating_add(1) } else { 0 };
             }
         }
     }
 
     /// Is the button currently held?
     pub fn btn(&self, b: u32) -> bool {
-        (b as usize) < BUTTON_COUNT && self.held[b as usize]
+        slot(b).is_some_and(|(p, i)| self.held[p][i])
     }
 
     /// Was the button just pressed this frame? Repeats after a short delay
     /// while held, matching the classic `btnp` feel.
     pub fn btnp(&self, b: u32) -> bool {
-        let i = b as usize;
-        if i >= BUTTON_COUNT {
+        let Some((p, i)) = slot(b) else {
             return false;
-        }
-        let f = self.frames_held[i];
+        };
+        let f = self.frames_held[p][i];
         f == 1 || (f > REPEAT_DELAY && (f - REPEAT_DELAY) % REPEAT_EVERY == 1)
     }
 
-    /// Bitmask of all currently-held buttons (bit `i` == button `i`).
+    /// Bitmask of player 1's currently-held buttons (bit `i` == button `i`).
     pub fn btn_mask(&self) -> u32 {
         let mut mask = 0;
         for i in 0..BUTTON_COUNT {
-            if self.held[i] {
+            if self.held[0][i] {
                 mask |= 1 << i;
             }
         }
         mask
     }
 
-    /// Bitmask of all buttons that fired this frame, with repeat
+    /// Bitmask of player 1's buttons that fired this frame, with repeat
     /// (bit `i` == button `i`), matching `btnp`.
     pub fn btnp_mask(&self) -> u32 {
         let mut mask = 0;
@@ -92,8 +112,7 @@ impl InputState {
 
     /// Clear all held buttons (e.g. when leaving run mode).
     pub fn clear(&mut self) {
-        self.held = [false; BUTTON_COUNT];
-        self.frames_held = [0; BUTTON_COUNT];
+        *self = Self::default();
     }
 }
 
@@ -148,6 +167,17 @@ mod tests {
         assert_eq!(s.btn_mask(), 0b10_0001); // bit 0 Left, bit 5 X
     }
 
+    #[test]
+    fn second_player_reads_at_stride_eight() {
+        let mut s = InputState::default();
+        s.set_player_mask(1, 1 << Button::Left as u8);
+        s.tick();
+        assert!(s.btn(PLAYER_STRIDE) && s.btnp(PLAYER_STRIDE));
+        assert!(!s.btn(0), "player 1 is untouched");
+        assert_eq!(s.btn_mask(), 0, "masks stay player 1 only");
+        assert!(!s.btn(PLAYER_STRIDE + 6) && !s.btn(2 * PLAYER_STRIDE));
+    }
+
     #[test]
     fn btnp_mask_fires_then_clears() {
         let mut s = InputState::default();
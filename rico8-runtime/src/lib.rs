@@ -12,13 +12,22 @@
 
 pub mod assets;
 pub mod audio;
+pub mod backtrace;
 pub mod cart;
 pub mod clipboard;
 pub mod fb;
 pub mod font;
 pub mod input;
+pub mod live;
+pub mod log;
+pub mod memmap;
+pub mod netplay;
 pub mod palette;
+pub mod perf;
 pub mod pico8;
 pub mod project;
+pub mod script;
+pub mod speed;
 pub mod ui;
 pub mod vm;
+pub mod wav;
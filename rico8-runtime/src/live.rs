@@ -0,0 +1,114 @@
+//! Live asset edits for a running cart.
+//!
+//! A cart draws from its own copies of the sprite sheet and map, taken at
+//! [`crate::vm::GameVm::load`], and the synth plays from its own copy of the
+//! SFX and music. [`LiveAssets`] pushes what the editors change into those
+//! copies while the cart runs, so tuning a sprite, tile or sound shows up
+//! without a restart.
+//!
+//! Only the pixels, flags and tiles the editors changed since the last push
+//! are written, so whatever the cart changed itself with `sset`, `fset` or
+//! `mset` elsewhere stays as the cart left it. [`LiveAssets::reset`] hands
+//! over the editors' sheet and map whole instead.
+
+use crate::{assets::Assets, vm::HostState};
+
+/// The loaded assets as last pushed into a running cart.
+pub struct LiveAssets {
+    pushed: Assets,
+}
+
+impl LiveAssets {
+    /// Track a cart that was just loaded from `assets`.
+    pub fn new(assets: &Assets) -> Self {
+        Self {
+            pushed: assets.clone(),
+        }
+    }
+
+    /// Push the edits made to `edited` since the last push into the cart's
+    /// copies in `st`, and the SFX and music into the synth when they
+    /// changed. Returns whether anything was pushed.
+    pub fn push(&mut self, edited: &Assets, st: &mut HostState) -> bool {
+        let pushed = &self.pushed;
+        let mut changed = merge(
+            &pushed.sprites.pixels,
+            &edited.sprites.pixels,
+            &mut st.sprites.pixels,
+        );
+        changed |= merge(
+            &pushed.sprites.flags,
+            &edited.sprites.flags,
+            &mut st.sprites.flags,
+        );
+        changed |= merge(&pushed.map.tiles, &edited.map.tiles, &mut st.map.tiles);
+        if edited.sfx != pushed.sfx || edited.music != pushed.music {
+            // Playing voices keep the SFX they started with; the next note
+            // the cart or the music sequencer starts is the edited one.
+            st.audio.load(edited.sfx.clone(), edited.music.clone());
+            changed = true;
+        }
+        if changed {
+            self.pushed = edited.clone();
+        }
+        changed
+    }
+
+    /// Give the cart `edited`'s sheet and map whole, dropping its own
+    /// runtime edits to them, along with the SFX and music.
+    pub fn reset(&mut self, edited: &Assets, st: &mut HostState) {
+        st.sprites = edited.sprites.clone();
+        st.map = edited.map.clone();
+        st.audio.load(edited.sfx.clone(), edited.music.clone());
+        self.pushed = edited.clone();
+    }
+}
+
+/// Copy every byte that differs between `before` and `after` into `live`.
+fn merge(before: &[u8], after: &[u8], live: &mut [u8]) -> bool {
+    let mut changed = false;
+    for ((b, a), l) in before.iter().zip(after).zip(live) {
+        if a != b {
+            *l = *a;
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioHandle;
+
+    #[test]
+    fn edits_land_without_undoing_the_carts_own() {
+        let mut assets = Assets::default();
+        let mut st = HostState::new(&assets, AudioHandle::dummy());
+        let mut live = LiveAssets::new(&assets);
+        assert!(!live.push(&assets, &mut st), "nothing edited yet");
+
+        // The cart draws on its sheet and map while it runs.
+        st.sprites.set(0, 0, 8);
+        st.map.set(3, 3, 4);
+        // Meanwhile the editors change other pixels and tiles.
+        assets.sprites.set(1, 0, 12);
+        assets.sprites.set_flag(2, 0, true);
+        assets.map.set(5, 5, 9);
+        assert!(live.push(&assets, &mut st));
+        assert_eq!(st.sprites.get(1, 0), 12);
+        assert_eq!(st.sprites.flags(2), 1);
+        assert_eq!(st.map.get(5, 5), 9);
+        assert_eq!(st.sprites.get(0, 0), 8, "the cart's sset stays");
+        assert_eq!(st.map.get(3, 3), 4, "the cart's mset stays");
+
+        // An editor stroke over a cell the cart changed wins.
+        assets.map.set(3, 3, 1);
+        assert!(live.push(&assets, &mut st));
+        assert_eq!(st.map.get(3, 3), 1);
+        assert!(!live.push(&assets, &mut st), "already pushed");
+
+        live.reset(&assets, &mut st);
+        assert_eq!(st.sprites.get(0, 0), 0, "reset drops the cart's edits");
+    }
+}
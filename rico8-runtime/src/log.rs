@@ -0,0 +1,94 @@
+//! Cart log lines: what a cart reports through `log` and `log_level`,
+//! stamped by the host with the frame it arrived on.
+
+use std::fmt;
+
+/// How serious a log line is, mirroring the SDK's `LogLevel`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Every level, least serious first.
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    /// The level a cart passed to `log_level`. Out-of-range values clamp to
+    /// the nearest end rather than losing the line.
+    pub fn from_abi(level: i32) -> LogLevel {
+        Self::ALL[level.clamp(0, 3) as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// A level by name, as [`LogLevel::name`] spells it.
+    pub fn parse(s: &str) -> Option<LogLevel> {
+        Self::ALL
+            .into_iter()
+            .find(|l| l.name().eq_ignore_ascii_case(s))
+    }
+
+    /// The next level up, wrapping from error back to debug.
+    pub fn next(self) -> LogLevel {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// One line of a cart's log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// The cart's frame counter when it logged the line.
+    pub frame: u64,
+    pub level: LogLevel,
+    pub text: String,
+}
+
+/// `frame level text`, the form `savelog` writes: `  120 warn  low hp`.
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>5} {:<5} {}",
+            self.frame,
+            self.level.name(),
+            self.text
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_clamp_parse_and_cycle() {
+        assert_eq!(LogLevel::from_abi(2), LogLevel::Warn);
+        assert_eq!(LogLevel::from_abi(-1), LogLevel::Debug);
+        assert_eq!(LogLevel::from_abi(9), LogLevel::Error);
+        assert_eq!(LogLevel::parse("WARN"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::parse("loud"), None);
+        assert_eq!(LogLevel::Error.next(), LogLevel::Debug);
+        let line = LogLine {
+            frame: 120,
+            level: LogLevel::Warn,
+            text: "low hp".into(),
+        };
+        assert_eq!(line.to_string(), "  120 warn  low hp");
+    }
+}
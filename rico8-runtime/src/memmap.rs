@@ -0,0 +1,252 @@
+//! Where things live in a cart's linear memory: stack, data, bss and heap.
+//!
+//! Rust carts are linked `--stack-first`: the shadow stack fills the bottom
+//! of memory and grows down from `__stack_pointer`'s initial value, the
+//! data segments come next, zeroed statics (bss) run on to `__data_end`,
+//! and the allocator's heap starts at `__heap_base`. All of it is read from
+//! the module itself, so a cart missing some of it (hand-written wasm, a
+//! stripped name section) just has fewer regions.
+
+use std::ops::Range;
+use wasmparser::{
+    DataKind, ExternalKind, KnownCustom, Name, Operator, Parser, Payload, TypeRef, ValType,
+};
+
+/// What an address in linear memory holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Stack,
+    /// Initialized statics, from the data segments.
+    Data,
+    /// Zeroed statics, between the data segments and `__data_end`.
+    Bss,
+    Heap,
+    /// None of the above, or a cart that does not say.
+    Unknown,
+}
+
+impl Region {
+    pub fn name(self) -> &'static str {
+        match self {
+            Region::Stack => "stack",
+            Region::Data => "data",
+            Region::Bss => "bss",
+            Region::Heap => "heap",
+            Region::Unknown => "?",
+        }
+    }
+}
+
+/// The regions of a cart's memory, from its module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+    pub stack: Option<Range<u32>>,
+    pub data: Option<Range<u32>>,
+    pub bss: Option<Range<u32>>,
+    /// Runs from here to the end of memory.
+    pub heap_base: Option<u32>,
+}
+
+impl MemoryMap {
+    /// Read the map from a module. Anything it cannot find is left out.
+    pub fn parse(wasm: &[u8]) -> Self {
+        let mut imported_globals = 0;
+        // Initial values of the module's own `i32` globals, by global index,
+        // and whether each is mutable.
+        let mut globals: Vec<(Option<u32>, bool)> = Vec::new();
+        let mut exports: Vec<(String, u32)> = Vec::new();
+        let mut stack_global = None;
+        let mut data: Option<Range<u32>> = None;
+        for payload in Parser::new(0).parse_all(wasm) {
+            let Ok(payload) = payload else {
+                return Self::default();
+            };
+            match payload {
+                Payload::ImportSection(reader) => {
+                    for import in reader.into_imports().flatten() {
+                        if let TypeRef::Global(_) = import.ty {
+                            imported_globals += 1;
+                        }
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader.into_iter().flatten() {
+                        let value = (global.ty.content_type == ValType::I32)
+                            .then(|| const_i32(&global.init_expr))
+                            .flatten();
+                        globals.push((value, global.ty.mutable));
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader.into_iter().flatten() {
+                        if export.kind == ExternalKind::Global {
+                            exports.push((export.name.to_string(), export.index));
+                        }
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for segment in reader.into_iter().flatten() {
+                        let DataKind::Active { offset_expr, .. } = segment.kind else {
+                            continue;
+                        };
+                        let Some(start) = const_i32(&offset_expr) else {
+                            continue;
+                        };
+                        let end = start.saturating_add(segment.data.len() as u32);
+                        data = Some(match data {
+                            Some(d) => d.start.min(start)..d.end.max(end),
+                            None => start..end,
+                        });
+                    }
+                }
+                Payload::CustomSection(section) => {
+                    let KnownCustom::Name(reader) = section.as_known() else {
+                        continue;
+                    };
+                    for sub in reader.into_iter().flatten() {
+                        if let Name::Global(map) = sub {
+                            for naming in map.into_iter().flatten() {
+                                if naming.name == "__stack_pointer" {
+                                    stack_global = Some(naming.index);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let global = |index: u32| {
+            let own = index.checked_sub(imported_globals)?;
+            globals.get(own as usize).copied()
+        };
+        let exported = |name: &str| {
+            let (_, index) = exports.iter().find(|(n, _)| n == name)?;
+            global(*index)?.0
+        };
+        // Without a name section, the stack pointer is the module's first
+        // mutable `i32` global, as LLVM emits it.
+        let stack_top = match stack_global {
+            Some(index) => global(index).and_then(|(value, _)| value),
+            None => globals
+                .iter()
+                .find_map(|&(value, mutable)| value.filter(|_| mutable)),
+        };
+        let data_end = exported("__data_end");
+        let heap_base = exported("__heap_base");
+
+        let bss = match (&data, data_end) {
+            (Some(d), Some(end)) if end > d.end => Some(d.end..end),
+            _ => None,
+        };
+        // Stack-first, the stack sits below everything; otherwise it sits
+        // between the statics and the heap.
+        let statics_end = data_end.or(data.as_ref().map(|d| d.end));
+        let stack = stack_top.map(|top| match statics_end {
+            Some(end) if end <= top => end..top,
+            _ => 0..top,
+        });
+        Self {
+            stack,
+            data,
+            bss,
+            heap_base,
+        }
+    }
+
+    /// The region `addr` falls in.
+    pub fn region(&self, addr: u32) -> Region {
+        let within = |range: &Option<Range<u32>>| range.as_ref().is_some_and(|r| r.contains(&addr));
+        if within(&self.stack) {
+            Region::Stack
+        } else if within(&self.data) {
+            Region::Data
+        } else if within(&self.bss) {
+            Region::Bss
+        } else if self.heap_base.is_some_and(|base| addr >= base) {
+            Region::Heap
+        } else {
+            Region::Unknown
+        }
+    }
+
+    /// The first address of each region present, lowest first.
+    pub fn starts(&self) -> Vec<(u32, Region)> {
+        let mut starts: Vec<(u32, Region)> = [
+            self.stack.as_ref().map(|r| (r.start, Region::Stack)),
+            self.data.as_ref().map(|r| (r.start, Region::Data)),
+            self.bss.as_ref().map(|r| (r.start, Region::Bss)),
+            self.heap_base.map(|base| (base, Region::Heap)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        starts.sort_by_key(|&(addr, _)| addr);
+        starts
+    }
+}
+
+/// The value of a constant expression that is a lone `i32.const`.
+fn const_i32(expr: &wasmparser::ConstExpr<'_>) -> Option<u32> {
+    match expr.get_operators_reader().read().ok()? {
+        Operator::I32Const { value } => Some(value as u32),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Laid out like a `--stack-first` Rust cart: 4K of stack, then data,
+    /// bss and heap.
+    const RUST_LAYOUT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $__stack_pointer (mut i32) (i32.const 4096))
+          (global (export "__data_end") i32 (i32.const 4200))
+          (global (export "__heap_base") i32 (i32.const 4208))
+          (data (i32.const 4096) "hello")
+          (data (i32.const 4150) "world")
+          (func (export "rico8_init"))
+          (func (export "rico8_update"))
+          (func (export "rico8_draw")))
+    "#;
+
+    #[test]
+    fn reads_a_rust_carts_layout() {
+        let map = MemoryMap::parse(&wat::parse_str(RUST_LAYOUT).unwrap());
+        assert_eq!(map.stack, Some(0..4096));
+        assert_eq!(map.data, Some(4096..4155));
+        assert_eq!(map.bss, Some(4155..4200));
+        assert_eq!(map.heap_base, Some(4208));
+        assert_eq!(map.region(100), Region::Stack);
+        assert_eq!(map.region(4096), Region::Data);
+        assert_eq!(map.region(4199), Region::Bss);
+        assert_eq!(map.region(4204), Region::Unknown, "alignment padding");
+        assert_eq!(map.region(60_000), Region::Heap);
+        assert_eq!(
+            map.starts(),
+            [
+                (0, Region::Stack),
+                (4096, Region::Data),
+                (4155, Region::Bss),
+                (4208, Region::Heap)
+            ]
+        );
+    }
+
+    #[test]
+    fn hand_written_carts_have_no_regions() {
+        let wasm = wat::parse_str(
+            r#"(module (memory (export "memory") 1)
+                 (func (export "rico8_init")) (func (export "rico8_update"))
+                 (func (export "rico8_draw")))"#,
+        )
+        .unwrap();
+        let map = MemoryMap::parse(&wasm);
+        assert_eq!(map, MemoryMap::default());
+        assert_eq!(map.region(0), Region::Unknown);
+    }
+}
@@ -0,0 +1,559 @@
+//! Two-player rollback netplay over UDP.
+//!
+//! Each side runs every frame as soon as its own input is in, predicting
+//! that the remote player still holds whatever they held last. When the
+//! real input arrives and differs, the VM rewinds to the snapshot taken
+//! before the mispredicted frame and re-runs to the present with the right
+//! buttons. RICO-8 suits this unusually well: a snapshot is at most 128K of
+//! memory plus the screen, and fuel-bounded frames re-run fast and exactly.
+//!
+//! [`Rollback`] is the transport-free core; [`Session`] carries it over a
+//! UDP socket. Both peers run the same cart as players 1 and 2; nothing but
+//! button masks and state hashes crosses the wire.
+
+use crate::vm::{GameVm, RuntimeError, VmSnapshot};
+use anyhow::{anyhow, Context as _, Result};
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+};
+
+/// Frames the local player's input is scheduled ahead, hiding most of the
+/// round trip so rollbacks stay short.
+pub const INPUT_DELAY: u32 = 2;
+
+/// How far the simulation may run past the last confirmed remote input
+/// before it waits for the peer. Bounds rollback length and snapshot memory.
+pub const MAX_PREDICTION: u32 = 8;
+
+/// Confirmed-frame state hashes kept for comparison with the peer's.
+const HASH_HISTORY: usize = 64;
+
+/// Most inputs resent in one packet (everything the peer hasn't acked).
+const MAX_INPUTS_PER_PACKET: usize = 128;
+
+const MAGIC: &[u8; 4] = b"R8NP";
+
+/// The rollback bookkeeping for one side of a two-player session.
+pub struct Rollback {
+    /// This side's pad: 0 for player 1, 1 for player 2.
+    local: usize,
+    /// Frames run so far, i.e. the next frame to run.
+    frame: u32,
+    /// Local buttons per frame, starting with `INPUT_DELAY` idle frames.
+    local_inputs: Vec<u8>,
+    /// Remote buttons per frame, contiguous from frame 0.
+    remote_inputs: Vec<u8>,
+    /// The remote buttons each frame last ran with, guessed or confirmed.
+    used_remote: Vec<u8>,
+    /// State before each frame that ran on a guess, oldest first.
+    snapshots: VecDeque<(u32, VmSnapshot)>,
+    /// Earliest frame found to have run on a wrong guess.
+    rollback_to: Option<u32>,
+    /// `(frame, state hash)` once a frame's outcome is final, in order.
+    hashes: VecDeque<(u32, u32)>,
+    /// Frames re-run by rollbacks so far.
+    pub resimulated: u64,
+}
+
+impl Rollback {
+    /// `local` is this side's player index: 0 or 1.
+    pub fn new(local: usize) -> Self {
+        Self {
+            local: local.min(1),
+            frame: 0,
+            local_inputs: vec![0; INPUT_DELAY as usize],
+            remote_inputs: Vec::new(),
+            used_remote: Vec::new(),
+            snapshots: VecDeque::new(),
+            rollback_to: None,
+            hashes: VecDeque::new(),
+            resimulated: 0,
+        }
+    }
+
+    /// Frames run so far.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Frames whose remote input is known.
+    pub fn confirmed(&self) -> u32 {
+        self.remote_inputs.len() as u32
+    }
+
+    /// Queue this tick's local buttons for frame `frame + INPUT_DELAY`.
+    /// Ignored while stalled on the peer, so waiting doesn't add delay.
+    pub fn add_local(&mut self, buttons: u8) {
+        if self.local_inputs.len() as u32 <= self.frame + INPUT_DELAY {
+            self.local_inputs.push(buttons);
+        }
+    }
+
+    /// Local inputs from `start` on, for sending.
+    pub fn local_inputs_from(&self, start: u32) -> &[u8] {
+        self.local_inputs.get(start as usize..).unwrap_or(&[])
+    }
+
+    /// Record the remote buttons for `frame`. Inputs must arrive in order;
+    /// repeats and gaps are dropped (the sender resends until acked).
+    pub fn add_remote(&mut self, frame: u32, buttons: u8) {
+        if frame != self.confirmed() {
+            return;
+        }
+        self.remote_inputs.push(buttons);
+        if frame < self.frame && self.used_remote[frame as usize] != buttons {
+            self.rollback_to = Some(self.rollback_to.map_or(frame, |r| r.min(frame)));
+        }
+    }
+
+    /// Fix any misprediction, then run the next frame if the prediction
+    /// window allows. `Ok(false)` means waiting on the peer.
+    pub fn advance(&mut self, vm: &mut GameVm) -> std::result::Result<bool, RuntimeError> {
+        let confirmed = self.confirmed();
+        // A snapshot before frame `g` holds frame `g - 1`'s outcome, final
+        // once that frame is confirmed and not about to be rolled back.
+        let settled = self.rollback_to.map_or(confirmed, |r| r.min(confirmed));
+        let finished: Vec<_> = self
+            .snapshots
+            .iter()
+            .filter(|(g, _)| (1..=settled).contains(g))
+            .map(|(g, snap)| (g - 1, snap.hash()))
+            .collect();
+        for (f, hash) in finished {
+            self.record_hash(f, hash);
+        }
+
+        if let Some(target) = self.rollback_to.take() {
+            let i = self
+                .snapshots
+                .iter()
+                .position(|(f, _)| *f == target)
+                .ok_or_else(|| netplay_error(format!("no snapshot for frame {target}")))?;
+            vm.restore(&self.snapshots[i].1)
+                .map_err(|e| netplay_error(format!("{e:#}")))?;
+            self.snapshots.truncate(i);
+            let end = std::mem::replace(&mut self.frame, target);
+            vm.state_mut().replaying = true;
+            let result = (target..end).try_for_each(|_| self.run_frame(vm));
+            vm.state_mut().replaying = false;
+            result?;
+            self.resimulated += (end - target) as u64;
+        }
+        // Frames before the confirmed line can never be rolled back to.
+        while self.snapshots.front().is_some_and(|(f, _)| *f < confirmed) {
+            self.snapshots.pop_front();
+        }
+
+        let has_local = (self.frame as usize) < self.local_inputs.len();
+        let ran = has_local && self.frame < confirmed + MAX_PREDICTION;
+        if ran {
+            self.run_frame(vm)?;
+        }
+        // The newest frame may have settled without ever being re-run.
+        if self.frame > 0 && self.frame - 1 < confirmed {
+            self.record_hash(self.frame - 1, vm.state_hash());
+        }
+        Ok(ran)
+    }
+
+    fn run_frame(&mut self, vm: &mut GameVm) -> std::result::Result<(), RuntimeError> {
+        let f = self.frame;
+        let remote = match self.remote_inputs.get(f as usize) {
+            Some(&b) => b,
+            None => {
+                self.snapshots.push_back((f, vm.snapshot()));
+                self.remote_inputs.last().copied().unwrap_or(0)
+            }
+        };
+        match self.used_remote.get_mut(f as usize) {
+            Some(used) => *used = remote,
+            None => self.used_remote.push(remote),
+        }
+        let local = self.local_inputs[f as usize];
+        let input = &mut vm.state_mut().input;
+        input.set_player_mask(self.local, local);
+        input.set_player_mask(1 - self.local, remote);
+        vm.call_update().and_then(|()| vm.call_draw())?;
+        self.frame += 1;
+        Ok(())
+    }
+
+    /// Keep a settled frame's hash; each frame is recorded once, in order.
+    fn record_hash(&mut self, frame: u32, hash: u32) {
+        if self.hashes.back().is_some_and(|&(f, _)| f >= frame) {
+            return;
+        }
+        if self.hashes.len() == HASH_HISTORY {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back((frame, hash));
+    }
+
+    /// The newest settled `(frame, hash)`, for the peer to compare.
+    pub fn latest_hash(&self) -> Option<(u32, u32)> {
+        self.hashes.back().copied()
+    }
+
+    /// Compare the peer's hash for `frame` with ours, if we still have it.
+    pub fn check_hash(&self, frame: u32, hash: u32) -> std::result::Result<(), RuntimeError> {
+        match self.hashes.iter().find(|(f, _)| *f == frame) {
+            Some(&(_, ours)) if ours != hash => Err(netplay_error(format!(
+                "desync at frame {frame}\n(the carts diverged)"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn netplay_error(message: String) -> RuntimeError {
+    RuntimeError {
+        phase: "netplay",
+        message,
+        backtrace: Vec::new(),
+        location: None,
+    }
+}
+
+/// One side of a session: a [`Rollback`] plus the UDP link to the peer.
+///
+/// Every tick sends all local inputs the peer hasn't acknowledged, so a
+/// lost packet costs nothing but a slightly later confirmation.
+pub struct Session {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    /// CRC-32 of the cart's wasm; a peer running anything else is refused.
+    cart_id: u32,
+    pub rollback: Rollback,
+    /// Local inputs the peer has confirmed.
+    peer_ack: u32,
+    /// A packet has arrived from the peer.
+    connected: bool,
+}
+
+impl Session {
+    /// Play as `player` (1 or 2) from `bind` against the peer at `peer`.
+    pub fn new(
+        player: u8,
+        bind: impl ToSocketAddrs,
+        peer: impl ToSocketAddrs,
+        wasm: &[u8],
+    ) -> Result<Self> {
+        if !(1..=2).contains(&player) {
+            anyhow::bail!("player must be 1 or 2, got {player}");
+        }
+        let socket = UdpSocket::bind(bind).context("Binding the netplay socket")?;
+        socket.set_nonblocking(true)?;
+        let peer = peer
+            .to_socket_addrs()
+            .context("Resolving the peer address")?
+            .next()
+            .ok_or_else(|| anyhow!("Peer address resolves to nothing"))?;
+        Ok(Self {
+            socket,
+            peer,
+            cart_id: crc32fast::hash(wasm),
+            rollback: Rollback::new(player as usize - 1),
+            peer_ack: 0,
+            connected: false,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Whether the peer has been heard from yet.
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    /// One display frame: take in the peer's packets, queue `buttons`, run
+    /// a frame if the prediction window allows, and report back to the
+    /// peer. `Ok(false)` means the game is waiting on the peer this tick.
+    pub fn tick(
+        &mut self,
+        vm: &mut GameVm,
+        buttons: u8,
+    ) -> std::result::Result<bool, RuntimeError> {
+        self.receive()?;
+        self.rollback.add_local(buttons);
+        let ran = self.rollback.advance(vm)?;
+        self.send();
+        Ok(ran)
+    }
+
+    fn receive(&mut self) -> std::result::Result<(), RuntimeError> {
+        let mut buf = [0u8; 512];
+        loop {
+            let (n, from) = match self.socket.recv_from(&mut buf) {
+                Ok(got) => got,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                // ICMP port-unreachable from a peer that isn't up yet.
+                Err(e) if e.kind() == ErrorKind::ConnectionReset => continue,
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => continue,
+                Err(e) => return Err(netplay_error(format!("receive failed: {e}"))),
+            };
+            if from != self.peer {
+                continue;
+            }
+            let Some(packet) = Packet::decode(&buf[..n]) else {
+                continue;
+            };
+            if packet.cart_id != self.cart_id {
+                return Err(netplay_error(
+                    "the peer is running a different cart".to_string(),
+                ));
+            }
+            self.connected = true;
+            self.peer_ack = self.peer_ack.max(packet.ack);
+            for (i, &b) in packet.inputs.iter().enumerate() {
+                self.rollback.add_remote(packet.start + i as u32, b);
+            }
+            if let Some((frame, hash)) = packet.hash {
+                self.rollback.check_hash(frame, hash)?;
+            }
+        }
+    }
+
+    fn send(&self) {
+        let inputs = self.rollback.local_inputs_from(self.peer_ack);
+        let packet = Packet {
+            cart_id: self.cart_id,
+            ack: self.rollback.confirmed(),
+            start: self.peer_ack,
+            hash: self.rollback.latest_hash(),
+            inputs: &inputs[..inputs.len().min(MAX_INPUTS_PER_PACKET)],
+        };
+        // Best effort: a dropped packet is covered by the next one.
+        let _ = self.socket.send_to(&packet.encode(), self.peer);
+    }
+}
+
+/// The one message type: `R8NP`, cart id, ack, first input's frame, the
+/// newest confirmed hash (frame `u32::MAX` for none), then the inputs.
+/// Little-endian throughout.
+struct Packet<'a> {
+    cart_id: u32,
+    ack: u32,
+    start: u32,
+    hash: Option<(u32, u32)>,
+    inputs: &'a [u8],
+}
+
+impl<'a> Packet<'a> {
+    const HEADER: usize = 4 + 4 * 5 + 1;
+
+    fn encode(&self) -> Vec<u8> {
+        let (hash_frame, hash) = self.hash.unwrap_or((u32::MAX, 0));
+        let mut out = Vec::with_capacity(Self::HEADER + self.inputs.len());
+        out.extend_from_slice(MAGIC);
+        for v in [self.cart_id, self.ack, self.start, hash_frame, hash] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out.push(self.inputs.len() as u8);
+        out.extend_from_slice(self.inputs);
+        out
+    }
+
+    fn decode(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.len() < Self::HEADER || &bytes[..4] != MAGIC {
+            return None;
+        }
+        let word = |i: usize| u32::from_le_bytes(bytes[4 + 4 * i..8 + 4 * i].try_into().unwrap());
+        let count = bytes[Self::HEADER - 1] as usize;
+        let inputs = bytes.get(Self::HEADER..Self::HEADER + count)?;
+        Some(Self {
+            cart_id: word(0),
+            ack: word(1),
+            start: word(2),
+            hash: (word(3) != u32::MAX).then(|| (word(3), word(4))),
+            inputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assets::Assets, audio::AudioHandle};
+
+    /// Each player's Right moves their own counter in memory; player 2's
+    /// counter moves twice as fast so the two can't be mixed up. Both are
+    /// drawn as pixels so the screen reflects the state too.
+    const TWO_PLAYER_CART: &str = r#"
+        (module
+          (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+          (import "rico8" "clear" (func $cls (param i32)))
+          (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+          (memory (export "memory") 1)
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (if (call $btn (i32.const 1))
+              (then (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))))
+            (if (call $btn (i32.const 9))
+              (then (i32.store (i32.const 4) (i32.add (i32.load (i32.const 4)) (i32.const 2))))))
+          (func (export "rico8_draw")
+            (call $cls (i32.const 0))
+            (call $pset (i32.load (i32.const 0)) (i32.const 0) (i32.const 7))
+            (call $pset (i32.load (i32.const 4)) (i32.const 1) (i32.const 8))))
+    "#;
+
+    fn wasm() -> Vec<u8> {
+        wat::parse_str(TWO_PLAYER_CART).unwrap()
+    }
+
+    fn vm() -> GameVm {
+        GameVm::load(&wasm(), &Assets::default(), AudioHandle::dummy()).unwrap()
+    }
+
+    fn counters(vm: &GameVm) -> (u32, u32) {
+        let fb = &vm.state().fb;
+        let find = |y, c| (0..128).find(|&x| fb.pget(x, y) == c).unwrap_or(-1) as u32;
+        (find(0, 7), find(1, 8))
+    }
+
+    /// Player 1 holds Right for frames 0..20; player 2 from frame 10 on.
+    fn buttons(player: usize, frame: u32) -> u8 {
+        let held = if player == 0 { frame < 20 } else { frame >= 10 };
+        if held {
+            1 << 1
+        } else {
+            0
+        }
+    }
+
+    #[test]
+    fn late_remote_input_rolls_back_to_the_true_timeline() {
+        // Player 1 runs ahead on guesses while player 2's input trickles in
+        // four frames late; the end state must match a run with no latency.
+        let mut vm = vm();
+        let mut rb = Rollback::new(0);
+        for tick in 0..40u32 {
+            rb.add_local(buttons(0, tick));
+            if tick >= 4 {
+                let f = tick - 4;
+                rb.add_remote(
+                    f,
+                    if f < INPUT_DELAY {
+                        0
+                    } else {
+                        buttons(1, f - INPUT_DELAY)
+                    },
+                );
+            }
+            rb.advance(&mut vm).unwrap();
+        }
+        // Catch up on the rest of the remote input and settle.
+        for f in 36..rb.frame() {
+            rb.add_remote(f, buttons(1, f - INPUT_DELAY));
+        }
+        rb.advance(&mut vm).unwrap();
+        assert!(rb.resimulated > 0, "the late press forced a rollback");
+
+        let mut truth = self::vm();
+        for f in 0..rb.frame() {
+            let (p1, p2) = if f < INPUT_DELAY {
+                (0, 0)
+            } else {
+                (buttons(0, f - INPUT_DELAY), buttons(1, f - INPUT_DELAY))
+            };
+            truth.state_mut().input.set_player_mask(0, p1);
+            truth.state_mut().input.set_player_mask(1, p2);
+            truth
+                .call_update()
+                .and_then(|()| truth.call_draw())
+                .unwrap();
+        }
+        assert_eq!(counters(&vm), counters(&truth));
+        assert_eq!(vm.state_hash(), truth.state_hash());
+    }
+
+    #[test]
+    fn stalls_when_the_peer_falls_too_far_behind() {
+        let mut vm = vm();
+        let mut rb = Rollback::new(0);
+        let mut ran = 0;
+        for _ in 0..30 {
+            rb.add_local(0);
+            ran += rb.advance(&mut vm).unwrap() as u32;
+        }
+        assert_eq!(
+            ran, MAX_PREDICTION,
+            "no remote input: run the window, then wait"
+        );
+        assert_eq!(
+            rb.local_inputs_from(0).len() as u32,
+            MAX_PREDICTION + INPUT_DELAY + 1
+        );
+    }
+
+    #[test]
+    fn desync_is_reported() {
+        let mut vm = vm();
+        let mut rb = Rollback::new(0);
+        rb.add_local(0);
+        rb.add_remote(0, 0);
+        rb.advance(&mut vm).unwrap();
+        let (frame, hash) = rb.latest_hash().unwrap();
+        assert!(rb.check_hash(frame, hash).is_ok());
+        let err = rb.check_hash(frame, hash ^ 1).unwrap_err();
+        assert!(err.message.contains("desync at frame 0"), "{err}");
+    }
+
+    #[test]
+    fn two_sessions_agree_over_loopback() {
+        let wasm = wasm();
+        let mut a = Session::new(1, "127.0.0.1:0", "127.0.0.1:9", &wasm).unwrap();
+        let mut b = Session::new(2, "127.0.0.1:0", a.local_addr().unwrap(), &wasm).unwrap();
+        a.peer = b.local_addr().unwrap();
+        let (mut vm_a, mut vm_b) = (vm(), vm());
+        for tick in 0..400u32 {
+            a.tick(&mut vm_a, buttons(0, tick)).unwrap();
+            b.tick(&mut vm_b, buttons(1, tick)).unwrap();
+            if a.rollback.frame() >= 60 && b.rollback.frame() >= 60 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(a.connected() && b.connected());
+        // Settle: stop feeding new frames once both have confirmed the same span.
+        let target = a.rollback.frame().min(b.rollback.frame());
+        assert!(target >= 60, "both sides kept running");
+        let hash_a = a.rollback.hashes.iter().find(|(f, _)| *f == target - 10);
+        let hash_b = b.rollback.hashes.iter().find(|(f, _)| *f == target - 10);
+        assert!(hash_a.is_some());
+        assert_eq!(hash_a, hash_b, "same state on both machines");
+    }
+
+    #[test]
+    fn a_different_cart_is_refused() {
+        let mut a = Session::new(1, "127.0.0.1:0", "127.0.0.1:9", &wasm()).unwrap();
+        let b = Session::new(2, "127.0.0.1:0", a.local_addr().unwrap(), b"other").unwrap();
+        a.peer = b.local_addr().unwrap();
+        b.send();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let err = a.tick(&mut vm(), 0).unwrap_err();
+        assert!(err.message.contains("different cart"), "{err}");
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let p = Packet {
+            cart_id: 7,
+            ack: 3,
+            start: 5,
+            hash: Some((4, 0xdead_beef)),
+            inputs: &[1, 2, 3],
+        };
+        let bytes = p.encode();
+        let q = Packet::decode(&bytes).unwrap();
+        assert_eq!(
+            (q.cart_id, q.ack, q.start, q.hash),
+            (7, 3, 5, Some((4, 0xdead_beef)))
+        );
+        assert_eq!(q.inputs, &[1, 2, 3]);
+        assert!(Packet::decode(&bytes[..bytes.len() - 1]).is_none());
+    }
+}
@@ -0,0 +1,256 @@
+//! A few seconds of per-frame performance history, and the graph panel the
+//! console's and player's stats overlays draw from it.
+//!
+//! Numbers alone only show the last frame, so a one-frame spike is gone
+//! before it can be read. The graph mode plots update CPU, draw CPU, fps and
+//! memory high-water as sparklines, newest on the right, and picks out the
+//! frame that cost the most CPU.
+
+use crate::{fb::Framebuffer, palette::col};
+use std::collections::VecDeque;
+
+/// Frames of history kept: three seconds at 60 fps, six at 30.
+pub const HISTORY: usize = 180;
+
+/// Frames folded into each sparkline column, keeping the worst of them.
+const FRAMES_PER_COL: usize = 2;
+const GRAPH_W: usize = HISTORY / FRAMES_PER_COL;
+const GRAPH_X: i32 = 15;
+/// Bar height at 100%.
+const GRAPH_H: i32 = 6;
+const ROW_H: i32 = 8;
+
+/// Height of the graph panel: four sparkline rows and the worst-frame line.
+pub const PANEL_H: i32 = 5 * ROW_H + 1;
+
+/// Which stats overlay is showing; F1 steps through them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsMode {
+    #[default]
+    Off,
+    /// The latest frame's numbers.
+    Numbers,
+    /// The last few seconds as sparklines.
+    Graphs,
+}
+
+impl StatsMode {
+    /// The next mode, wrapping from graphs back to off.
+    pub fn next(self) -> StatsMode {
+        match self {
+            StatsMode::Off => StatsMode::Numbers,
+            StatsMode::Numbers => StatsMode::Graphs,
+            StatsMode::Graphs => StatsMode::Off,
+        }
+    }
+}
+
+/// One frame's readings.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerfSample {
+    /// The cart's frame counter.
+    pub frame: u64,
+    /// Fractions (0.0..1.0) of the fuel budgets `update` and `draw` used.
+    pub cpu_update: f32,
+    pub cpu_draw: f32,
+    /// Measured frames per second.
+    pub fps: f32,
+    /// Memory high-water as a fraction of the 128 K cap.
+    pub mem: f32,
+}
+
+impl PerfSample {
+    fn cpu(&self) -> f32 {
+        self.cpu_update + self.cpu_draw
+    }
+}
+
+/// The last [`HISTORY`] frames' samples, oldest first.
+#[derive(Debug, Default)]
+pub struct PerfHistory {
+    samples: VecDeque<PerfSample>,
+}
+
+impl PerfHistory {
+    pub fn push(&mut self, sample: PerfSample) {
+        if self.samples.len() == HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// The sample that used the most CPU, `update` and `draw` together.
+    pub fn worst(&self) -> Option<&PerfSample> {
+        self.samples
+            .iter()
+            .max_by(|a, b| a.cpu().total_cmp(&b.cpu()))
+    }
+
+    /// The samples behind each sparkline column, left to right; the newest
+    /// frames land in the rightmost column and missing history stays empty.
+    fn columns(&self) -> impl Iterator<Item = (usize, Vec<PerfSample>)> + '_ {
+        let n = self.samples.len();
+        (0..GRAPH_W).filter_map(move |c| {
+            let end = n.checked_sub((GRAPH_W - 1 - c) * FRAMES_PER_COL)?;
+            let start = end.saturating_sub(FRAMES_PER_COL);
+            (start < end).then(|| (c, self.samples.range(start..end).copied().collect()))
+        })
+    }
+
+    /// Draw the graph panel across the screen with its top at `y0`. `target`
+    /// is the cart's frame rate, the top of the fps graph.
+    pub fn draw(&self, fb: &mut Framebuffer, y0: i32, target: u32) {
+        fb.rectfill(0, y0, 127, y0 + PANEL_H - 1, col::BLACK);
+        let target = target.max(1) as f32;
+        let worst = self.worst().map(|w| w.frame);
+        let latest = self.samples.back().copied().unwrap_or_default();
+        type Reading = fn(&[PerfSample], f32) -> f32;
+        let rows: [(&str, Reading, String); 4] = [
+            (
+                "UPD",
+                |s, _| s.iter().map(|s| s.cpu_update).fold(0.0, f32::max),
+                percent(latest.cpu_update),
+            ),
+            (
+                "DRW",
+                |s, _| s.iter().map(|s| s.cpu_draw).fold(0.0, f32::max),
+                percent(latest.cpu_draw),
+            ),
+            (
+                "FPS",
+                |s, target| s.iter().map(|s| s.fps / target).fold(1.0, f32::min),
+                format!("{:.0}", latest.fps),
+            ),
+            (
+                "MEM",
+                |s, _| s.iter().map(|s| s.mem).fold(0.0, f32::max),
+                percent(latest.mem),
+            ),
+        ];
+        for (i, (label, reading, now)) in rows.iter().enumerate() {
+            let y = y0 + 1 + i as i32 * ROW_H;
+            fb.print(label, 1, y, col::LIGHT_GREY);
+            let base = y + GRAPH_H - 1;
+            for (c, samples) in self.columns() {
+                let x = GRAPH_X + c as i32;
+                let v = reading(&samples, target).clamp(0.0, 1.0);
+                let marked = worst.is_some_and(|w| samples.iter().any(|s| s.frame == w));
+                let color = if marked {
+                    fb.line(x, y, x, base, col::DARK_BLUE);
+                    col::WHITE
+                } else if *label == "FPS" {
+                    fps_color(v * target, target as u32)
+                } else {
+                    stat_color(v)
+                };
+                // Every column shows at least a dot, so an idle cart still
+                // draws a baseline.
+                let h = ((v * GRAPH_H as f32).round() as i32).max(1);
+                fb.line(x, base - h + 1, x, base, color);
+            }
+            fb.print(now, GRAPH_X + GRAPH_W as i32 + 2, y, col::WHITE);
+        }
+        if let Some(w) = self.worst() {
+            let text = format!(
+                "WORST U{} D{} F{}",
+                percent(w.cpu_update),
+                percent(w.cpu_draw),
+                w.frame
+            );
+            fb.print(&text, 1, y0 + 1 + 4 * ROW_H, col::WHITE);
+        }
+    }
+}
+
+fn percent(frac: f32) -> String {
+    format!("{:.0}%", frac * 100.0)
+}
+
+/// Color a budget fraction: green with headroom, yellow from 70%, red above
+/// 90%.
+pub fn stat_color(frac: f32) -> u8 {
+    if frac < 0.7 {
+        col::GREEN
+    } else if frac <= 0.9 {
+        col::YELLOW
+    } else {
+        col::RED
+    }
+}
+
+/// Color an fps reading by how close it is to the cart's target rate.
+pub fn fps_color(measured: f32, target: u32) -> u8 {
+    let ratio = if target > 0 {
+        measured / target as f32
+    } else {
+        1.0
+    };
+    if ratio >= 0.95 {
+        col::GREEN
+    } else if ratio >= 0.8 {
+        col::YELLOW
+    } else {
+        col::RED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frame: u64, cpu_update: f32) -> PerfSample {
+        PerfSample {
+            frame,
+            cpu_update,
+            cpu_draw: 0.1,
+            fps: 60.0,
+            mem: 0.25,
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_frames_and_finds_the_worst() {
+        let mut h = PerfHistory::default();
+        for f in 0..HISTORY as u64 + 20 {
+            h.push(sample(f, if f == 5 || f == 150 { 0.9 } else { 0.2 }));
+        }
+        assert_eq!(h.samples.len(), HISTORY);
+        assert_eq!(h.samples[0].frame, 20);
+        assert_eq!(h.worst().unwrap().frame, 150, "frame 5 scrolled out");
+    }
+
+    #[test]
+    fn columns_fill_from_the_right() {
+        let mut h = PerfHistory::default();
+        for f in 0..5 {
+            h.push(sample(f, 0.2));
+        }
+        let cols: Vec<_> = h.columns().map(|(c, s)| (c, s.len())).collect();
+        assert_eq!(cols, [(GRAPH_W - 3, 1), (GRAPH_W - 2, 2), (GRAPH_W - 1, 2)]);
+        // A full history gives every column its frames.
+        for f in 5..HISTORY as u64 + 7 {
+            h.push(sample(f, 0.2));
+        }
+        assert_eq!(h.columns().count(), GRAPH_W);
+        let frames: usize = h.columns().map(|(_, s)| s.len()).sum();
+        assert_eq!(frames, HISTORY);
+    }
+
+    #[test]
+    fn the_worst_frame_is_marked_in_white() {
+        let mut h = PerfHistory::default();
+        for f in 0..10 {
+            h.push(sample(f, if f == 9 { 0.95 } else { 0.1 }));
+        }
+        let mut fb = Framebuffer::new();
+        h.draw(&mut fb, 0, 60);
+        let x = GRAPH_X + GRAPH_W as i32 - 1;
+        assert_eq!(fb.pget(x, GRAPH_H), col::WHITE, "the newest column");
+        assert_eq!(fb.pget(x - 2, GRAPH_H), col::GREEN);
+        assert!(StatsMode::Graphs.next() == StatsMode::Off);
+    }
+}
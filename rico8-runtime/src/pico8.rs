@@ -663,7 +663,7 @@ fn parse_png(bytes: &[u8]) -> Result<Assets> {
 
 /// Reassemble the cart ROM from a PICO-8 PNG: two bits per channel, A/R/G/B.
 fn rom_from_png(bytes: &[u8]) -> Result<Vec<u8>> {
-    let (w, h, rgba) = decode_png_rgba(bytes)?;
+    let (w, h, rgba) = crate::cart::decode_png_rgba(bytes)?;
     if w != PICO8_PNG_W || h != PICO8_PNG_H {
         bail!("Not a PICO-8 cart PNG (expected {PICO8_PNG_W}x{PICO8_PNG_H}, got {w}x{h})");
     }
@@ -745,107 +745,6 @@ fn channel(b: u8) -> Option<u8> {
     (b & 0x40 == 0).then_some(b & 0x3f)
 }
 
-// ---------------------------------------------------------------------------
-// A small PNG decoder (8-bit RGBA, non-interlaced) for cart images
-// ---------------------------------------------------------------------------
-
-/// Decode an 8-bit RGBA, non-interlaced PNG to `(width, height, rgba)`.
-/// Just enough of the spec to read a PICO-8 cart image; richer PNGs are
-/// rejected with a clear message rather than mis-decoded.
-fn decode_png_rgba(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>)> {
-    if !bytes.starts_with(&PNG_SIG) {
-        bail!("not a png file");
-    }
-    let mut rest = &bytes[8..];
-    let (mut width, mut height) = (0usize, 0usize);
-    let mut idat = Vec::new();
-    let mut have_ihdr = false;
-    while rest.len() >= 12 {
-        let len = u32::from_be_bytes(rest[0..4].try_into().unwrap()) as usize;
-        let ctype = &rest[4..8];
-        if rest.len() < 12 + len {
-            bail!("truncated png chunk");
-        }
-        let data = &rest[8..8 + len];
-        match ctype {
-            b"IHDR" => {
-                if len < 13 {
-                    bail!("malformed png header");
-                }
-                width = u32::from_be_bytes(data[0..4].try_into().unwrap()) as usize;
-                height = u32::from_be_bytes(data[4..8].try_into().unwrap()) as usize;
-                let (bit_depth, color_type, interlace) = (data[8], data[9], data[12]);
-                if bit_depth != 8 || color_type != 6 {
-                    bail!("unsupported png: need 8-bit rgba (a pico-8 cart png is)");
-                }
-                if interlace != 0 {
-                    bail!("interlaced png is not supported");
-                }
-                have_ihdr = true;
-            }
-            b"IDAT" => idat.extend_from_slice(data),
-            b"IEND" => break,
-            _ => {}
-        }
-        rest = &rest[12 + len..];
-    }
-    if !have_ihdr {
-        bail!("png has no header chunk");
-    }
-    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&idat, 64 * 1024 * 1024)
-        .map_err(|e| anyhow!("png image data is corrupted: {e:?}"))?;
-
-    const BPP: usize = 4;
-    let stride = width * BPP;
-    if raw.len() < height * (stride + 1) {
-        bail!("png image data is truncated");
-    }
-    let mut out = vec![0u8; height * stride];
-    for y in 0..height {
-        let filter = raw[y * (stride + 1)];
-        let line = &raw[y * (stride + 1) + 1..y * (stride + 1) + 1 + stride];
-        for i in 0..stride {
-            let a = if i >= BPP {
-                out[y * stride + i - BPP]
-            } else {
-                0
-            };
-            let b = if y > 0 { out[(y - 1) * stride + i] } else { 0 };
-            let c = if y > 0 && i >= BPP {
-                out[(y - 1) * stride + i - BPP]
-            } else {
-                0
-            };
-            out[y * stride + i] = match filter {
-                0 => line[i],
-                1 => line[i].wrapping_add(a),
-                2 => line[i].wrapping_add(b),
-                3 => line[i].wrapping_add(((a as u16 + b as u16) / 2) as u8),
-                4 => line[i].wrapping_add(paeth(a, b, c)),
-                f => bail!("unknown png filter type {f}"),
-            };
-        }
-    }
-    Ok((width, height, out))
-}
-
-/// The PNG Paeth predictor.
-fn paeth(a: u8, b: u8, c: u8) -> u8 {
-    let p = a as i32 + b as i32 - c as i32;
-    let (pa, pb, pc) = (
-        (p - a as i32).abs(),
-        (p - b as i32).abs(),
-        (p - c as i32).abs(),
-    );
-    if pa <= pb && pa <= pc {
-        a
-    } else if pb <= pc {
-        b
-    } else {
-        c
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Hex helpers
 // ---------------------------------------------------------------------------
@@ -0,0 +1,553 @@
+//! Scripted cart tests: drive a cart headless with button input and check
+//! what it draws and logs, so CI can catch gameplay regressions.
+//!
+//! A script is plain text, one command per line; `#` starts a comment.
+//!
+//! ```text
+//! wait 30                 # run 30 frames with no buttons held
+//! press x                 # hold X for one frame
+//! hold right+o 12         # hold Right and O for 12 frames
+//! pixel 64 64 8           # pixel (64, 64) is color 8
+//! region 0 0 128 8 1      # a whole rectangle is color 1
+//! hash 1a2b3c4d           # CRC-32 of the 128x128 palette indices
+//! golden title.png        # screen matches a PNG (relative to the script)
+//! log game over           # a log line since the last match contains this
+//! ```
+//!
+//! Buttons are `left`, `right`, `up`, `down`, `o` and `x`, joined with `+`.
+//! Goldens are written from the current screen only when the run asks to
+//! (`update`); without it a missing golden fails its check.
+//!
+//! Scripts double as input recordings: [`Script::input_frames`] expands the
+//! `wait`/`press`/`hold` lines into one button mask per frame, ignoring the
+//! checks, and [`write_inputs`] turns masks back into a script.
+
+use crate::{cart, input::BUTTON_COUNT, vm::GameVm};
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+
+/// A parsed test script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    steps: Vec<(usize, Step)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// Run `frames` frames holding the buttons in the `buttons` bitmask.
+    Run {
+        buttons: u8,
+        frames: u32,
+    },
+    Pixel {
+        x: i32,
+        y: i32,
+        color: u8,
+    },
+    Region {
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        color: u8,
+    },
+    Hash(u32),
+    Golden(String),
+    Log(String),
+}
+
+/// What a script run found. Checks keep going after a failure so one run
+/// reports everything; a cart runtime error ends the run.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Frames run.
+    pub frames: u64,
+    /// Checks evaluated (passed or not).
+    pub checks: usize,
+    /// One message per failed check, prefixed with its script line.
+    pub failures: Vec<String>,
+    /// Golden images written because the run was updating them.
+    pub goldens_written: Vec<String>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Script {
+    /// Parse a script, reporting the first bad line as `line N: ...`.
+    pub fn parse(src: &str) -> Result<Script> {
+        let mut steps = Vec::new();
+        for (i, raw) in src.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let step = parse_step(line).with_context(|| format!("line {}", i + 1))?;
+            steps.push((i + 1, step));
+        }
+        Ok(Script { steps })
+    }
+
+    /// Run the script against a loaded cart. `base` is where golden paths
+    /// resolve; with `update` set every golden is rewritten from the screen.
+    pub fn run(&self, vm: &mut GameVm, base: &Path, update: bool) -> Result<Report> {
+        let mut report = Report::default();
+        // Index into the VM's log of the first line not yet matched.
+        let mut log_cursor = vm.state().logs.len();
+        for (line, step) in &self.steps {
+            let fail = |report: &mut Report, msg: String| {
+                report.failures.push(format!("line {line}: {msg}"));
+            };
+            match step {
+                Step::Run { buttons, frames } => {
+                    set_buttons(vm, *buttons);
+                    for _ in 0..*frames {
+                        let frame = report.frames;
+                        if let Err(e) = vm.call_update().and_then(|()| vm.call_draw()) {
+                            fail(&mut report, format!("frame {frame}: {e}"));
+                            return Ok(report);
+                        }
+                        report.frames += 1;
+                    }
+                    set_buttons(vm, 0);
+                }
+                Step::Pixel { x, y, color } => {
+                    report.checks += 1;
+                    let got = vm.state().fb.pget(*x, *y);
+                    if got != *color {
+                        fail(
+                            &mut report,
+                            format!("pixel ({x}, {y}) is {got}, expected {color}"),
+                        );
+                    }
+                }
+                Step::Region { x, y, w, h, color } => {
+                    report.checks += 1;
+                    let fb = &vm.state().fb;
+                    let wrong = (*y..y + h)
+                        .flat_map(|py| (*x..x + w).map(move |px| (px, py)))
+                        .find(|&(px, py)| fb.pget(px, py) != *color);
+                    if let Some((px, py)) = wrong {
+                        let got = fb.pget(px, py);
+                        fail(
+                            &mut report,
+                            format!(
+                                "region {x},{y} {w}x{h} is not all {color}: \
+                                 ({px}, {py}) is {got}"
+                            ),
+                        );
+                    }
+                }
+                Step::Hash(want) => {
+                    report.checks += 1;
+                    let got = screen_hash(vm);
+                    if got != *want {
+                        fail(
+                            &mut report,
+                            format!("hash is {got:08x}, expected {want:08x}"),
+                        );
+                    }
+                }
+                Step::Golden(name) => {
+                    report.checks += 1;
+                    let path = base.join(name);
+                    if update {
+                        std::fs::write(&path, cart::encode_screen_png(&vm.state().fb, 1))
+                            .with_context(|| format!("Writing golden {}", path.display()))?;
+                        report.goldens_written.push(name.clone());
+                        continue;
+                    }
+                    if !path.exists() {
+                        fail(
+                            &mut report,
+                            format!("golden {name} is missing (--update writes it)"),
+                        );
+                        continue;
+                    }
+                    let bytes = std::fs::read(&path)
+                        .with_context(|| format!("Reading golden {}", path.display()))?;
+                    let (w, h, want) = cart::decode_png_rgba(&bytes)
+                        .with_context(|| format!("Decoding golden {}", path.display()))?;
+                    if (w, h) != (128, 128) {
+                        fail(
+                            &mut report,
+                            format!("golden {name} is {w}x{h}, not 128x128"),
+                        );
+                        continue;
+                    }
+                    let mut got = vec![0; 128 * 128 * 4];
+                    vm.state().fb.write_rgba(&mut got);
+                    let differing: Vec<usize> = got
+                        .chunks_exact(4)
+                        .zip(want.chunks_exact(4))
+                        .enumerate()
+                        .filter(|(_, (got, want))| got != want)
+                        .map(|(i, _)| i)
+                        .collect();
+                    if let Some(&i) = differing.first() {
+                        fail(
+                            &mut report,
+                            format!(
+                                "screen differs from {name} in {} pixels, first at ({}, {})",
+                                differing.len(),
+                                i % 128,
+                                i / 128
+                            ),
+                        );
+                    }
+                }
+                Step::Log(text) => {
+                    report.checks += 1;
+                    let logs = &vm.state().logs;
+                    match logs[log_cursor..]
+                        .iter()
+                        .position(|l| l.text.contains(text.as_str()))
+                    {
+                        Some(i) => log_cursor += i + 1,
+                        None => fail(&mut report, format!("no log line containing {text:?}")),
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// The buttons held on each frame the script runs, as bitmasks (bit `b`
+    /// is button `b`), for replaying it as an input recording.
+    pub fn input_frames(&self) -> Vec<u8> {
+        let mut masks = Vec::new();
+        for (_, step) in &self.steps {
+            if let Step::Run { buttons, frames } = step {
+                masks.extend(std::iter::repeat_n(*buttons, *frames as usize));
+            }
+        }
+        masks
+    }
+}
+
+/// Write per-frame button masks as a script that replays them, merging
+/// runs of the same buttons into single `wait`/`press`/`hold` lines.
+pub fn write_inputs(masks: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < masks.len() {
+        let mask = masks[i] & 0x3f;
+        let n = masks[i..].iter().take_while(|&&m| m & 0x3f == mask).count();
+        let line = match (mask, n) {
+            (0, _) => format!("wait {n}"),
+            (_, 1) => format!("press {}", button_names(mask)),
+            _ => format!("hold {} {n}", button_names(mask)),
+        };
+        out.push_str(&line);
+        out.push('\n');
+        i += n;
+    }
+    out
+}
+
+/// Hold exactly the buttons in `mask` (bit `b` is button `b`).
+pub fn set_buttons(vm: &mut GameVm, mask: u8) {
+    for b in 0..BUTTON_COUNT {
+        vm.state_mut().input.set_button(b, mask & (1 << b) != 0);
+    }
+}
+
+/// CRC-32 of the screen's palette indices, as printed by a failing `hash`.
+pub fn screen_hash(vm: &GameVm) -> u32 {
+    crc32fast::hash(vm.state().fb.pixels())
+}
+
+fn parse_step(line: &str) -> Result<Step> {
+    let (cmd, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let args: Vec<&str> = rest.split_whitespace().collect();
+    let num = |s: &str| -> Result<i32> {
+        s.parse()
+            .map_err(|_| anyhow!("expected a number, got {s:?}"))
+    };
+    let color = |s: &str| -> Result<u8> {
+        s.parse()
+            .ok()
+            .filter(|c| *c < 16)
+            .ok_or_else(|| anyhow!("expected a color 0-15, got {s:?}"))
+    };
+    let size = |s: &str| -> Result<i32> {
+        s.parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("expected a size above 0, got {s:?}"))
+    };
+    let frames = |s: &str| -> Result<u32> {
+        s.parse()
+            .map_err(|_| anyhow!("expected a frame count, got {s:?}"))
+    };
+    Ok(match (cmd, args.as_slice()) {
+        ("wait", [n]) => Step::Run {
+            buttons: 0,
+            frames: frames(n)?,
+        },
+        ("press", [b]) => Step::Run {
+            buttons: parse_buttons(b)?,
+            frames: 1,
+        },
+        ("hold", [b, n]) => Step::Run {
+            buttons: parse_buttons(b)?,
+            frames: frames(n)?,
+        },
+        ("pixel", [x, y, c]) => Step::Pixel {
+            x: num(x)?,
+            y: num(y)?,
+            color: color(c)?,
+        },
+        ("region", [x, y, w, h, c]) => Step::Region {
+            x: num(x)?,
+            y: num(y)?,
+            w: size(w)?,
+            h: size(h)?,
+            color: color(c)?,
+        },
+        ("hash", [h]) => Step::Hash(
+            u32::from_str_radix(h.trim_start_matches("0x"), 16)
+                .map_err(|_| anyhow!("expected a hex hash, got {h:?}"))?,
+        ),
+        ("golden", [file]) => Step::Golden(file.to_string()),
+        ("log", _) if !rest.is_empty() => Step::Log(rest.to_string()),
+        ("wait" | "press" | "hold" | "pixel" | "region" | "hash" | "golden" | "log", _) => {
+            bail!("wrong arguments for {cmd}: {}", usage(cmd))
+        }
+        _ => bail!("unknown command {cmd:?}"),
+    })
+}
+
+fn usage(cmd: &str) -> &'static str {
+    match cmd {
+        "wait" => "wait FRAMES",
+        "press" => "press BUTTONS",
+        "hold" => "hold BUTTONS FRAMES",
+        "pixel" => "pixel X Y COLOR",
+        "region" => "region X Y W H COLOR",
+        "hash" => "hash HEX",
+        "golden" => "golden FILE.png",
+        _ => "log TEXT",
+    }
+}
+
+/// Script names of the buttons, by index.
+const BUTTON_NAMES: [&str; BUTTON_COUNT] = ["left", "right", "up", "down", "o", "x"];
+
+/// `right+o` -> a bitmask of button indices.
+fn parse_buttons(s: &str) -> Result<u8> {
+    let mut mask = 0;
+    for name in s.split('+') {
+        let lower = name.to_ascii_lowercase();
+        let Some(b) = BUTTON_NAMES.iter().position(|n| *n == lower) else {
+            bail!("unknown button {lower:?} (left, right, up, down, o, x)");
+        };
+        mask |= 1 << b;
+    }
+    Ok(mask)
+}
+
+/// A non-empty bitmask -> `right+o`.
+fn button_names(mask: u8) -> String {
+    let names: Vec<&str> = (0..BUTTON_COUNT)
+        .filter(|b| mask & (1 << b) != 0)
+        .map(|b| BUTTON_NAMES[b])
+        .collect();
+    names.join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assets::Assets, audio::AudioHandle};
+
+    /// Moves a pixel right while Right is held and logs when X is pressed.
+    const CART: &str = r#"
+        (module
+          (import "rico8" "clear" (func $cls (param i32)))
+          (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+          (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
+          (import "rico8" "log" (func $log (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 16) "fire")
+          (global $x (mut i32) (i32.const 0))
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (if (i32.ne (call $btn (i32.const 1)) (i32.const 0))
+              (then (global.set $x (i32.add (global.get $x) (i32.const 1)))))
+            (if (i32.ne (call $btn (i32.const 5)) (i32.const 0))
+              (then (call $log (i32.const 16) (i32.const 4)))))
+          (func (export "rico8_draw")
+            (call $cls (i32.const 1))
+            (call $pset (global.get $x) (i32.const 0) (i32.const 8)))
+        )
+    "#;
+
+    fn vm() -> GameVm {
+        let wasm = wat::parse_str(CART).unwrap();
+        GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap()
+    }
+
+    fn run(src: &str, base: &Path) -> Report {
+        Script::parse(src)
+            .unwrap()
+            .run(&mut vm(), base, false)
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_commands_and_reports_bad_lines() {
+        let s = Script::parse("# comment\n\nhold right+x 3  # trailing\nlog game over\n").unwrap();
+        assert_eq!(
+            s.steps,
+            vec![
+                (
+                    3,
+                    Step::Run {
+                        buttons: 0b100010,
+                        frames: 3
+                    }
+                ),
+                (4, Step::Log("game over".into())),
+            ]
+        );
+        let err = Script::parse("wait 1\npress jump\n").unwrap_err();
+        assert!(
+            format!("{err:#}").contains("line 2: unknown button"),
+            "{err:#}"
+        );
+        let err = Script::parse("region 0 0 0 8 1\n").unwrap_err();
+        assert!(format!("{err:#}").contains("size above 0"), "{err:#}");
+        let err = Script::parse("pixel 1 2\n").unwrap_err();
+        assert!(format!("{err:#}").contains("pixel X Y COLOR"), "{err:#}");
+        assert!(Script::parse("teleport 3").is_err());
+    }
+
+    #[test]
+    fn input_frames_round_trip_through_a_written_script() {
+        let masks = [0, 0, 0b10, 0b10, 0b10, 0b100001, 0, 0b1000];
+        let src = write_inputs(&masks);
+        assert_eq!(
+            src,
+            "wait 2\nhold right 3\npress left+x\nwait 1\npress down\n"
+        );
+        let script = Script::parse(&format!("{src}pixel 0 0 1\n")).unwrap();
+        assert_eq!(script.input_frames(), masks);
+    }
+
+    #[test]
+    fn input_drives_the_cart_and_checks_pass() {
+        let dir = std::env::temp_dir();
+        let report = run(
+            "wait 2\npixel 0 0 8\nhold right 5\npixel 5 0 8\nregion 0 1 128 127 1\n\
+             press x\nlog fire\n",
+            &dir,
+        );
+        assert!(report.passed(), "{:?}", report.failures);
+        assert_eq!((report.frames, report.checks), (8, 4));
+    }
+
+    #[test]
+    fn failures_name_the_line_and_keep_going() {
+        let dir = std::env::temp_dir();
+        let report = run(
+            "wait 1\npixel 0 0 3\nregion 0 0 2 1 1\nlog fire\nhash 0\n",
+            &dir,
+        );
+        assert_eq!(report.checks, 4);
+        assert_eq!(report.failures.len(), 4, "{:?}", report.failures);
+        assert!(report.failures[0].starts_with("line 2: pixel (0, 0) is 8"));
+        assert!(report.failures[1].contains("(0, 0) is 8"));
+        assert!(report.failures[2].contains("\"fire\""));
+        // The actual hash is printed so it can be pasted into the script.
+        let actual = format!(
+            "{:08x}",
+            crc32fast::hash(&{
+                let mut px = vec![1u8; 128 * 128];
+                px[0] = 8;
+                px
+            })
+        );
+        assert!(
+            report.failures[3].contains(&actual),
+            "{}",
+            report.failures[3]
+        );
+    }
+
+    #[test]
+    fn log_lines_are_matched_once_in_order() {
+        let dir = std::env::temp_dir();
+        let report = run("press x\nlog fire\nlog fire\n", &dir);
+        assert_eq!(report.failures.len(), 1, "{:?}", report.failures);
+        assert!(report.failures[0].starts_with("line 3:"));
+    }
+
+    #[test]
+    fn goldens_are_written_on_update_then_compared() {
+        let dir = std::env::temp_dir().join(format!("rico8_script_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing = run("wait 1\ngolden shot.png\n", &dir);
+        assert_eq!(missing.failures.len(), 1);
+        assert!(
+            missing.failures[0].contains("missing"),
+            "{}",
+            missing.failures[0]
+        );
+        assert!(missing.goldens_written.is_empty() && !dir.join("shot.png").exists());
+
+        let script = Script::parse("wait 1\ngolden shot.png\n").unwrap();
+        let first = script.run(&mut vm(), &dir, true).unwrap();
+        assert!(first.passed());
+        assert_eq!(first.goldens_written, vec!["shot.png".to_string()]);
+
+        let same = run("wait 1\ngolden shot.png\n", &dir);
+        assert!(same.passed() && same.goldens_written.is_empty());
+
+        let moved = run("hold right 3\ngolden shot.png\n", &dir);
+        assert_eq!(moved.failures.len(), 1);
+        assert!(
+            moved.failures[0].contains("in 2 pixels, first at (0, 0)"),
+            "{}",
+            moved.failures[0]
+        );
+
+        let script = Script::parse("hold right 3\ngolden shot.png\n").unwrap();
+        let updated = script.run(&mut vm(), &dir, true).unwrap();
+        assert!(updated.passed() && updated.goldens_written.len() == 1);
+        assert!(run("hold right 3\ngolden shot.png\n", &dir).passed());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_cart_trap_ends_the_run_with_its_frame() {
+        let wasm = wat::parse_str(
+            r#"(module
+                 (global $n (mut i32) (i32.const 0))
+                 (func (export "rico8_init"))
+                 (func (export "rico8_update")
+                   (global.set $n (i32.add (global.get $n) (i32.const 1)))
+                   (if (i32.eq (global.get $n) (i32.const 3)) (then unreachable)))
+                 (func (export "rico8_draw")))"#,
+        )
+        .unwrap();
+        let mut vm = GameVm::load(&wasm, &Assets::default(), AudioHandle::dummy()).unwrap();
+        let report = Script::parse("wait 10\npixel 0 0 0\n")
+            .unwrap()
+            .run(&mut vm, Path::new("."), false)
+            .unwrap();
+        assert_eq!(report.frames, 2);
+        assert_eq!(report.checks, 0);
+        assert!(
+            report.failures[0].starts_with("line 1: frame 2:"),
+            "{:?}",
+            report.failures
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Slow motion and fast-forward for a running cart.
+//!
+//! The host keeps ticking at the cart's own frame rate; a [`SpeedClock`]
+//! says how many logical frames each tick runs. Slowed down, most ticks run
+//! none; sped up, a tick runs several back to back. The cart always sees
+//! whole frames, so `time()` and its frame count move exactly as they would
+//! at 1x, just faster or slower against the wall clock.
+
+/// How fast a cart runs against the wall clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Speed {
+    Quarter,
+    Half,
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+    /// As many frames as the host can run: each tick runs frames until its
+    /// time is up.
+    Unthrottled,
+}
+
+impl Speed {
+    /// Every speed, slowest first.
+    pub const ALL: [Speed; 6] = [
+        Speed::Quarter,
+        Speed::Half,
+        Speed::Normal,
+        Speed::Double,
+        Speed::Quadruple,
+        Speed::Unthrottled,
+    ];
+
+    /// The next speed up, stopping at unthrottled.
+    pub fn faster(self) -> Speed {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(2);
+        Self::ALL[(i + 1).min(Self::ALL.len() - 1)]
+    }
+
+    /// The next speed down, stopping at 1/4x.
+    pub fn slower(self) -> Speed {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(2);
+        Self::ALL[i.saturating_sub(1)]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Speed::Quarter => "1/4x",
+            Speed::Half => "1/2x",
+            Speed::Normal => "1x",
+            Speed::Double => "2x",
+            Speed::Quadruple => "4x",
+            Speed::Unthrottled => "max",
+        }
+    }
+
+    /// The synth's playback rate to match: music keeps pace with the cart,
+    /// pitched up or down with it. Unthrottled has no steady rate, so it is
+    /// silent (`0.0`).
+    pub fn audio_rate(self) -> f32 {
+        match self {
+            Speed::Quarter => 0.25,
+            Speed::Half => 0.5,
+            Speed::Normal => 1.0,
+            Speed::Double => 2.0,
+            Speed::Quadruple => 4.0,
+            Speed::Unthrottled => 0.0,
+        }
+    }
+
+    /// Logical frames per host tick, in quarters (1x is 4).
+    fn quarters(self) -> u32 {
+        match self {
+            Speed::Quarter => 1,
+            Speed::Half => 2,
+            Speed::Normal => 4,
+            Speed::Double => 8,
+            Speed::Quadruple | Speed::Unthrottled => 16,
+        }
+    }
+}
+
+/// Turns host ticks into logical frames at the chosen [`Speed`].
+#[derive(Debug, Default)]
+pub struct SpeedClock {
+    speed: Speed,
+    /// Quarter frames owed but not yet run.
+    credit: u32,
+}
+
+impl SpeedClock {
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        self.credit = 0;
+    }
+
+    /// Logical frames to run on this host tick, or `None` when unthrottled:
+    /// run frames until the tick's time is up.
+    pub fn tick(&mut self) -> Option<u32> {
+        if self.speed == Speed::Unthrottled {
+            return None;
+        }
+        self.credit += self.speed.quarters();
+        let frames = self.credit / 4;
+        self.credit %= 4;
+        Some(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames_over(speed: Speed, ticks: usize) -> Vec<u32> {
+        let mut clock = SpeedClock::default();
+        clock.set_speed(speed);
+        (0..ticks).map(|_| clock.tick().unwrap()).collect()
+    }
+
+    #[test]
+    fn ticks_run_whole_frames_at_every_speed() {
+        assert_eq!(frames_over(Speed::Quarter, 8), [0, 0, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(frames_over(Speed::Half, 4), [0, 1, 0, 1]);
+        assert_eq!(frames_over(Speed::Normal, 2), [1, 1]);
+        assert_eq!(frames_over(Speed::Double, 2), [2, 2]);
+        assert_eq!(frames_over(Speed::Quadruple, 2), [4, 4]);
+        let mut clock = SpeedClock::default();
+        clock.set_speed(Speed::Unthrottled);
+        assert_eq!(clock.tick(), None);
+    }
+
+    #[test]
+    fn steps_between_speeds() {
+        assert_eq!(Speed::Normal.faster(), Speed::Double);
+        assert_eq!(Speed::Unthrottled.faster(), Speed::Unthrottled);
+        assert_eq!(Speed::Quarter.slower(), Speed::Quarter);
+        assert_eq!(Speed::Half.slower(), Speed::Quarter);
+    }
+}
@@ -11,12 +11,17 @@ use crate::{
     audio::AudioHandle,
     fb::Framebuffer,
     input::InputState,
+    log::{LogLevel, LogLine},
+    memmap::MemoryMap,
 };
 use anyhow::{anyhow, Context as _, Result};
+use std::collections::HashMap;
+use wasm_encoder::reencode::{self, Reencode};
 use wasmi::{
-    Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
-    TypedFunc,
+    Caller, Config, Engine, Global, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc,
 };
+use wasmparser::{ExternalKind, Parser, Payload, TypeRef};
 
 /// A cart's logical frames per second when it doesn't say otherwise.
 pub const DEFAULT_FPS: u32 = 60;
@@ -30,7 +35,7 @@ pub const UI_FPS: u32 = 30;
 /// per call — one number shared with the memory and cart-size limits. A real
 /// frame uses a few thousand; exceeding this means the cart is stuck or doing
 /// far too much, and surfaces as a friendly error screen.
-const FUEL_PER_CALL: u64 = 131_072;
+pub const FUEL_PER_CALL: u64 = 131_072;
 
 /// Hard cap on a cart's total linear memory: 128 K, the same number as the
 /// fuel and cart-size limits. Covers static data, the shadow stack and the
@@ -46,10 +51,16 @@ pub struct HostState {
     pub sprites: SpriteSheet,
     pub map: MapData,
     pub audio: AudioHandle,
-    /// Messages from the cart's `log` calls, drained by the console.
-    pub logs: Vec<String>,
+    /// Lines from the cart's `log`/`log_level` calls, drained by the console.
+    pub logs: Vec<LogLine>,
     /// Message from the cart's panic hook, captured just before the trap.
     pub panic_message: Option<String>,
+    /// Where that panic happened, reported by the hook alongside it.
+    pub panic_location: Option<PanicLocation>,
+    /// Set while re-running frames that already ran once (a netplay
+    /// rollback): sound effects, music and logs are skipped so they don't
+    /// repeat.
+    pub replaying: bool,
     pub frame: u64,
     /// The cart's logical frames per second (30 or 60), from its `rico8_fps`
     /// export. Drives `time()` and the host's update/draw cadence.
@@ -58,16 +69,30 @@ pub struct HostState {
     last_update_cpu: f32,
     /// Fraction (0.0..1.0) of `draw`'s fuel budget used last completed frame.
     last_draw_cpu: f32,
+    /// Fuel `update` burned last completed frame (what `last_update_cpu` rounds).
+    last_update_fuel: u64,
+    /// Fuel `draw` burned last completed frame.
+    last_draw_fuel: u64,
     /// Real frames per second measured by the host frontend; `0.0` until fed.
     measured_fps: f32,
     rng: u64,
     /// Enforces `MAX_MEMORY` on linear-memory growth, including the initial
     /// allocation at instantiation.
     limits: StoreLimits,
+    /// Hot-reload state passing through `state_write`/`state_read`, and how
+    /// much of it the cart has read back.
+    saved_state: Vec<u8>,
+    saved_state_read: usize,
+    /// Function indices the traced replay of a trap has entered and not yet
+    /// left, outermost first; see [`crate::backtrace`].
+    call_stack: Vec<u32>,
 }
 
 impl HostState {
-    fn new(assets: &Assets, audio: AudioHandle) -> Self {
+    /// Fresh console state for a cart: a blank screen, the cart's own sprite
+    /// sheet and map, and the fixed RNG seed. Native hosts (`rico8-test`)
+    /// build on this directly; everything else gets one from [`GameVm::load`].
+    pub fn new(assets: &Assets, audio: AudioHandle) -> Self {
         Self {
             fb: Framebuffer::new(),
             input: InputState::default(),
@@ -76,20 +101,37 @@ impl HostState {
             audio,
             logs: Vec::new(),
             panic_message: None,
+            panic_location: None,
+            replaying: false,
             frame: 0,
             fps: DEFAULT_FPS,
             last_update_cpu: 0.0,
             last_draw_cpu: 0.0,
+            last_update_fuel: 0,
+            last_draw_fuel: 0,
             measured_fps: 0.0,
             rng: 0x2545_f491_4f6c_dd1d,
             limits: StoreLimitsBuilder::new()
                 .memory_size(MAX_MEMORY)
                 .trap_on_grow_failure(true)
                 .build(),
+            saved_state: Vec::new(),
+            saved_state_read: 0,
+            call_stack: Vec::new(),
         }
     }
 
-    fn next_rand(&mut self) -> f32 {
+    /// Add a line to the cart's log, stamped with the current frame.
+    pub fn log(&mut self, level: LogLevel, text: String) {
+        self.logs.push(LogLine {
+            frame: self.frame,
+            level,
+            text,
+        });
+    }
+
+    /// The next value for the cart's `rnd`, in `0.0..1.0`.
+    pub fn next_rand(&mut self) -> f32 {
         // xorshift64*; carts that need determinism can bring their own RNG.
         let mut x = self.rng;
         x ^= x >> 12;
@@ -115,32 +157,299 @@ impl HostState {
         }
     }
 
-    fn seed_rand(&mut self, seed: u32) {
+    /// Append to the hot-reload state, as the cart's `state_write` does.
+    pub fn state_write(&mut self, data: &[u8]) {
+        self.saved_state.extend_from_slice(data);
+    }
+
+    /// Read the hot-reload state back into `buf`, as the cart's
+    /// `state_read` does. Returns how many bytes were left to copy.
+    pub fn state_read(&mut self, buf: &mut [u8]) -> usize {
+        let rest = &self.saved_state[self.saved_state_read..];
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.saved_state_read += n;
+        n
+    }
+
+    /// Take what the cart has written with `state_write`, emptying the buffer.
+    pub fn take_saved_state(&mut self) -> Vec<u8> {
+        self.saved_state_read = 0;
+        std::mem::take(&mut self.saved_state)
+    }
+
+    /// Stage `state` for the cart to read back with `state_read`.
+    pub fn set_saved_state(&mut self, state: Vec<u8>) {
+        self.saved_state = state;
+        self.saved_state_read = 0;
+    }
+
+    /// Whether the cart has read back every staged byte.
+    pub fn saved_state_fully_read(&self) -> bool {
+        self.saved_state_read == self.saved_state.len()
+    }
+
+    /// Reseed the cart's `rnd`, as its `seed_rng` does.
+    pub fn seed_rand(&mut self, seed: u32) {
         // Force a nonzero xorshift state; all-zero is a fixed point.
         self.rng = (((seed as u64) << 32) | (seed as u64)) | 1;
     }
 }
 
+/// Where in the cart's source a panic happened, as Rust's
+/// `core::panic::Location` reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicLocation {
+    /// The path rustc was given for the file: `src/player.rs` for the cart's
+    /// own files, an absolute path for its dependencies'.
+    pub file: String,
+    /// 1-based.
+    pub line: u32,
+    /// 1-based.
+    pub column: u32,
+}
+
+impl PanicLocation {
+    /// The file's name under a project's flat `src/`, or `None` when it lies
+    /// elsewhere (a dependency, a nested module).
+    pub fn project_file(&self) -> Option<&str> {
+        let name = self.file.strip_prefix("src/")?;
+        (!name.contains('/') && name.ends_with(".rs")).then_some(name)
+    }
+}
+
+/// Frames of a trap's backtrace shown on the error screen.
+const SCREEN_FRAMES: usize = 3;
+
 /// A cart-side runtime error, formatted for the error screen.
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
     /// Which lifecycle call failed: "init", "update" or "draw".
     pub phase: &'static str,
     pub message: String,
+    /// The cart's call stack when it trapped, innermost first and
+    /// demangled. Empty for panics, which carry their own location, and for
+    /// carts without a `name` section.
+    pub backtrace: Vec<String>,
+    /// Where a panic happened; `None` for other traps and for carts built
+    /// against an SDK that does not report it.
+    pub location: Option<PanicLocation>,
+}
+
+impl RuntimeError {
+    /// The backtrace as `in <function>` lines, innermost first, up to
+    /// [`MAX_FRAMES`](crate::backtrace::MAX_FRAMES) and a count of the rest.
+    pub fn backtrace_lines(&self) -> Vec<String> {
+        let max = crate::backtrace::MAX_FRAMES;
+        let mut lines: Vec<String> = self
+            .backtrace
+            .iter()
+            .take(max)
+            .map(|frame| format!("in {frame}"))
+            .collect();
+        if self.backtrace.len() > max {
+            lines.push(format!("... {} more frames", self.backtrace.len() - max));
+        }
+        lines
+    }
 }
 
+/// The phase and message, then the top of the backtrace. The alternate form
+/// (`{:#}`) lists the whole backtrace, for logs.
 impl std::fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Runtime error in {}:\n{}", self.phase, self.message)
+        write!(f, "Runtime error in {}:\n{}", self.phase, self.message)?;
+        let lines = self.backtrace_lines();
+        let shown = if f.alternate() {
+            lines.len()
+        } else {
+            SCREEN_FRAMES
+        };
+        for line in lines.iter().take(shown) {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything a frame can change, captured between frames so a VM can be
+/// rewound: linear memory, every mutable global (see [`export_globals`]),
+/// and the screen, pads, sprite sheet and map it works on. Audio and logs
+/// are output and are not rewound.
+#[derive(Clone, Default)]
+pub struct VmSnapshot {
+    memory: Vec<u8>,
+    /// In the order of [`GameVm::globals`].
+    globals: Vec<wasmi::Val>,
+    fb: Framebuffer,
+    input: InputState,
+    sprites: SpriteSheet,
+    map: MapData,
+    frame: u64,
+    rng: u64,
+    cpu: (f32, f32),
+    fuel: (u64, u64),
+}
+
+impl VmSnapshot {
+    /// The [`GameVm::state_hash`] of the VM this was taken from.
+    pub fn hash(&self) -> u32 {
+        let mut h = crc32fast::Hasher::new();
+        h.update(&self.memory);
+        h.update(self.fb.pixels());
+        h.finalize()
     }
 }
 
+/// How [`GameVm::load_with`] sets a cart up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadOptions {
+    /// Seed `rnd` before `rico8_init` runs, so what init rolls (a generated
+    /// level, say) follows the seed too.
+    pub seed: Option<u32>,
+}
+
 /// A loaded, running cart.
 pub struct GameVm {
     store: Store<HostState>,
     _instance: Instance,
     update: TypedFunc<(), ()>,
     draw: TypedFunc<(), ()>,
+    /// Kept to re-instantiate on [`GameVm::restore`] when memory must shrink.
+    module: Module,
+    linker: Linker<HostState>,
+    /// Every mutable global of the instance, in export order: what a
+    /// [`VmSnapshot`] saves besides memory.
+    globals: Vec<Global>,
+    /// Present when the cart has function names to put on a backtrace.
+    symbols: Option<Symbols>,
+    /// The VM as the current frame (or `init`) found it, to replay a trap
+    /// from. Only kept when there are `symbols`, and refilled in place each
+    /// frame: a copy of memory and the screen, without allocating.
+    pre_frame: Option<VmSnapshot>,
+    memory_map: MemoryMap,
+}
+
+/// What naming a trap takes: the cart as loaded, to build the traced copy
+/// from, and its function names.
+struct Symbols {
+    wasm: Vec<u8>,
+    names: HashMap<u32, String>,
+}
+
+/// Name under which [`export_globals`] exports a cart's hidden global.
+const GLOBAL_EXPORT_PREFIX: &str = "__rico8_global_";
+
+/// Rewrite a cart so every mutable global it defines is exported, the ones
+/// it keeps to itself (rustc's `__stack_pointer`, say) as
+/// `__rico8_global_<index>`, so snapshots can save and restore them. Only
+/// the export section changes. `None` if there is nothing to add, or the
+/// module does not parse (instantiating it reports that).
+fn export_globals(wasm: &[u8]) -> Option<Vec<u8>> {
+    let mut mutable = Vec::new();
+    let mut exported = Vec::new();
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.ok()? {
+            Payload::ImportSection(reader) => {
+                for import in reader.into_imports() {
+                    if let TypeRef::Global(ty) = import.ok()?.ty {
+                        mutable.push(ty.mutable);
+                    }
+                }
+            }
+            Payload::GlobalSection(reader) => {
+                for global in reader {
+                    mutable.push(global.ok()?.ty.mutable);
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export.ok()?;
+                    if export.kind == ExternalKind::Global {
+                        exported.push(export.index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let hidden: Vec<u32> = (0..mutable.len() as u32)
+        .filter(|&i| mutable[i as usize] && !exported.contains(&i))
+        .collect();
+    if hidden.is_empty() {
+        return None;
+    }
+    let mut module = wasm_encoder::Module::new();
+    GlobalExporter { hidden }
+        .parse_core_module(&mut module, Parser::new(0), wasm)
+        .ok()?;
+    Some(module.finish())
+}
+
+/// Appends the exports [`export_globals`] adds; the rest of the module is
+/// copied as is.
+struct GlobalExporter {
+    hidden: Vec<u32>,
+}
+
+impl Reencode for GlobalExporter {
+    type Error = std::convert::Infallible;
+
+    fn parse_export_section(
+        &mut self,
+        exports: &mut wasm_encoder::ExportSection,
+        section: wasmparser::ExportSectionReader<'_>,
+    ) -> Result<(), reencode::Error> {
+        reencode::utils::parse_export_section(self, exports, section)?;
+        for &index in &self.hidden {
+            let name = format!("{GLOBAL_EXPORT_PREFIX}{index}");
+            exports.export(&name, wasm_encoder::ExportKind::Global, index);
+        }
+        Ok(())
+    }
+}
+
+/// Every mutable global `instance` exports, in export order.
+fn mutable_globals(store: &Store<HostState>, instance: &Instance) -> Vec<Global> {
+    instance
+        .exports(store)
+        .filter_map(|e| e.into_global())
+        .filter(|g| g.ty(store).mutability().is_mut())
+        .collect()
+}
+
+/// The lifecycle exports of a freshly instantiated cart: init, update, draw.
+type Lifecycle = (Instance, [TypedFunc<(), ()>; 3]);
+
+fn instantiate(
+    store: &mut Store<HostState>,
+    linker: &Linker<HostState>,
+    module: &Module,
+) -> Result<Lifecycle> {
+    store
+        .set_fuel(FUEL_PER_CALL)
+        .map_err(|e| anyhow!("Fuel setup: {e}"))?;
+    let instance = linker
+        .instantiate_and_start(&mut *store, module)
+        .map_err(|e| {
+            let s = e.to_string();
+            if s.contains("resource limiter denied") {
+                anyhow!("Cart needs more than 128K of memory to start")
+            } else {
+                anyhow!("Cart does not match the RICO-8 ABI: {e}")
+            }
+        })?;
+    let func = |name: &str| {
+        instance
+            .get_typed_func::<(), ()>(&*store, name)
+            .map_err(|e| anyhow!("Cart is missing {name}: {e}"))
+    };
+    let funcs = [
+        func("rico8_init")?,
+        func("rico8_update")?,
+        func("rico8_draw")?,
+    ];
+    Ok((instance, funcs))
 }
 
 fn read_guest_str(caller: &Caller<'_, HostState>, ptr: u32, len: u32) -> String {
@@ -170,6 +479,16 @@ macro_rules! link {
 impl GameVm {
     /// Load a cart module, wire up the ABI, and run `rico8_init`.
     pub fn load(wasm: &[u8], assets: &Assets, audio: AudioHandle) -> Result<Self> {
+        Self::load_with(wasm, assets, audio, LoadOptions::default())
+    }
+
+    /// [`GameVm::load`], with `options`.
+    pub fn load_with(
+        wasm: &[u8],
+        assets: &Assets,
+        audio: AudioHandle,
+        options: LoadOptions,
+    ) -> Result<Self> {
         // The single chokepoint every frontend runs a cart through: the
         // desktop console, the standalone player, the web player and headless
         // verify all land here. Reject mis-sized asset bundles before they
@@ -180,10 +499,16 @@ impl GameVm {
         let mut config = Config::default();
         config.consume_fuel(true);
         let engine = Engine::new(&config);
+        let exported = export_globals(wasm);
+        let wasm = exported.as_deref().unwrap_or(wasm);
         let module = Module::new(&engine, wasm).map_err(|e| anyhow!("Invalid cart wasm: {e}"))?;
 
         audio.load(assets.sfx.clone(), assets.music.clone());
-        let mut store = Store::new(&engine, HostState::new(assets, audio));
+        let mut state = HostState::new(assets, audio);
+        if let Some(seed) = options.seed {
+            state.seed_rand(seed);
+        }
+        let mut store = Store::new(&engine, state);
         store.limiter(|state| &mut state.limits);
         let mut linker = <Linker<HostState>>::new(&engine);
 
@@ -347,15 +672,38 @@ impl GameVm {
         link!(linker, "sfx", |c: Caller<'_, HostState>,
                               n: i32,
                               channel: i32| {
-            c.data().audio.play_sfx(n, channel)
+            if !c.data().replaying {
+                c.data().audio.play_sfx(n, channel)
+            }
         });
+        link!(
+            linker,
+            "sfx_priority",
+            |c: Caller<'_, HostState>, n: i32, channel: i32, priority: i32| {
+                if !c.data().replaying {
+                    c.data()
+                        .audio
+                        .play_sfx_priority(n, channel, priority.clamp(0, 255) as u8)
+                }
+            }
+        );
         link!(linker, "music", |c: Caller<'_, HostState>,
                                 n: i32,
                                 fade: i32,
                                 mask: i32,
                                 token: i32|
          -> i32 {
-            c.data().audio.play_music(n, fade, mask, token)
+            let audio = &c.data().audio;
+            if c.data().replaying {
+                // The first run already started or stopped the song; a start
+                // gets back the token it was handed then.
+                return if n < 0 {
+                    0
+                } else {
+                    audio.with_synth(|s| s.music_token())
+                };
+            }
+            audio.play_music(n, fade, mask, token)
         });
         link!(linker, "cpu_update", |c: Caller<'_, HostState>| -> f32 {
             c.data().last_update_cpu
@@ -376,15 +724,35 @@ impl GameVm {
         link!(linker, "log", |mut c: Caller<'_, HostState>,
                               ptr: u32,
                               len: u32| {
-            let s = read_guest_str(&c, ptr, len);
-            c.data_mut().logs.push(s);
+            if !c.data().replaying {
+                let s = read_guest_str(&c, ptr, len);
+                c.data_mut().log(LogLevel::Info, s);
+            }
         });
+        link!(
+            linker,
+            "log_level",
+            |mut c: Caller<'_, HostState>, level: i32, ptr: u32, len: u32| {
+                if !c.data().replaying {
+                    let s = read_guest_str(&c, ptr, len);
+                    c.data_mut().log(LogLevel::from_abi(level), s);
+                }
+            }
+        );
         link!(linker, "panic", |mut c: Caller<'_, HostState>,
                                 ptr: u32,
                                 len: u32| {
             let s = read_guest_str(&c, ptr, len);
             c.data_mut().panic_message = Some(s);
         });
+        link!(
+            linker,
+            "panic_location",
+            |mut c: Caller<'_, HostState>, ptr: u32, len: u32, line: u32, column: u32| {
+                let file = read_guest_str(&c, ptr, len);
+                c.data_mut().panic_location = Some(PanicLocation { file, line, column });
+            }
+        );
         link!(
             linker,
             "seed_rng",
@@ -501,37 +869,70 @@ impl GameVm {
             let s = read_guest_str(&c, ptr, len);
             c.data_mut().fb.print_pen(&s)
         });
-
-        store
-            .set_fuel(FUEL_PER_CALL)
-            .map_err(|e| anyhow!("Fuel setup: {e}"))?;
-        let instance = linker
-            .instantiate_and_start(&mut store, &module)
-            .map_err(|e| {
-                let s = e.to_string();
-                if s.contains("resource limiter denied") {
-                    anyhow!("Cart needs more than 128K of memory to start")
-                } else {
-                    anyhow!("Cart does not match the RICO-8 ABI: {e}")
-                }
-            })?;
-
-        let init = instance
-            .get_typed_func::<(), ()>(&store, "rico8_init")
-            .map_err(|e| anyhow!("Cart is missing rico8_init: {e}"))?;
-        let update = instance
-            .get_typed_func::<(), ()>(&store, "rico8_update")
-            .map_err(|e| anyhow!("Cart is missing rico8_update: {e}"))?;
-        let draw = instance
-            .get_typed_func::<(), ()>(&store, "rico8_draw")
-            .map_err(|e| anyhow!("Cart is missing rico8_draw: {e}"))?;
-
+        link!(
+            linker,
+            "state_write",
+            |mut c: Caller<'_, HostState>, ptr: u32, len: u32| {
+                let Some(mem) = c.get_export("memory").and_then(wasmi::Extern::into_memory) else {
+                    return;
+                };
+                let (data, st) = mem.data_and_store_mut(&mut c);
+                let start = (ptr as usize).min(data.len());
+                let end = start.saturating_add(len as usize).min(data.len());
+                st.state_write(&data[start..end]);
+            }
+        );
+        link!(linker, "state_read", |mut c: Caller<'_, HostState>,
+                                     ptr: u32,
+                                     len: u32|
+         -> u32 {
+            let Some(mem) = c.get_export("memory").and_then(wasmi::Extern::into_memory) else {
+                return 0;
+            };
+            let (data, st) = mem.data_and_store_mut(&mut c);
+            let start = (ptr as usize).min(data.len());
+            let end = start.saturating_add(len as usize).min(data.len());
+            st.state_read(&mut data[start..end]) as u32
+        });
+        // Only the traced copy of a cart imports these; see `trace_trap`.
+        linker
+            .func_wrap(
+                crate::backtrace::TRACE_MODULE,
+                "enter",
+                |mut c: Caller<'_, HostState>, func: u32| c.data_mut().call_stack.push(func),
+            )
+            .context("registering the trace hooks")?;
+        linker
+            .func_wrap(
+                crate::backtrace::TRACE_MODULE,
+                "leave",
+                |mut c: Caller<'_, HostState>| {
+                    c.data_mut().call_stack.pop();
+                },
+            )
+            .context("registering the trace hooks")?;
+
+        let names = crate::backtrace::function_names(wasm);
+        let (instance, [init, update, draw]) = instantiate(&mut store, &linker, &module)?;
+        let globals = mutable_globals(&store, &instance);
         let mut vm = Self {
             store,
             _instance: instance,
             update,
             draw,
+            module,
+            linker,
+            globals,
+            symbols: (!names.is_empty()).then(|| Symbols {
+                wasm: wasm.to_vec(),
+                names,
+            }),
+            pre_frame: None,
+            memory_map: MemoryMap::parse(wasm),
         };
+        if vm.symbols.is_some() {
+            vm.pre_frame = Some(vm.snapshot());
+        }
         vm.call("init", init).map_err(|e| anyhow!(e.to_string()))?;
         vm.store.data_mut().fps = vm.query_fps();
         Ok(vm)
@@ -562,27 +963,36 @@ impl GameVm {
     ) -> std::result::Result<(), RuntimeError> {
         self.store.set_fuel(FUEL_PER_CALL).ok();
         let result = func.call(&mut self.store, ()).map_err(|err| {
-            let message = match self.store.data_mut().panic_message.take() {
-                Some(panic) => panic,
+            let location = self.store.data_mut().panic_location.take();
+            let (message, backtrace) = match self.store.data_mut().panic_message.take() {
+                Some(panic) => (panic, Vec::new()),
                 None => {
                     let s = err.to_string();
-                    if s.contains("fuel") {
+                    let message = if s.contains("fuel") {
                         format!("{phase}() ran too long\n(infinite loop?)")
                     } else if s.contains("growth operation limited") {
                         format!("{phase}() ran out of memory\n(128K limit)")
                     } else {
                         s
-                    }
+                    };
+                    (message, self.trace_trap(phase))
                 }
             };
-            RuntimeError { phase, message }
+            RuntimeError {
+                phase,
+                message,
+                backtrace,
+                location,
+            }
         });
         if result.is_ok() {
             let remaining = self.store.get_fuel().unwrap_or(0);
-            let frac = FUEL_PER_CALL.saturating_sub(remaining) as f32 / FUEL_PER_CALL as f32;
+            let used = FUEL_PER_CALL.saturating_sub(remaining);
+            let frac = used as f32 / FUEL_PER_CALL as f32;
+            let st = self.store.data_mut();
             match phase {
-                "update" => self.store.data_mut().last_update_cpu = frac,
-                "draw" => self.store.data_mut().last_draw_cpu = frac,
+                "update" => (st.last_update_cpu, st.last_update_fuel) = (frac, used),
+                "draw" => (st.last_draw_cpu, st.last_draw_fuel) = (frac, used),
                 _ => {}
             }
         }
@@ -591,6 +1001,11 @@ impl GameVm {
 
     /// Run one logical frame: tick input, call `rico8_update`.
     pub fn call_update(&mut self) -> std::result::Result<(), RuntimeError> {
+        if self.symbols.is_some() {
+            let mut before = self.pre_frame.take().unwrap_or_default();
+            self.snapshot_into(&mut before);
+            self.pre_frame = Some(before);
+        }
         self.store.data_mut().input.tick();
         let r = self.call("update", self.update);
         self.store.data_mut().frame += 1;
@@ -617,6 +1032,16 @@ impl GameVm {
         self.store.data().last_draw_cpu
     }
 
+    /// Fuel `update` used last completed frame, out of [`FUEL_PER_CALL`].
+    pub fn fuel_update(&self) -> u64 {
+        self.store.data().last_update_fuel
+    }
+
+    /// Fuel `draw` used last completed frame, out of [`FUEL_PER_CALL`].
+    pub fn fuel_draw(&self) -> u64 {
+        self.store.data().last_draw_fuel
+    }
+
     /// Fraction (0.0..1.0) of the 128K memory cap currently in use.
     pub fn memory_used_fraction(&self) -> f32 {
         let Some(mem) = self._instance.get_memory(&self.store, "memory") else {
@@ -625,6 +1050,29 @@ impl GameVm {
         mem.data_size(&self.store) as f32 / MAX_MEMORY as f32
     }
 
+    /// The cart's linear memory as it stands, for inspecting; empty for a
+    /// cart that exports none.
+    pub fn memory(&self) -> &[u8] {
+        match self._instance.get_memory(&self.store, "memory") {
+            Some(mem) => mem.data(&self.store),
+            None => &[],
+        }
+    }
+
+    /// The cart's linear memory, writable, for debuggers poking values
+    /// between frames.
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        match self._instance.get_memory(&self.store, "memory") {
+            Some(mem) => mem.data_mut(&mut self.store),
+            None => &mut [],
+        }
+    }
+
+    /// Where the stack, statics and heap lie in [`GameVm::memory`].
+    pub fn memory_map(&self) -> &MemoryMap {
+        &self.memory_map
+    }
+
     /// The cart's committed-memory high-water in bytes (shadow-stack reserve +
     /// statics + the highest the heap has reached), via its `rico8_mem_used`
     /// export, or 0 for carts without it (hand-written or allocation-free).
@@ -641,6 +1089,196 @@ impl GameVm {
         func.call(&mut self.store, ()).unwrap_or(0)
     }
 
+    /// Ask the cart to write out its game state through `rico8_save_state`,
+    /// ahead of a hot reload. `None` for carts without the export, when the
+    /// save itself fails, or when it saved nothing: the SDK's default hooks
+    /// save nothing and would refuse it anyway.
+    pub fn save_state(&mut self) -> Option<Vec<u8>> {
+        let func = self
+            ._instance
+            .get_typed_func::<(), ()>(&self.store, "rico8_save_state")
+            .ok()?;
+        self.store.data_mut().take_saved_state();
+        self.store.set_fuel(FUEL_PER_CALL).ok();
+        let result = func.call(&mut self.store, ());
+        let state = self.store.data_mut().take_saved_state();
+        result.ok().filter(|()| !state.is_empty()).map(|()| state)
+    }
+
+    /// Hand `state` from the previous build to the cart's `rico8_load_state`.
+    /// True only if the cart accepted it and read every byte; on false the
+    /// game may be half-loaded, so the caller should boot a fresh VM.
+    pub fn load_state(&mut self, state: &[u8]) -> bool {
+        let Ok(func) = self
+            ._instance
+            .get_typed_func::<(), u32>(&self.store, "rico8_load_state")
+        else {
+            return false;
+        };
+        self.store.data_mut().set_saved_state(state.to_vec());
+        self.store.set_fuel(FUEL_PER_CALL).ok();
+        let accepted = matches!(func.call(&mut self.store, ()), Ok(n) if n != 0);
+        let read_all = self.store.data().saved_state_fully_read();
+        self.store.data_mut().take_saved_state();
+        accepted && read_all
+    }
+
+    /// Capture the VM between frames; see [`VmSnapshot`].
+    pub fn snapshot(&self) -> VmSnapshot {
+        let mut snap = VmSnapshot::default();
+        self.snapshot_into(&mut snap);
+        snap
+    }
+
+    /// [`GameVm::snapshot`] into an earlier snapshot, reusing its buffers.
+    fn snapshot_into(&self, snap: &mut VmSnapshot) {
+        snap.memory.clear();
+        if let Some(mem) = self._instance.get_memory(&self.store, "memory") {
+            snap.memory.extend_from_slice(mem.data(&self.store));
+        }
+        snap.globals.clear();
+        snap.globals
+            .extend(self.globals.iter().map(|g| g.get(&self.store)));
+        let st = self.store.data();
+        snap.fb.clone_from(&st.fb);
+        snap.input.clone_from(&st.input);
+        snap.sprites.clone_from(&st.sprites);
+        snap.map.clone_from(&st.map);
+        snap.frame = st.frame;
+        snap.rng = st.rng;
+        snap.cpu = (st.last_update_cpu, st.last_draw_cpu);
+        snap.fuel = (st.last_update_fuel, st.last_draw_fuel);
+    }
+
+    /// Rewind to a snapshot taken from this VM. Wasm memory cannot shrink,
+    /// so if it has grown since, the cart is re-instantiated first (keeping
+    /// the host state): the cart then sees exactly the memory size it had.
+    pub fn restore(&mut self, snap: &VmSnapshot) -> Result<()> {
+        let grown = self
+            ._instance
+            .get_memory(&self.store, "memory")
+            .is_some_and(|m| m.data_size(&self.store) > snap.memory.len());
+        if grown {
+            self.reinstantiate()?;
+        }
+        if let Some(mem) = self._instance.get_memory(&self.store, "memory") {
+            let have = mem.data_size(&self.store);
+            if snap.memory.len() > have {
+                let pages = ((snap.memory.len() - have) / 65536) as u64;
+                mem.grow(&mut self.store, pages)
+                    .map_err(|e| anyhow!("Restoring memory: {e}"))?;
+            }
+            mem.data_mut(&mut self.store).copy_from_slice(&snap.memory);
+        }
+        for (global, val) in self.globals.iter().zip(&snap.globals) {
+            global
+                .set(&mut self.store, val.clone())
+                .map_err(|e| anyhow!("Restoring a global: {e}"))?;
+        }
+        let st = self.store.data_mut();
+        st.fb = snap.fb.clone();
+        st.input = snap.input.clone();
+        st.sprites = snap.sprites.clone();
+        st.map = snap.map.clone();
+        st.frame = snap.frame;
+        st.rng = snap.rng;
+        (st.last_update_cpu, st.last_draw_cpu) = snap.cpu;
+        (st.last_update_fuel, st.last_draw_fuel) = snap.fuel;
+        Ok(())
+    }
+
+    /// Name the cart's call stack at a trap in `phase`, by replaying the frame
+    /// from `pre_frame` on a traced copy of the cart (see
+    /// [`crate::backtrace`]). Empty if the cart has no names or the replay
+    /// does not trap.
+    fn trace_trap(&self, phase: &str) -> Vec<String> {
+        let (Some(symbols), Some(before)) = (&self.symbols, &self.pre_frame) else {
+            return Vec::new();
+        };
+        let Ok(stack) = self.replay_traced(&symbols.wasm, before, phase) else {
+            return Vec::new();
+        };
+        crate::backtrace::symbolize(&stack, &symbols.names)
+    }
+
+    /// The traced call stack where the replay trapped, outermost first.
+    fn replay_traced(&self, wasm: &[u8], before: &VmSnapshot, phase: &str) -> Result<Vec<u32>> {
+        let traced = crate::backtrace::instrument(wasm).map_err(|e| anyhow!(e))?;
+        let engine = self.module.engine();
+        let module = Module::new(engine, &traced[..])?;
+        let mut state = HostState::new(&Assets::default(), AudioHandle::dummy());
+        state.replaying = true;
+        state.fps = self.fps();
+        state.measured_fps = self.store.data().measured_fps;
+        let mut store = Store::new(engine, state);
+        store.limiter(|state| &mut state.limits);
+        let (instance, [init, update, draw]) = instantiate(&mut store, &self.linker, &module)?;
+        let globals = mutable_globals(&store, &instance);
+        let mut vm = Self {
+            store,
+            _instance: instance,
+            update,
+            draw,
+            module,
+            linker: self.linker.clone(),
+            globals,
+            symbols: None,
+            pre_frame: None,
+            memory_map: MemoryMap::default(),
+        };
+        vm.restore(before)?;
+        // Room for the tracing calls on top of what the frame itself used.
+        let run = |store: &mut Store<HostState>, func: TypedFunc<(), ()>| {
+            store.data_mut().call_stack.clear();
+            store.set_fuel(FUEL_PER_CALL * 4).ok();
+            func.call(&mut *store, ()).is_err()
+        };
+        let trapped = match phase {
+            "init" => run(&mut vm.store, init),
+            _ => {
+                vm.store.data_mut().input.tick();
+                let trapped = run(&mut vm.store, vm.update);
+                vm.store.data_mut().frame += 1;
+                if phase == "draw" && !trapped {
+                    run(&mut vm.store, vm.draw)
+                } else {
+                    trapped
+                }
+            }
+        };
+        if !trapped {
+            return Err(anyhow!("The replay did not trap"));
+        }
+        Ok(std::mem::take(&mut vm.store.data_mut().call_stack))
+    }
+
+    /// A fresh store and instance of the same module, carrying the host state
+    /// over. Without running `rico8_init`: the caller overwrites memory next.
+    fn reinstantiate(&mut self) -> Result<()> {
+        let engine = self.module.engine().clone();
+        let placeholder = HostState::new(&Assets::default(), self.store.data().audio.clone());
+        let state =
+            std::mem::replace(&mut self.store, Store::new(&engine, placeholder)).into_data();
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        let (instance, [_, update, draw]) = instantiate(&mut store, &self.linker, &self.module)?;
+        self.globals = mutable_globals(&store, &instance);
+        (self.store, self._instance, self.update, self.draw) = (store, instance, update, draw);
+        Ok(())
+    }
+
+    /// CRC-32 over linear memory and the screen: equal on two machines that
+    /// ran the same cart with the same input, so netplay peers compare it to
+    /// catch a desync.
+    pub fn state_hash(&self) -> u32 {
+        let mut h = crc32fast::Hasher::new();
+        if let Some(mem) = self._instance.get_memory(&self.store, "memory") {
+            h.update(mem.data(&self.store));
+        }
+        h.update(self.store.data().fb.pixels());
+        h.finalize()
+    }
+
     pub fn state(&self) -> &HostState {
         self.store.data()
     }
@@ -663,12 +1301,14 @@ mod tests {
           (import "rico8" "is_button_down" (func $btn (param i32) (result i32)))
           (import "rico8" "print" (func $print (param i32 i32 i32 i32 i32) (result i32)))
           (import "rico8" "log" (func $log (param i32 i32)))
+          (import "rico8" "log_level" (func $log_level (param i32 i32 i32)))
           (memory (export "memory") 1)
           (data (i32.const 16) "hi from cart")
           (global $x (mut i32) (i32.const 5))
           (func (export "rico8_init")
             (call $log (i32.const 16) (i32.const 12)))
           (func (export "rico8_update")
+            (call $log_level (i32.const 2) (i32.const 16) (i32.const 2))
             (if (i32.ne (call $btn (i32.const 1)) (i32.const 0))
               (then (global.set $x (i32.add (global.get $x) (i32.const 1))))))
           (func (export "rico8_draw")
@@ -702,6 +1342,21 @@ mod tests {
           (func (export "rico8_mem_used") (result i32) (i32.const 32768)))
     "#;
 
+    /// Counts updates at address 0 and carries the count across a hot reload.
+    const STATEFUL_CART: &str = r#"
+        (module
+          (import "rico8" "state_write" (func $save (param i32 i32)))
+          (import "rico8" "state_read" (func $load (param i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1))))
+          (func (export "rico8_draw"))
+          (func (export "rico8_save_state") (call $save (i32.const 0) (i32.const 4)))
+          (func (export "rico8_load_state") (result i32)
+            (i32.eq (call $load (i32.const 0) (i32.const 4)) (i32.const 4))))
+    "#;
+
     /// Update loops ~10k times — well under the 131,072-fuel budget.
     const BUDGET_OK_CART: &str = r#"
         (module
@@ -756,6 +1411,22 @@ mod tests {
           (func (export "rico8_draw")))
     "#;
 
+    /// Fills channel 0 at priority 9, then asks for an auto-routed sfx at
+    /// priority 1 once every other channel is busy too.
+    const SFX_PRIORITY_CART: &str = r#"
+        (module
+          (import "rico8" "sfx" (func $sfx (param i32 i32)))
+          (import "rico8" "sfx_priority" (func $sfxp (param i32 i32 i32)))
+          (func (export "rico8_init"))
+          (func (export "rico8_update")
+            (call $sfxp (i32.const 5) (i32.const 0) (i32.const 9))
+            (call $sfx (i32.const 6) (i32.const 1))
+            (call $sfx (i32.const 6) (i32.const 2))
+            (call $sfx (i32.const 6) (i32.const 3))
+            (call $sfxp (i32.const 7) (i32.const -1) (i32.const 1)))
+          (func (export "rico8_draw")))
+    "#;
+
     const PARITY_CART: &str = r#"
         (module
           (import "rico8" "ellipse" (func $ovalo (param i32 i32 i32 i32 i32)))
@@ -819,12 +1490,76 @@ mod tests {
         assert_eq!(vm.state().fb.pget(4, 4), 8, "oval filled the box center");
     }
 
+    #[test]
+    fn sfx_priority_import_protects_the_important_voice() {
+        let audio = AudioHandle::dummy();
+        let wasm = wat::parse_str(SFX_PRIORITY_CART).unwrap();
+        let mut vm = GameVm::load(&wasm, &Assets::default(), audio.clone()).unwrap();
+        vm.call_update().unwrap();
+        let chans = audio.with_synth(|s| s.channel_sfx());
+        assert_eq!(chans[0], Some(5), "priority 9 survives");
+        assert_eq!(chans[1], Some(7), "the oldest priority-0 voice is stolen");
+    }
+
+    #[test]
+    fn replayed_frames_leave_the_music_alone() {
+        // Alternates music(2) and music(-1), storing what it got back at 0.
+        let wasm = wat::parse_str(
+            r#"
+            (module
+              (import "rico8" "music" (func $music (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (global $calls (mut i32) (i32.const 0))
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (i32.store (i32.const 0)
+                  (call $music
+                    (select (i32.const 2) (i32.const -1)
+                      (i32.eqz (i32.and (global.get $calls) (i32.const 1))))
+                    (i32.const 0) (i32.const 0) (i32.const 0)))
+                (global.set $calls (i32.add (global.get $calls) (i32.const 1))))
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        // Pattern 2 plays SFX 0, a single audible note.
+        let mut assets = Assets::default();
+        assets.sfx[0].notes[0].volume = 5;
+        assets.music[2].channels[0] = Some(0);
+        let audio = AudioHandle::dummy();
+        let mut vm = GameVm::load(&wasm, &assets, audio.clone()).unwrap();
+        let returned =
+            |vm: &GameVm| i32::from_le_bytes(vm.snapshot().memory[..4].try_into().unwrap());
+        vm.call_update().unwrap();
+        let token = returned(&vm);
+        assert_ne!(token, 0);
+
+        vm.state_mut().replaying = true;
+        vm.call_update().unwrap();
+        assert_eq!(
+            audio.with_synth(|s| s.playing_pattern()),
+            Some(2),
+            "not stopped"
+        );
+        vm.call_update().unwrap();
+        assert_eq!(returned(&vm), token, "the first run's token");
+        assert_eq!(
+            audio.with_synth(|s| s.music_token()),
+            token,
+            "not restarted"
+        );
+    }
+
     #[test]
     fn abi_lifecycle_and_drawing() {
         let mut vm = load_test_vm(TEST_CART).unwrap();
-        assert_eq!(vm.state_mut().logs.pop().as_deref(), Some("hi from cart"));
+        let init_log = vm.state_mut().logs.pop().unwrap();
+        assert_eq!(init_log.text, "hi from cart");
+        assert_eq!((init_log.level, init_log.frame), (LogLevel::Info, 0));
 
         vm.call_update().unwrap();
+        let warn = vm.state_mut().logs.pop().unwrap();
+        assert_eq!((warn.level, warn.text.as_str()), (LogLevel::Warn, "hi"));
         vm.call_draw().unwrap();
         assert_eq!(vm.state().fb.pget(5, 7), 8, "set_pixel through ABI");
         assert_eq!(vm.state().fb.pget(0, 0), 7, "print drew a glyph pixel");
@@ -864,6 +1599,29 @@ mod tests {
         assert_eq!(vm2.mem_used_bytes(), 0);
     }
 
+    #[test]
+    fn game_state_carries_across_a_hot_reload() {
+        let mut old = load_test_vm(STATEFUL_CART).unwrap();
+        for _ in 0..3 {
+            old.call_update().unwrap();
+        }
+        let state = old.save_state().unwrap();
+        assert_eq!(state, 3u32.to_le_bytes());
+
+        let mut new = load_test_vm(STATEFUL_CART).unwrap();
+        assert!(new.load_state(&state));
+        new.call_update().unwrap();
+        assert_eq!(new.save_state().unwrap(), 4u32.to_le_bytes());
+
+        // Leftover bytes mean the layout changed: cold boot.
+        let mut grown = load_test_vm(STATEFUL_CART).unwrap();
+        assert!(!grown.load_state(&[0; 8]));
+        // Carts without the exports never keep state.
+        let mut plain = load_test_vm(TEST_CART).unwrap();
+        assert_eq!(plain.save_state(), None);
+        assert!(!plain.load_state(&state));
+    }
+
     #[test]
     fn infinite_loop_is_trapped() {
         let mut vm = load_test_vm(LOOPING_CART).unwrap();
@@ -901,6 +1659,11 @@ mod tests {
             vm.call_update().is_ok(),
             "10k-iteration frame must fit the 128K-fuel budget"
         );
+        let used = vm.fuel_update();
+        assert!((10_000..FUEL_PER_CALL).contains(&used), "used {used}");
+        let frac = used as f32 / FUEL_PER_CALL as f32;
+        assert!((vm.cpu_update() - frac).abs() < 1e-6);
+        assert_eq!(vm.fuel_draw(), 0, "draw has not run yet");
     }
 
     #[test]
@@ -964,6 +1727,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn snapshot_restore_rewinds_the_whole_frame_state() {
+        // Counts frames in memory and in a global it does not export, draws
+        // the count and grows memory on the third frame.
+        const COUNTER_CART: &str = r#"
+            (module
+              (import "rico8" "set_pixel" (func $pset (param i32 i32 i32)))
+              (import "rico8" "set_map_tile" (func $mset (param i32 i32 i32)))
+              (memory (export "memory") 1)
+              (global $g (mut i32) (i32.const 0))
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+                (global.set $g (i32.add (global.get $g) (i32.const 1)))
+                (call $mset (global.get $g) (i32.const 0) (i32.const 5))
+                (if (i32.eq (global.get $g) (i32.const 3))
+                  (then (drop (memory.grow (i32.const 1))))))
+              (func (export "rico8_draw")
+                (call $pset (i32.load (i32.const 0)) (i32.const 0) (i32.const 7))))
+        "#;
+        let mut vm = load_test_vm(COUNTER_CART).unwrap();
+        let step = |vm: &mut GameVm| vm.call_update().and_then(|()| vm.call_draw()).unwrap();
+        step(&mut vm);
+        let snap = vm.snapshot();
+        let hash = vm.state_hash();
+        for _ in 0..4 {
+            step(&mut vm);
+        }
+        assert_eq!(vm.state().map.get(5, 0), 5);
+        assert!(vm.memory_used_fraction() > 0.9, "grew to two pages");
+
+        vm.restore(&snap).unwrap();
+        assert_eq!(vm.state_hash(), hash);
+        assert_eq!(vm.state().frame, 1);
+        assert_eq!(vm.state().fb.pget(2, 0), 0, "later pixels are gone");
+        assert_eq!(vm.state().map.get(5, 0), 0, "map edits are rewound");
+
+        // Replaying lands exactly where a fresh run does, memory size included.
+        let mut again = load_test_vm(COUNTER_CART).unwrap();
+        step(&mut again);
+        for _ in 0..4 {
+            step(&mut vm);
+            step(&mut again);
+        }
+        assert_eq!(vm.state_hash(), again.state_hash());
+        assert_eq!(vm.memory_used_fraction(), again.memory_used_fraction());
+    }
+
     #[test]
     fn fps_falls_back_to_target_until_measured() {
         // No frontend measurement yet: report the cart's target rate (30).
@@ -973,4 +1784,111 @@ mod tests {
         vm.state_mut().set_measured_fps(58.0);
         assert_eq!(vm.state().measured_fps_or_target(), 58.0);
     }
+
+    /// Counts updates in memory and, on the third, reads far out of bounds
+    /// two calls deep. Draw recurses without end. Named like a Rust cart.
+    const TRAPPING_CART: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func $_ZN4game4deep17h0123456789abcdefE (param i32) (result i32)
+            (i32.load (local.get 0)))
+          (func $_ZN4game4step17hfedcba9876543210E
+            (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+            (if (i32.eq (i32.load (i32.const 0)) (i32.const 3))
+              (then (drop (call $_ZN4game4deep17h0123456789abcdefE (i32.const 0x7fff0000))))))
+          (func $_ZN4game4dive17h0000000000000000E (param i32)
+            (call $_ZN4game4dive17h0000000000000000E (i32.add (local.get 0) (i32.const 1))))
+          (func $rico8_init (export "rico8_init"))
+          (func $rico8_update (export "rico8_update")
+            (call $_ZN4game4step17hfedcba9876543210E))
+          (func $rico8_draw (export "rico8_draw")
+            (if (i32.eq (i32.load (i32.const 0)) (i32.const 2))
+              (then (call $_ZN4game4dive17h0000000000000000E (i32.const 0)))))
+        )
+    "#;
+
+    #[test]
+    fn traps_carry_a_named_backtrace() {
+        let mut vm = load_test_vm(TRAPPING_CART).unwrap();
+        vm.call_update().unwrap();
+        vm.call_draw().unwrap();
+        vm.call_update().unwrap();
+        let e = vm.call_draw().unwrap_err();
+        assert_eq!(e.backtrace.len(), 2, "{e:#}");
+        assert!(e.backtrace[0].starts_with("game::dive (x"), "{e:#}");
+        assert_eq!(e.backtrace[1], "rico8_draw");
+
+        let mut vm = load_test_vm(TRAPPING_CART).unwrap();
+        for _ in 0..2 {
+            vm.call_update().unwrap();
+        }
+        let e = vm.call_update().unwrap_err();
+        assert_eq!(e.backtrace, ["game::deep", "game::step", "rico8_update"]);
+        let screen = e.to_string();
+        assert!(screen.ends_with("\nin game::deep\nin game::step\nin rico8_update"));
+        assert_eq!(format!("{e:#}"), screen, "short traces print whole");
+
+        // The snapshot each frame starts with reuses the last one's buffers.
+        let mut vm = load_test_vm(TRAPPING_CART).unwrap();
+        vm.call_update().unwrap();
+        let kept = |vm: &GameVm| {
+            let before = vm.pre_frame.as_ref().unwrap();
+            (before.memory.as_ptr(), before.fb.pixels().as_ptr())
+        };
+        let first = kept(&vm);
+        vm.call_update().unwrap();
+        assert_eq!(kept(&vm), first);
+    }
+
+    #[test]
+    fn panics_carry_their_location() {
+        let mut vm = load_test_vm(
+            r#"
+            (module
+              (import "rico8" "panic" (func $panic (param i32 i32)))
+              (import "rico8" "panic_location" (func $at (param i32 i32 i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "src/enemy.rsboom")
+              (func (export "rico8_init"))
+              (func (export "rico8_update")
+                (call $at (i32.const 0) (i32.const 12) (i32.const 42) (i32.const 9))
+                (call $panic (i32.const 12) (i32.const 4))
+                unreachable)
+              (func (export "rico8_draw")))
+            "#,
+        )
+        .unwrap();
+        let e = vm.call_update().unwrap_err();
+        assert_eq!(e.message, "boom");
+        let at = e.location.unwrap();
+        assert_eq!(
+            (at.file.as_str(), at.line, at.column),
+            ("src/enemy.rs", 42, 9)
+        );
+        assert_eq!(at.project_file(), Some("enemy.rs"));
+        assert!(vm.state().panic_location.is_none(), "taken with the error");
+    }
+
+    #[test]
+    fn only_flat_src_files_are_project_files() {
+        let at = |file: &str| PanicLocation {
+            file: file.into(),
+            line: 1,
+            column: 1,
+        };
+        assert_eq!(at("src/lib.rs").project_file(), Some("lib.rs"));
+        assert_eq!(at("src/enemy/mod.rs").project_file(), None);
+        assert_eq!(
+            at("/home/me/.cargo/registry/src/rico8/lib.rs").project_file(),
+            None
+        );
+    }
+
+    #[test]
+    fn carts_without_names_trap_without_a_backtrace() {
+        let mut vm = load_test_vm(LOOPING_CART).unwrap();
+        let e = vm.call_update().unwrap_err();
+        assert!(e.backtrace.is_empty());
+        assert!(!e.to_string().contains("\nin "));
+    }
 }
@@ -0,0 +1,176 @@
+//! Offline audio rendering: run the synth without a device and write the
+//! result as a WAV file, so songs and effects can be shared as audio.
+//!
+//! The renders drive the same `Synth` the runtime plays through, so the
+//! output matches what a running cart sounds like sample for sample.
+
+use crate::{
+    assets::{Assets, MUSIC_COUNT, SFX_COUNT},
+    audio::{OscQuality, Synth},
+};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Output rate for rendered audio.
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Default cap on a render, for looping songs and SFX that never end.
+pub const DEFAULT_SECONDS: f32 = 60.0;
+
+/// Render the song starting at pattern `n` until it stops, or until
+/// `max_seconds` have been rendered if it loops.
+pub fn render_music(
+    assets: &Assets,
+    n: usize,
+    max_seconds: f32,
+    quality: OscQuality,
+) -> Result<Vec<f32>> {
+    if n >= MUSIC_COUNT {
+        bail!("Music pattern {n} out of range (0-{})", MUSIC_COUNT - 1);
+    }
+    if assets.music.get(n).is_none_or(|p| p.is_empty()) {
+        bail!("Music pattern {n} is empty");
+    }
+    let mut synth = Synth::new(SAMPLE_RATE as f32);
+    synth.set_quality(quality);
+    synth.load(assets.sfx.clone(), assets.music.clone());
+    synth.play_music(n as i32, 0, 0, 0);
+    Ok(render_until(&mut synth, max_seconds, |s| {
+        s.playing_pattern().is_none()
+    }))
+}
+
+/// Render SFX `n` on its own until it ends, or until `max_seconds` have been
+/// rendered if it loops.
+pub fn render_sfx(
+    assets: &Assets,
+    n: usize,
+    max_seconds: f32,
+    quality: OscQuality,
+) -> Result<Vec<f32>> {
+    if n >= SFX_COUNT {
+        bail!("SFX {n} out of range (0-{})", SFX_COUNT - 1);
+    }
+    if assets.sfx.get(n).is_none_or(|s| s.is_empty()) {
+        bail!("SFX {n} is empty");
+    }
+    let mut synth = Synth::new(SAMPLE_RATE as f32);
+    synth.set_quality(quality);
+    synth.load(assets.sfx.clone(), assets.music.clone());
+    synth.play_sfx(n as i32, 0);
+    Ok(render_until(&mut synth, max_seconds, |s| {
+        s.channel_sfx()[0].is_none()
+    }))
+}
+
+/// Pull samples until `done` reports the synth has gone quiet or the cap hits.
+fn render_until(synth: &mut Synth, max_seconds: f32, done: impl Fn(&Synth) -> bool) -> Vec<f32> {
+    let cap = (max_seconds.max(0.0) * SAMPLE_RATE as f32) as usize;
+    let mut samples = Vec::new();
+    while samples.len() < cap {
+        samples.push(synth.next_sample());
+        if done(synth) {
+            break;
+        }
+    }
+    samples
+}
+
+/// Encode mono samples as a 16-bit PCM WAV file.
+pub fn encode(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(44 + samples.len() * 2);
+    let data_len = (samples.len() * 2) as u32;
+    let byte_rate = sample_rate * 2;
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&(36 + data_len).to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    buf.extend_from_slice(&1u16.to_le_bytes()); // mono
+    buf.extend_from_slice(&sample_rate.to_le_bytes());
+    buf.extend_from_slice(&byte_rate.to_le_bytes());
+    buf.extend_from_slice(&2u16.to_le_bytes()); // block align
+    buf.extend_from_slice(&16u16.to_le_bytes()); // bits
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_len.to_le_bytes());
+    for &s in samples {
+        let v = (s.clamp(-1.0, 1.0) * 32767.0) as i16;
+        buf.extend_from_slice(&v.to_le_bytes());
+    }
+    buf
+}
+
+/// Encode and write a WAV file.
+pub fn write(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    std::fs::write(path, encode(samples, sample_rate))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::{MusicPattern, Note};
+
+    fn assets_with_tune() -> Assets {
+        let mut assets = Assets::default();
+        let sfx = &mut assets.sfx[1];
+        sfx.speed = 4;
+        for (i, n) in sfx.notes.iter_mut().enumerate().take(8) {
+            *n = Note {
+                pitch: 24 + i as u8,
+                wave: 1,
+                volume: 5,
+                effect: 0,
+            };
+        }
+        assets.music[0] = MusicPattern {
+            channels: [Some(1), None, None, None],
+            stop_at_end: true,
+            ..Default::default()
+        };
+        assets
+    }
+
+    #[test]
+    fn wav_header_describes_mono_16_bit() {
+        let bytes = encode(&[0.0, 1.0, -1.0], 22050);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..12], b"WAVE");
+        assert_eq!(u16::from_le_bytes([bytes[22], bytes[23]]), 1, "mono");
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 22050);
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()), 6);
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(i16::from_le_bytes([bytes[46], bytes[47]]), 32767);
+    }
+
+    #[test]
+    fn sfx_render_stops_when_the_sfx_ends() {
+        let assets = assets_with_tune();
+        let samples = render_sfx(&assets, 1, DEFAULT_SECONDS, OscQuality::Authentic).unwrap();
+        // 32 steps at speed 4: 32 * 4 * 183 / 22050 ~= 1.06 s.
+        let secs = samples.len() as f32 / SAMPLE_RATE as f32;
+        assert!((0.9..1.2).contains(&secs), "rendered {secs}s");
+        assert!(samples.iter().any(|s| s.abs() > 0.01), "audible");
+    }
+
+    #[test]
+    fn music_render_follows_the_song_and_honours_the_cap() {
+        let mut assets = assets_with_tune();
+        let song = render_music(&assets, 0, DEFAULT_SECONDS, OscQuality::Authentic).unwrap();
+        assert!(!song.is_empty() && song.len() < 2 * SAMPLE_RATE as usize);
+
+        assets.music[0].stop_at_end = false;
+        assets.music[0].loop_back = true;
+        let looped = render_music(&assets, 0, 2.0, OscQuality::Authentic).unwrap();
+        assert_eq!(looped.len(), 2 * SAMPLE_RATE as usize);
+    }
+
+    #[test]
+    fn empty_or_out_of_range_is_an_error() {
+        let assets = assets_with_tune();
+        assert!(render_sfx(&assets, 0, 1.0, OscQuality::Authentic).is_err());
+        assert!(render_sfx(&assets, 64, 1.0, OscQuality::Authentic).is_err());
+        assert!(render_music(&assets, 1, 1.0, OscQuality::Authentic).is_err());
+    }
+}
@@ -0,0 +1,584 @@
+//! # rico8-test — unit-test carts natively with a real software console
+//!
+//! On wasm the SDK's imports go to the console; on the host they go to
+//! whatever [`rico8::host::Host`] is installed, and do nothing otherwise.
+//! [`TestConsole`] installs one built on the console's own framebuffer,
+//! input and asset code, so drawing, map reads and input behave exactly as
+//! they do in the real thing:
+//!
+//! ```ignore
+//! use rico8::Button;
+//! use rico8_test::TestConsole;
+//!
+//! #[test]
+//! fn walks_right() {
+//!     let mut con = TestConsole::new(MyGame { x: 64, y: 64 });
+//!     con.hold(Button::Right);
+//!     con.step_frames(10);
+//!     assert_eq!(con.game().x, 74);
+//!     assert_eq!(con.pixel(74, 64), Color::WHITE);
+//! }
+//! ```
+//!
+//! Add it under `[dev-dependencies]` and make the cart
+//! `#![cfg_attr(not(test), no_std)]`. Projects default to the wasm target,
+//! so run the tests for the host: `cargo test --target x86_64-unknown-linux-gnu`
+//! (or whatever `rustc -vV` lists as `host`).
+
+use rico8::{host::Host, BitFlags, Button, Color, Game};
+use rico8_runtime::{
+    assets::{Assets, SPRITE_COUNT},
+    audio::AudioHandle,
+    fb::Framebuffer,
+    log::{LogLevel, LogLine},
+    vm::{HostState, PanicLocation},
+};
+use std::path::Path;
+
+/// A cart's game running on a software console, one frame at a time.
+pub struct TestConsole<G: Game> {
+    game: G,
+    host: SoftHost,
+}
+
+impl<G: Game> TestConsole<G> {
+    /// Run `game` with empty assets: a blank sprite sheet, map and sounds.
+    pub fn new(game: G) -> Self {
+        Self::with_assets(game, &Assets::default())
+    }
+
+    /// Run `game` with the cart's assets (see [`load_assets`]).
+    pub fn with_assets(game: G, assets: &Assets) -> Self {
+        let audio = AudioHandle::dummy();
+        audio.load(assets.sfx.clone(), assets.music.clone());
+        let mut state = HostState::new(assets, audio);
+        state.fps = G::FRAME_RATE.fps();
+        Self {
+            game,
+            host: SoftHost(state),
+        }
+    }
+
+    /// Hold `button` down until [`release`](Self::release)d.
+    pub fn hold(&mut self, button: Button) {
+        self.host.0.input.set_button(button_index(button), true);
+    }
+
+    /// Let go of `button`.
+    pub fn release(&mut self, button: Button) {
+        self.host.0.input.set_button(button_index(button), false);
+    }
+
+    /// Hold exactly `buttons`, releasing every other.
+    pub fn set_buttons(&mut self, buttons: BitFlags<Button>) {
+        for (i, b) in [
+            Button::Left,
+            Button::Right,
+            Button::Up,
+            Button::Down,
+            Button::O,
+            Button::X,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            self.host.0.input.set_button(i, buttons.contains(b));
+        }
+    }
+
+    /// Tap `button`: hold it for one frame, then run a released frame so
+    /// the next tap registers as a fresh press. Takes two frames.
+    pub fn press(&mut self, button: Button) {
+        self.hold(button);
+        self.step();
+        self.release(button);
+        self.step();
+    }
+
+    /// Run one frame exactly as the console does: tick input, `update`,
+    /// then `draw`.
+    pub fn step(&mut self) {
+        let Self { game, host } = self;
+        host.0.input.tick();
+        rico8::host::with_host(host, || rico8::host::update(game));
+        host.0.frame += 1;
+        rico8::host::with_host(host, || rico8::host::draw(game));
+    }
+
+    /// Run `n` frames.
+    pub fn step_frames(&mut self, n: usize) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Hot-reload into `fresh`, the rebuilt cart's newly made game, as the
+    /// console does: the running game's `save_state` feeds `fresh`'s
+    /// `load_state`. On success `fresh` takes over and the console (screen,
+    /// map and sprite edits, frame count) carries on; on `false` the old
+    /// game keeps running, where the console would cold-boot.
+    pub fn hot_reload(&mut self, mut fresh: G) -> bool {
+        let Self { game, host } = self;
+        host.0.take_saved_state();
+        rico8::host::with_host(host, || rico8::host::save_state(game));
+        let state = host.0.take_saved_state();
+        host.0.set_saved_state(state);
+        let loaded = rico8::host::with_host(host, || rico8::host::load_state(&mut fresh));
+        let accepted = loaded && host.0.saved_state_fully_read();
+        host.0.take_saved_state();
+        if accepted {
+            self.game = fresh;
+        }
+        accepted
+    }
+
+    /// Frames run so far.
+    pub fn frame(&self) -> u64 {
+        self.host.0.frame
+    }
+
+    /// The game's state, for asserting on it directly.
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    /// The game's state, for setting up a scenario.
+    pub fn game_mut(&mut self) -> &mut G {
+        &mut self.game
+    }
+
+    /// The screen color at `(x, y)` as last drawn (black off-screen).
+    pub fn pixel(&self, x: i32, y: i32) -> Color {
+        Color::new(self.host.0.fb.pget(x, y)).unwrap_or(Color::BLACK)
+    }
+
+    /// The whole screen, for region checks or hashing.
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.host.0.fb
+    }
+
+    /// The text of every line the game has logged so far.
+    pub fn logs(&self) -> Vec<&str> {
+        self.host.0.logs.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    /// Every line the game has logged so far, with its level and frame.
+    pub fn log_lines(&self) -> &[LogLine] {
+        &self.host.0.logs
+    }
+
+    /// The full console state: sprites, map, audio and the rest.
+    pub fn state(&self) -> &HostState {
+        &self.host.0
+    }
+
+    pub fn state_mut(&mut self) -> &mut HostState {
+        &mut self.host.0
+    }
+}
+
+/// Load a project's `assets.rico8`, e.g. `load_assets("assets.rico8")` from a
+/// cart's tests (which run in the cart's directory).
+pub fn load_assets(path: impl AsRef<Path>) -> anyhow::Result<Assets> {
+    let path = path.as_ref();
+    let bytes =
+        std::fs::read(path).map_err(|e| anyhow::anyhow!("Reading {}: {e}", path.display()))?;
+    rico8_runtime::project::decode_assets(&bytes)
+}
+
+fn button_index(b: Button) -> usize {
+    (b as u8).trailing_zeros() as usize
+}
+
+/// The `"rico8"` imports on top of the runtime's host state, mirroring the
+/// wasm linker in `rico8_runtime::vm` call for call.
+struct SoftHost(HostState);
+
+impl Host for SoftHost {
+    fn clear(&mut self, color: i32) {
+        self.0.fb.cls(color as u8)
+    }
+
+    fn camera(&mut self, x: i32, y: i32) {
+        self.0.fb.camera(x, y)
+    }
+
+    fn clip(&mut self, x: i32, y: i32, w: i32, h: i32) {
+        self.0.fb.clip(x, y, w, h)
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: i32) {
+        self.0.fb.pset(x, y, color as u8)
+    }
+
+    fn pixel(&mut self, x: i32, y: i32) -> i32 {
+        self.0.fb.pget(x, y) as i32
+    }
+
+    fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        self.0.fb.line(x0, y0, x1, y1, color as u8)
+    }
+
+    fn rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        self.0.fb.rect(x0, y0, x1, y1, color as u8)
+    }
+
+    fn rect_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        self.0.fb.rectfill(x0, y0, x1, y1, color as u8)
+    }
+
+    fn circle(&mut self, x: i32, y: i32, r: i32, color: i32) {
+        self.0.fb.circ(x, y, r, color as u8)
+    }
+
+    fn circle_fill(&mut self, x: i32, y: i32, r: i32, color: i32) {
+        self.0.fb.circfill(x, y, r, color as u8)
+    }
+
+    fn print(&mut self, text: &str, x: i32, y: i32, color: i32) -> i32 {
+        self.0.fb.print(text, x, y, color as u8)
+    }
+
+    fn is_button_down(&mut self, b: u32) -> i32 {
+        self.0.input.btn(b) as i32
+    }
+
+    fn is_button_pressed(&mut self, b: u32) -> i32 {
+        self.0.input.btnp(b) as i32
+    }
+
+    fn buttons_down(&mut self) -> u32 {
+        self.0.input.btn_mask()
+    }
+
+    fn buttons_pressed(&mut self) -> u32 {
+        self.0.input.btnp_mask()
+    }
+
+    fn sprite(&mut self, n: u32, x: i32, y: i32, w: i32, h: i32, flip_x: i32, flip_y: i32) {
+        let HostState { fb, sprites, .. } = &mut self.0;
+        fb.spr(sprites, n, x, y, w, h, flip_x != 0, flip_y != 0);
+    }
+
+    fn map(
+        &mut self,
+        cel_x: i32,
+        cel_y: i32,
+        sx: i32,
+        sy: i32,
+        cel_w: i32,
+        cel_h: i32,
+        layers: u32,
+    ) {
+        let HostState {
+            fb, sprites, map, ..
+        } = &mut self.0;
+        fb.map(
+            map,
+            sprites,
+            cel_x,
+            cel_y,
+            sx,
+            sy,
+            cel_w,
+            cel_h,
+            layers as u8,
+        );
+    }
+
+    fn map_tile(&mut self, x: i32, y: i32) -> i32 {
+        self.0.map.get(x, y) as i32
+    }
+
+    fn set_map_tile(&mut self, x: i32, y: i32, v: u32) {
+        self.0.map.set(x, y, v as u8)
+    }
+
+    fn sprite_flags(&mut self, n: u32) -> i32 {
+        self.0.sprites.flags(n) as i32
+    }
+
+    fn set_sprite_flags(&mut self, n: u32, flags: u32) {
+        self.0.sprites.flags[(n as usize) % SPRITE_COUNT] = flags as u8;
+    }
+
+    fn sfx(&mut self, n: i32, channel: i32) {
+        self.0.audio.play_sfx(n, channel)
+    }
+
+    fn sfx_priority(&mut self, n: i32, channel: i32, priority: i32) {
+        self.0
+            .audio
+            .play_sfx_priority(n, channel, priority.clamp(0, 255) as u8)
+    }
+
+    fn music(&mut self, n: i32, fade_duration: i32, channel_mask: i32, token: i32) -> i32 {
+        self.0
+            .audio
+            .play_music(n, fade_duration, channel_mask, token)
+    }
+
+    fn time(&mut self) -> f32 {
+        self.0.frame as f32 / self.0.fps as f32
+    }
+
+    fn rnd(&mut self) -> f32 {
+        self.0.next_rand()
+    }
+
+    fn seed_rng(&mut self, seed: u32) {
+        self.0.seed_rand(seed)
+    }
+
+    fn sprite_pixel(&mut self, x: i32, y: i32) -> i32 {
+        self.0.sprites.get(x, y) as i32
+    }
+
+    fn set_sprite_pixel(&mut self, x: i32, y: i32, color: i32) {
+        self.0.sprites.set(x, y, color as u8)
+    }
+
+    fn log(&mut self, text: &str) {
+        self.0.log(LogLevel::Info, text.to_string());
+    }
+
+    fn log_level(&mut self, level: i32, text: &str) {
+        self.0.log(LogLevel::from_abi(level), text.to_string());
+    }
+
+    fn panic(&mut self, text: &str) {
+        self.0.panic_message = Some(text.to_string());
+    }
+
+    fn panic_location(&mut self, file: &str, line: u32, column: u32) {
+        self.0.panic_location = Some(PanicLocation {
+            file: file.to_string(),
+            line,
+            column,
+        });
+    }
+
+    fn set_transparent_color(&mut self, color: i32, transparent: i32) {
+        self.0
+            .fb
+            .set_transparent_color(color as u8, transparent != 0)
+    }
+
+    fn reset_transparency(&mut self) {
+        self.0.fb.reset_transparency()
+    }
+
+    fn remap_color(&mut self, from: i32, to: i32, mode: i32) {
+        if mode == 0 {
+            self.0.fb.remap_color(from as u8, to as u8);
+        } else {
+            self.0.fb.remap_display_color(from as u8, to as u8);
+        }
+    }
+
+    fn reset_palette(&mut self) {
+        self.0.fb.reset_palette()
+    }
+
+    fn sprite_stretch(
+        &mut self,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        dx: i32,
+        dy: i32,
+        dw: i32,
+        dh: i32,
+        flip_x: i32,
+        flip_y: i32,
+    ) {
+        let HostState { fb, sprites, .. } = &mut self.0;
+        fb.sspr(
+            sprites,
+            sx,
+            sy,
+            sw,
+            sh,
+            dx,
+            dy,
+            dw,
+            dh,
+            flip_x != 0,
+            flip_y != 0,
+        );
+    }
+
+    fn ellipse(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        self.0.fb.oval(x0, y0, x1, y1, color as u8)
+    }
+
+    fn ellipse_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        self.0.fb.ovalfill(x0, y0, x1, y1, color as u8)
+    }
+
+    fn set_fill_pattern(&mut self, pattern: i32, secondary: i32, transparent: i32) {
+        self.0
+            .fb
+            .set_fill_pattern(pattern as u16, secondary as u8, transparent != 0)
+    }
+
+    fn set_pen_color(&mut self, color: i32) {
+        self.0.fb.set_pen_color(color as u8)
+    }
+
+    fn set_cursor(&mut self, x: i32, y: i32) {
+        self.0.fb.set_cursor(x, y)
+    }
+
+    fn print_pen(&mut self, text: &str) -> i32 {
+        self.0.fb.print_pen(text)
+    }
+
+    // No fuel metering natively: report an idle CPU.
+    fn cpu_update(&mut self) -> f32 {
+        0.0
+    }
+
+    fn cpu_draw(&mut self) -> f32 {
+        0.0
+    }
+
+    fn fps(&mut self) -> f32 {
+        self.0.measured_fps_or_target()
+    }
+
+    fn state_write(&mut self, data: &[u8]) {
+        self.0.state_write(data);
+    }
+
+    fn state_read(&mut self, buf: &mut [u8]) -> u32 {
+        self.0.state_read(buf) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rico8::{Context, Graphics, SpriteId, StateReader, StateWriter};
+
+    /// Walks a dot right while Right is held, counts O taps, and drops a
+    /// marker tile where it stands when X is tapped.
+    #[derive(Default)]
+    struct Walker {
+        x: i16,
+        taps: u32,
+        tile_under: Option<SpriteId>,
+    }
+
+    impl Game for Walker {
+        fn update(&mut self, ctx: &mut Context) {
+            if ctx.is_button_down(Button::Right) {
+                self.x += 1;
+            }
+            if ctx.is_button_pressed(Button::O) {
+                self.taps += 1;
+                ctx.log("tap");
+            }
+            if ctx.is_button_pressed(Button::X) {
+                ctx.set_map_tile(self.x, 0, SpriteId(3)).unwrap();
+            }
+            self.tile_under = ctx.map_tile(self.x, 0);
+        }
+
+        fn draw(&self, gfx: &mut Graphics) {
+            gfx.clear(Color::DARK_BLUE);
+            gfx.set_pixel(self.x, 10, Color::WHITE);
+        }
+
+        fn save_state(&self, state: &mut StateWriter) {
+            state.i16(self.x);
+            state.u32(self.taps);
+        }
+
+        fn load_state(&mut self, state: &mut StateReader) -> Option<()> {
+            self.x = state.i16()?;
+            self.taps = state.u32()?;
+            Some(())
+        }
+    }
+
+    #[test]
+    fn held_buttons_move_the_game_and_the_pixels() {
+        let mut con = TestConsole::new(Walker::default());
+        con.hold(Button::Right);
+        con.step_frames(5);
+        con.release(Button::Right);
+        con.step();
+        assert_eq!(con.game().x, 5);
+        assert_eq!(con.frame(), 6);
+        assert_eq!(con.pixel(5, 10), Color::WHITE);
+        assert_eq!(con.pixel(4, 10), Color::DARK_BLUE);
+    }
+
+    #[test]
+    fn taps_fire_once_and_reach_logs_and_map() {
+        let mut con = TestConsole::new(Walker::default());
+        con.press(Button::O);
+        con.press(Button::O);
+        con.game_mut().x = 7;
+        con.press(Button::X);
+        assert_eq!(con.game().taps, 2);
+        assert_eq!(con.logs(), ["tap", "tap"]);
+        assert_eq!(con.game().tile_under, Some(SpriteId(3)));
+        assert_eq!(con.state().map.get(7, 0), 3);
+    }
+
+    #[test]
+    fn log_lines_carry_their_level_and_frame() {
+        struct Warner;
+        impl Game for Warner {
+            fn update(&mut self, ctx: &mut Context) {
+                if ctx.is_button_pressed(Button::X) {
+                    rico8::log!(ctx, rico8::LogLevel::Warn, "hp {}", 3);
+                }
+            }
+            fn draw(&self, _gfx: &mut Graphics) {}
+        }
+        let mut con = TestConsole::new(Warner);
+        con.step_frames(2);
+        con.press(Button::X);
+        let line = &con.log_lines()[0];
+        assert_eq!((line.level, line.text.as_str()), (LogLevel::Warn, "hp 3"));
+        assert_eq!(line.frame, 2);
+    }
+
+    #[test]
+    fn hot_reload_keeps_the_game_where_it_was() {
+        let mut con = TestConsole::new(Walker::default());
+        con.hold(Button::Right);
+        con.step_frames(4);
+        con.press(Button::O);
+        assert!(con.hot_reload(Walker::default()));
+        assert_eq!((con.game().x, con.game().taps), (6, 1));
+        con.step();
+        assert_eq!(con.game().x, 7);
+    }
+
+    #[test]
+    fn games_without_state_hooks_save_nothing() {
+        struct Still;
+        impl Game for Still {
+            fn update(&mut self, _ctx: &mut Context) {}
+            fn draw(&self, _gfx: &mut Graphics) {}
+        }
+        let mut con = TestConsole::new(Still);
+        let TestConsole { game, host } = &mut con;
+        rico8::host::with_host(host, || rico8::host::save_state(game));
+        assert!(host.0.take_saved_state().is_empty(), "not even the version");
+        assert!(!con.hot_reload(Still));
+    }
+
+    #[test]
+    fn no_console_means_no_op_calls() {
+        // Outside a `TestConsole` step the SDK falls back to its no-op stubs.
+        let mut walker = Walker::default();
+        rico8::host::update(&mut walker);
+        assert_eq!(walker.x, 0);
+    }
+}
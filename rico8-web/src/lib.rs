@@ -47,7 +47,9 @@ pub struct Player {
     audio: AudioHandle,
     rgba: Vec<u8>,
     audio_buf: Vec<f32>,
-    errored: bool,
+    /// The full text of the cart's runtime error, backtrace included, once
+    /// it has had one.
+    error: Option<String>,
 }
 
 impl Player {
@@ -60,7 +62,7 @@ impl Player {
             audio: audio.clone(),
             rgba: vec![0; FB_BYTES],
             audio_buf: vec![0.0; AUDIO_CHUNK_MAX],
-            errored: false,
+            error: None,
         };
         match GameVm::load(&cart.wasm, &cart.assets, audio) {
             Ok(vm) => {
@@ -86,7 +88,7 @@ impl Player {
     /// Returns false once the cart has hit a runtime error; the buffer
     /// then holds the error screen.
     pub fn tick(&mut self) -> bool {
-        if self.errored {
+        if self.error.is_some() {
             return false;
         }
         let Some(vm) = &mut self.vm else {
@@ -99,17 +101,22 @@ impl Player {
             }
             Err(e) => {
                 self.fail(&e.to_string());
+                self.error = Some(format!("{e:#}"));
                 false
             }
         }
     }
 
     fn fail(&mut self, message: &str) {
-        self.errored = true;
         self.audio.stop_all();
         error_screen(message).write_rgba(&mut self.rgba);
     }
 
+    /// The cart's runtime error in full, once [`Player::tick`] returned false.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
     pub fn rgba(&self) -> &[u8] {
         &self.rgba
     }
@@ -118,12 +125,7 @@ impl Player {
     /// number rendered.
     pub fn render_audio(&mut self, n: usize) -> usize {
         let n = n.min(AUDIO_CHUNK_MAX);
-        let buf = &mut self.audio_buf[..n];
-        self.audio.with_synth(|s| {
-            for sample in buf.iter_mut() {
-                *sample = s.next_sample();
-            }
-        });
+        self.audio.render_block(&mut self.audio_buf[..n]);
         n
     }
 
@@ -212,13 +214,21 @@ pub extern "C" fn rico8_web_set_button(b: u32, down: i32) {
 }
 
 /// One logical frame. Returns 0 while running, 1 once the cart errored
-/// (the framebuffer then shows the error screen).
+/// (the framebuffer then shows the error screen, and
+/// `rico8_web_error_ptr/len` the full error with its backtrace).
 #[no_mangle]
 pub extern "C" fn rico8_web_tick() -> i32 {
-    match get(&PLAYER) {
-        Some(p) => !p.tick() as i32,
-        None => 1,
+    let Some(p) = get(&PLAYER) else {
+        return 1;
+    };
+    if p.tick() {
+        return 0;
+    }
+    let error = get(&ERROR);
+    if error.is_empty() {
+        *error = p.error().unwrap_or_default().as_bytes().to_vec();
     }
+    1
 }
 
 #[no_mangle]
@@ -302,6 +312,7 @@ mod tests {
         let mut p = Player::load(&png).unwrap();
         assert!(!p.tick(), "infinite loop must be trapped");
         assert!(!p.tick(), "stays errored");
+        assert!(p.error().unwrap().contains("ran too long"));
         // The error screen's top bar is red.
         assert_eq!(&p.rgba()[0..3], &[0xff, 0x00, 0x4d]);
     }
@@ -39,6 +39,7 @@ extern "C" {
     pub fn sprite_flags(n: u32) -> i32;
     pub fn set_sprite_flags(n: u32, flags: u32);
     pub fn sfx(n: i32, channel: i32);
+    pub fn sfx_priority(n: i32, channel: i32, priority: i32);
     pub fn music(n: i32, fade_duration: i32, channel_mask: i32, token: i32) -> i32;
     pub fn time() -> f32;
     pub fn rnd() -> f32;
@@ -46,7 +47,9 @@ extern "C" {
     pub fn sprite_pixel(x: i32, y: i32) -> i32;
     pub fn set_sprite_pixel(x: i32, y: i32, color: i32);
     pub fn log(ptr: *const u8, len: u32);
+    pub fn log_level(level: i32, ptr: *const u8, len: u32);
     pub fn panic(ptr: *const u8, len: u32);
+    pub fn panic_location(ptr: *const u8, len: u32, line: u32, column: u32);
     pub fn set_transparent_color(color: i32, transparent: i32);
     pub fn reset_transparency();
     pub fn remap_color(from: i32, to: i32, mode: i32);
@@ -72,113 +75,249 @@ extern "C" {
     pub fn cpu_update() -> f32;
     pub fn cpu_draw() -> f32;
     pub fn fps() -> f32;
+    pub fn state_write(ptr: *const u8, len: u32);
+    pub fn state_read(ptr: *mut u8, len: u32) -> u32;
 }
 
 // Host-target stubs so the SDK (and carts) also type-check, document and
-// unit-test on native targets. Real behavior only exists on wasm32.
+// unit-test on native targets. Each forwards to the thread's installed
+// [`Host`](crate::host::Host), if any, and otherwise does nothing.
 #[cfg(not(target_arch = "wasm32"))]
 mod stubs {
     #![allow(clippy::missing_safety_doc)]
 
-    pub unsafe fn clear(_color: i32) {}
-    pub unsafe fn camera(_x: i32, _y: i32) {}
-    pub unsafe fn clip(_x: i32, _y: i32, _w: i32, _h: i32) {}
-    pub unsafe fn set_pixel(_x: i32, _y: i32, _color: i32) {}
-    pub unsafe fn pixel(_x: i32, _y: i32) -> i32 {
-        0
+    use crate::host::dispatch;
+
+    /// The SDK only passes `&str`s, so the bytes are always valid UTF-8.
+    unsafe fn text<'a>(ptr: *const u8, len: u32) -> &'a str {
+        core::str::from_utf8(core::slice::from_raw_parts(ptr, len as usize)).unwrap_or_default()
     }
-    pub unsafe fn line(_x0: i32, _y0: i32, _x1: i32, _y1: i32, _color: i32) {}
-    pub unsafe fn rect(_x0: i32, _y0: i32, _x1: i32, _y1: i32, _color: i32) {}
-    pub unsafe fn rect_fill(_x0: i32, _y0: i32, _x1: i32, _y1: i32, _color: i32) {}
-    pub unsafe fn circle(_x: i32, _y: i32, _r: i32, _color: i32) {}
-    pub unsafe fn circle_fill(_x: i32, _y: i32, _r: i32, _color: i32) {}
-    pub unsafe fn print(_ptr: *const u8, _len: u32, _x: i32, _y: i32, _color: i32) -> i32 {
-        0
+
+    pub unsafe fn clear(color: i32) {
+        dispatch((), |host| host.clear(color))
     }
-    pub unsafe fn is_button_down(_b: u32) -> i32 {
-        0
+
+    pub unsafe fn camera(x: i32, y: i32) {
+        dispatch((), |host| host.camera(x, y))
     }
-    pub unsafe fn is_button_pressed(_b: u32) -> i32 {
-        0
+
+    pub unsafe fn clip(x: i32, y: i32, w: i32, h: i32) {
+        dispatch((), |host| host.clip(x, y, w, h))
     }
+
+    pub unsafe fn set_pixel(x: i32, y: i32, color: i32) {
+        dispatch((), |host| host.set_pixel(x, y, color))
+    }
+
+    pub unsafe fn pixel(x: i32, y: i32) -> i32 {
+        dispatch(0, |host| host.pixel(x, y))
+    }
+
+    pub unsafe fn line(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        dispatch((), |host| host.line(x0, y0, x1, y1, color))
+    }
+
+    pub unsafe fn rect(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        dispatch((), |host| host.rect(x0, y0, x1, y1, color))
+    }
+
+    pub unsafe fn rect_fill(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        dispatch((), |host| host.rect_fill(x0, y0, x1, y1, color))
+    }
+
+    pub unsafe fn circle(x: i32, y: i32, r: i32, color: i32) {
+        dispatch((), |host| host.circle(x, y, r, color))
+    }
+
+    pub unsafe fn circle_fill(x: i32, y: i32, r: i32, color: i32) {
+        dispatch((), |host| host.circle_fill(x, y, r, color))
+    }
+
+    pub unsafe fn print(ptr: *const u8, len: u32, x: i32, y: i32, color: i32) -> i32 {
+        dispatch(0, |host| host.print(text(ptr, len), x, y, color))
+    }
+
+    pub unsafe fn is_button_down(b: u32) -> i32 {
+        dispatch(0, |host| host.is_button_down(b))
+    }
+
+    pub unsafe fn is_button_pressed(b: u32) -> i32 {
+        dispatch(0, |host| host.is_button_pressed(b))
+    }
+
     pub unsafe fn buttons_down() -> u32 {
-        0
+        dispatch(0, |host| host.buttons_down())
     }
+
     pub unsafe fn buttons_pressed() -> u32 {
-        0
+        dispatch(0, |host| host.buttons_pressed())
+    }
+
+    pub unsafe fn sprite(n: u32, x: i32, y: i32, w: i32, h: i32, flip_x: i32, flip_y: i32) {
+        dispatch((), |host| host.sprite(n, x, y, w, h, flip_x, flip_y))
     }
-    pub unsafe fn sprite(_n: u32, _x: i32, _y: i32, _w: i32, _h: i32, _flip_x: i32, _flip_y: i32) {}
+
     pub unsafe fn map(
-        _cel_x: i32,
-        _cel_y: i32,
-        _sx: i32,
-        _sy: i32,
-        _cel_w: i32,
-        _cel_h: i32,
-        _layers: u32,
+        cel_x: i32,
+        cel_y: i32,
+        sx: i32,
+        sy: i32,
+        cel_w: i32,
+        cel_h: i32,
+        layers: u32,
     ) {
+        dispatch((), |host| {
+            host.map(cel_x, cel_y, sx, sy, cel_w, cel_h, layers)
+        })
+    }
+
+    pub unsafe fn map_tile(x: i32, y: i32) -> i32 {
+        dispatch(0, |host| host.map_tile(x, y))
+    }
+
+    pub unsafe fn set_map_tile(x: i32, y: i32, v: u32) {
+        dispatch((), |host| host.set_map_tile(x, y, v))
+    }
+
+    pub unsafe fn sprite_flags(n: u32) -> i32 {
+        dispatch(0, |host| host.sprite_flags(n))
     }
-    pub unsafe fn map_tile(_x: i32, _y: i32) -> i32 {
-        0
+
+    pub unsafe fn set_sprite_flags(n: u32, flags: u32) {
+        dispatch((), |host| host.set_sprite_flags(n, flags))
+    }
+
+    pub unsafe fn sfx(n: i32, channel: i32) {
+        dispatch((), |host| host.sfx(n, channel))
     }
-    pub unsafe fn set_map_tile(_x: i32, _y: i32, _v: u32) {}
-    pub unsafe fn sprite_flags(_n: u32) -> i32 {
-        0
+
+    pub unsafe fn sfx_priority(n: i32, channel: i32, priority: i32) {
+        dispatch((), |host| host.sfx_priority(n, channel, priority))
     }
-    pub unsafe fn set_sprite_flags(_n: u32, _flags: u32) {}
-    pub unsafe fn sfx(_n: i32, _channel: i32) {}
-    // Pretend a start always succeeds (nonzero token) so cart logic type-checks
-    // and unit-tests on native targets, with no real audio.
-    pub unsafe fn music(_n: i32, _fade_duration: i32, _channel_mask: i32, _token: i32) -> i32 {
-        1
+
+    // With no host installed, pretend a start always succeeds (nonzero
+    // token) so cart logic still type-checks and unit-tests.
+
+    pub unsafe fn music(n: i32, fade_duration: i32, channel_mask: i32, token: i32) -> i32 {
+        dispatch(1, |host| host.music(n, fade_duration, channel_mask, token))
     }
+
     pub unsafe fn time() -> f32 {
-        0.0
+        dispatch(0.0, |host| host.time())
     }
+
     pub unsafe fn rnd() -> f32 {
-        0.0
-    }
-    pub unsafe fn seed_rng(_seed: u32) {}
-    pub unsafe fn sprite_pixel(_x: i32, _y: i32) -> i32 {
-        0
-    }
-    pub unsafe fn set_sprite_pixel(_x: i32, _y: i32, _color: i32) {}
-    pub unsafe fn log(_ptr: *const u8, _len: u32) {}
-    pub unsafe fn panic(_ptr: *const u8, _len: u32) {}
-    pub unsafe fn set_transparent_color(_color: i32, _transparent: i32) {}
-    pub unsafe fn reset_transparency() {}
-    pub unsafe fn remap_color(_from: i32, _to: i32, _mode: i32) {}
-    pub unsafe fn reset_palette() {}
+        dispatch(0.0, |host| host.rnd())
+    }
+
+    pub unsafe fn seed_rng(seed: u32) {
+        dispatch((), |host| host.seed_rng(seed))
+    }
+
+    pub unsafe fn sprite_pixel(x: i32, y: i32) -> i32 {
+        dispatch(0, |host| host.sprite_pixel(x, y))
+    }
+
+    pub unsafe fn set_sprite_pixel(x: i32, y: i32, color: i32) {
+        dispatch((), |host| host.set_sprite_pixel(x, y, color))
+    }
+
+    pub unsafe fn log(ptr: *const u8, len: u32) {
+        dispatch((), |host| host.log(text(ptr, len)))
+    }
+
+    pub unsafe fn log_level(level: i32, ptr: *const u8, len: u32) {
+        dispatch((), |host| host.log_level(level, text(ptr, len)))
+    }
+
+    pub unsafe fn panic(ptr: *const u8, len: u32) {
+        dispatch((), |host| host.panic(text(ptr, len)))
+    }
+
+    pub unsafe fn panic_location(ptr: *const u8, len: u32, line: u32, column: u32) {
+        dispatch((), |host| host.panic_location(text(ptr, len), line, column))
+    }
+
+    pub unsafe fn set_transparent_color(color: i32, transparent: i32) {
+        dispatch((), |host| host.set_transparent_color(color, transparent))
+    }
+
+    pub unsafe fn reset_transparency() {
+        dispatch((), |host| host.reset_transparency())
+    }
+
+    pub unsafe fn remap_color(from: i32, to: i32, mode: i32) {
+        dispatch((), |host| host.remap_color(from, to, mode))
+    }
+
+    pub unsafe fn reset_palette() {
+        dispatch((), |host| host.reset_palette())
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub unsafe fn sprite_stretch(
-        _sx: i32,
-        _sy: i32,
-        _sw: i32,
-        _sh: i32,
-        _dx: i32,
-        _dy: i32,
-        _dw: i32,
-        _dh: i32,
-        _flip_x: i32,
-        _flip_y: i32,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        dx: i32,
+        dy: i32,
+        dw: i32,
+        dh: i32,
+        flip_x: i32,
+        flip_y: i32,
     ) {
+        dispatch((), |host| {
+            host.sprite_stretch(sx, sy, sw, sh, dx, dy, dw, dh, flip_x, flip_y)
+        })
+    }
+
+    pub unsafe fn ellipse(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        dispatch((), |host| host.ellipse(x0, y0, x1, y1, color))
+    }
+
+    pub unsafe fn ellipse_fill(x0: i32, y0: i32, x1: i32, y1: i32, color: i32) {
+        dispatch((), |host| host.ellipse_fill(x0, y0, x1, y1, color))
+    }
+
+    pub unsafe fn set_fill_pattern(pattern: i32, secondary: i32, transparent: i32) {
+        dispatch((), |host| {
+            host.set_fill_pattern(pattern, secondary, transparent)
+        })
     }
-    pub unsafe fn ellipse(_x0: i32, _y0: i32, _x1: i32, _y1: i32, _color: i32) {}
-    pub unsafe fn ellipse_fill(_x0: i32, _y0: i32, _x1: i32, _y1: i32, _color: i32) {}
-    pub unsafe fn set_fill_pattern(_pattern: i32, _secondary: i32, _transparent: i32) {}
-    pub unsafe fn set_pen_color(_color: i32) {}
-    pub unsafe fn set_cursor(_x: i32, _y: i32) {}
-    pub unsafe fn print_pen(_ptr: *const u8, _len: u32) -> i32 {
-        0
+
+    pub unsafe fn set_pen_color(color: i32) {
+        dispatch((), |host| host.set_pen_color(color))
+    }
+
+    pub unsafe fn set_cursor(x: i32, y: i32) {
+        dispatch((), |host| host.set_cursor(x, y))
     }
+
+    pub unsafe fn print_pen(ptr: *const u8, len: u32) -> i32 {
+        dispatch(0, |host| host.print_pen(text(ptr, len)))
+    }
+
     pub unsafe fn cpu_update() -> f32 {
-        0.0
+        dispatch(0.0, |host| host.cpu_update())
     }
+
     pub unsafe fn cpu_draw() -> f32 {
-        0.0
+        dispatch(0.0, |host| host.cpu_draw())
     }
+
     pub unsafe fn fps() -> f32 {
-        0.0
+        dispatch(0.0, |host| host.fps())
+    }
+
+    pub unsafe fn state_write(ptr: *const u8, len: u32) {
+        let data = core::slice::from_raw_parts(ptr, len as usize);
+        dispatch((), |host| host.state_write(data))
+    }
+
+    pub unsafe fn state_read(ptr: *mut u8, len: u32) -> u32 {
+        let buf = core::slice::from_raw_parts_mut(ptr, len as usize);
+        dispatch(0, |host| host.state_read(buf))
     }
 }
 
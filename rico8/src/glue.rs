@@ -1,6 +1,21 @@
 //! Lifecycle glue between the `game!` macro exports and the game trait.
 
-use crate::{Context, Game, Graphics};
+use crate::{Context, Game, Graphics, StateReader, StateWriter};
+
+/// Write `game`'s hot-reload state, tagged with its state version; nothing
+/// at all when the game saves nothing.
+pub(crate) fn save_state<G: Game>(game: &G) {
+    let mut state = StateWriter {
+        version: Some(G::STATE_VERSION),
+    };
+    game.save_state(&mut state);
+}
+
+/// Load state saved by [`save_state`], refusing another state version.
+pub(crate) fn load_state<G: Game>(game: &mut G) -> bool {
+    let mut state = StateReader { _private: () };
+    state.u32() == Some(G::STATE_VERSION) && game.load_state(&mut state).is_some()
+}
 
 /// Implementation details of the [`game!`](crate::game) macro. Not part
 /// of the public API; do not call directly.
@@ -41,6 +56,9 @@ pub mod __internal {
             // crate-level panic handler below does the same job.
             #[cfg(feature = "std")]
             std::panic::set_hook(std::boxed::Box::new(|info| {
+                if let Some(location) = info.location() {
+                    super::report_location(location);
+                }
                 let msg = info.to_string();
                 unsafe { crate::ffi::panic(msg.as_ptr(), msg.len() as u32) };
             }));
@@ -68,6 +86,21 @@ pub mod __internal {
             }
         }
 
+        /// Hand the game's state to the host ahead of a hot reload.
+        pub fn save_state(&self) {
+            if let Some(game) = self.get() {
+                super::save_state(game);
+            }
+        }
+
+        /// Take the state saved by the previous build. `false` asks the
+        /// host to cold-boot instead.
+        pub fn load_state(&self) -> bool {
+            self.get()
+                .as_mut()
+                .is_some_and(|game| super::load_state(game))
+        }
+
         #[allow(clippy::mut_from_ref)]
         fn get(&self) -> &mut Option<G> {
             unsafe { &mut *self.0.get() }
@@ -86,16 +119,32 @@ pub mod __internal {
 
 /// Capture the panic message and hand it to the host, then trap. Mirrors the
 /// `std` `set_hook` path so both kinds of cart show the same error screen.
-#[cfg(not(feature = "std"))]
+/// Wasm-only: native builds (cart unit tests) use std's handler.
+#[cfg(all(not(feature = "std"), target_arch = "wasm32"))]
 #[panic_handler]
 fn handle_panic(info: &core::panic::PanicInfo) -> ! {
     use core::fmt::Write;
     let mut buf = crate::fmt::FmtBuf::<256>::new();
     let _ = write!(buf, "{info}");
     let msg = buf.as_str();
+    if let Some(location) = info.location() {
+        report_location(location);
+    }
     unsafe { crate::ffi::panic(msg.as_ptr(), msg.len() as u32) };
-    #[cfg(target_arch = "wasm32")]
     core::arch::wasm32::unreachable();
-    #[cfg(not(target_arch = "wasm32"))]
-    loop {}
+}
+
+/// Hand the host where a panic happened, apart from the message, so the
+/// console can jump to it without parsing the message.
+#[cfg(any(feature = "std", target_arch = "wasm32"))]
+fn report_location(location: &core::panic::Location) {
+    let file = location.file();
+    unsafe {
+        crate::ffi::panic_location(
+            file.as_ptr(),
+            file.len() as u32,
+            location.line(),
+            location.column(),
+        )
+    };
 }
@@ -0,0 +1,158 @@
+//! A native stand-in for the console, for unit-testing carts with `cargo test`.
+//!
+//! On wasm32 the [`ffi`](crate::ffi) imports go to the real console. On
+//! native targets they forward to whatever [`Host`] is installed on the
+//! current thread with [`with_host`], and do nothing when none is. The
+//! `rico8-test` crate provides a complete software host built on the
+//! console's own framebuffer, input and asset code; implement this trait
+//! directly only to fake a narrower slice of the console.
+//!
+//! Hosts are per thread, so `cargo test`'s parallel tests each see their own.
+
+use crate::{Context, Game, Graphics};
+use std::cell::Cell;
+
+/// The `"rico8"` import set as a Rust trait: one method per [`ffi`](crate::ffi)
+/// function, with strings passed as `&str` instead of pointer and length.
+#[allow(clippy::too_many_arguments)]
+pub trait Host {
+    fn clear(&mut self, color: i32);
+    fn camera(&mut self, x: i32, y: i32);
+    fn clip(&mut self, x: i32, y: i32, w: i32, h: i32);
+    fn set_pixel(&mut self, x: i32, y: i32, color: i32);
+    fn pixel(&mut self, x: i32, y: i32) -> i32;
+    fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32);
+    fn rect(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32);
+    fn rect_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32);
+    fn circle(&mut self, x: i32, y: i32, r: i32, color: i32);
+    fn circle_fill(&mut self, x: i32, y: i32, r: i32, color: i32);
+    fn print(&mut self, text: &str, x: i32, y: i32, color: i32) -> i32;
+    fn is_button_down(&mut self, b: u32) -> i32;
+    fn is_button_pressed(&mut self, b: u32) -> i32;
+    fn buttons_down(&mut self) -> u32;
+    fn buttons_pressed(&mut self) -> u32;
+    fn sprite(&mut self, n: u32, x: i32, y: i32, w: i32, h: i32, flip_x: i32, flip_y: i32);
+    fn map(
+        &mut self,
+        cel_x: i32,
+        cel_y: i32,
+        sx: i32,
+        sy: i32,
+        cel_w: i32,
+        cel_h: i32,
+        layers: u32,
+    );
+    fn map_tile(&mut self, x: i32, y: i32) -> i32;
+    fn set_map_tile(&mut self, x: i32, y: i32, v: u32);
+    fn sprite_flags(&mut self, n: u32) -> i32;
+    fn set_sprite_flags(&mut self, n: u32, flags: u32);
+    fn sfx(&mut self, n: i32, channel: i32);
+    fn sfx_priority(&mut self, n: i32, channel: i32, priority: i32);
+    fn music(&mut self, n: i32, fade_duration: i32, channel_mask: i32, token: i32) -> i32;
+    fn time(&mut self) -> f32;
+    fn rnd(&mut self) -> f32;
+    fn seed_rng(&mut self, seed: u32);
+    fn sprite_pixel(&mut self, x: i32, y: i32) -> i32;
+    fn set_sprite_pixel(&mut self, x: i32, y: i32, color: i32);
+    fn log(&mut self, text: &str);
+    fn log_level(&mut self, level: i32, text: &str);
+    fn panic(&mut self, text: &str);
+    fn panic_location(&mut self, file: &str, line: u32, column: u32);
+    fn set_transparent_color(&mut self, color: i32, transparent: i32);
+    fn reset_transparency(&mut self);
+    fn remap_color(&mut self, from: i32, to: i32, mode: i32);
+    fn reset_palette(&mut self);
+    fn sprite_stretch(
+        &mut self,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        dx: i32,
+        dy: i32,
+        dw: i32,
+        dh: i32,
+        flip_x: i32,
+        flip_y: i32,
+    );
+    fn ellipse(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32);
+    fn ellipse_fill(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: i32);
+    fn set_fill_pattern(&mut self, pattern: i32, secondary: i32, transparent: i32);
+    fn set_pen_color(&mut self, color: i32);
+    fn set_cursor(&mut self, x: i32, y: i32);
+    fn print_pen(&mut self, text: &str) -> i32;
+    fn cpu_update(&mut self) -> f32;
+    fn cpu_draw(&mut self) -> f32;
+    fn fps(&mut self) -> f32;
+    fn state_write(&mut self, data: &[u8]);
+    fn state_read(&mut self, buf: &mut [u8]) -> u32;
+}
+
+std::thread_local! {
+    static CURRENT: Cell<Option<*mut (dyn Host + 'static)>> = const { Cell::new(None) };
+}
+
+/// Run `f` with `host` answering every console call made on this thread.
+/// Nests: the previous host (if any) is restored when `f` returns or panics.
+pub fn with_host<R>(host: &mut dyn Host, f: impl FnOnce() -> R) -> R {
+    // SAFETY: only the borrow's lifetime is erased. `Restore` takes the
+    // pointer back out before `host` can go out of scope, even on unwind.
+    let ptr: *mut (dyn Host + 'static) =
+        unsafe { core::mem::transmute::<*mut dyn Host, *mut (dyn Host + 'static)>(host) };
+    let _restore = Restore(CURRENT.with(|c| c.replace(Some(ptr))));
+    f()
+}
+
+/// Puts the installed host pointer back when dropped, even on unwind.
+struct Restore(Option<*mut (dyn Host + 'static)>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|c| c.set(self.0));
+    }
+}
+
+/// Call into the installed host, or return `fallback` when there is none.
+///
+/// The host is taken out of the thread for the length of the call, so a
+/// host method that calls back into the SDK sees no host (and gets the
+/// fallback) rather than a second `&mut` to itself.
+pub(crate) fn dispatch<R>(fallback: R, f: impl FnOnce(&mut dyn Host) -> R) -> R {
+    match CURRENT.with(Cell::take) {
+        Some(ptr) => {
+            let _restore = Restore(Some(ptr));
+            // SAFETY: set by `with_host`, which holds the exclusive borrow
+            // for as long as the pointer is installed, and taken out above,
+            // so this is the only live reference until `_restore` drops.
+            f(unsafe { &mut *ptr })
+        }
+        None => fallback,
+    }
+}
+
+/// Whether a host is installed on this thread.
+#[cfg(feature = "std")]
+pub(crate) fn installed() -> bool {
+    CURRENT.with(Cell::get).is_some()
+}
+
+/// Run one `update` of `game`, as the console's `rico8_update` export would.
+pub fn update<G: Game>(game: &mut G) {
+    game.update(&mut Context { _private: () });
+}
+
+/// Run one `draw` of `game`, as the console's `rico8_draw` export would.
+pub fn draw<G: Game>(game: &G) {
+    game.draw(&mut Graphics { _private: () });
+}
+
+/// Save `game`'s hot-reload state, as the `rico8_save_state` export would.
+pub fn save_state<G: Game>(game: &G) {
+    crate::glue::save_state(game);
+}
+
+/// Load hot-reload state into `game`, as the `rico8_load_state` export
+/// would. `false` means a hot reload would cold-boot instead.
+pub fn load_state<G: Game>(game: &mut G) -> bool {
+    crate::glue::load_state(game)
+}
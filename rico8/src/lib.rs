@@ -33,18 +33,26 @@
 //! if the game sets [`Game::FRAME_RATE`]). The constraints
 //! are the point.
 //!
-//! For formatted on-screen text and debug logs, see the [`printf!`](crate::printf)
-//! and [`logf!`](crate::logf) macros.
-#![cfg_attr(not(feature = "std"), no_std)]
+//! For formatted on-screen text and debug logs, see the [`printf!`](crate::printf),
+//! [`logf!`](crate::logf) and [`log!`](crate::log) macros. `std` carts can send
+//! `println!`/`eprintln!` to the console log too; see [`stdio`].
+// `no_std` is a promise about the wasm cart. Native builds (docs, `cargo
+// test` against a software host) always have std underneath.
+#![cfg_attr(all(not(feature = "std"), target_arch = "wasm32"), no_std)]
 
 mod dim;
 pub mod ffi;
 mod flags;
 mod fmt;
 mod glue;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod host;
 pub mod memstat;
 mod motion;
 mod music;
+mod state;
+#[cfg(feature = "std")]
+pub mod stdio;
 
 // Install the live-tracking allocator for `std` carts. It lives here, not in
 // the `game!` macro, so the `feature = "std"` cfg is evaluated in this crate
@@ -63,6 +71,7 @@ pub use dim::{Dim, ZeroSize};
 pub use glue::__internal;
 pub use motion::Body;
 pub use music::{Music, MusicBusy, PlayingMusic};
+pub use state::{StateReader, StateWriter};
 
 /// The screen is 128x128 pixels.
 pub const SCREEN_WIDTH: u16 = 128;
@@ -184,6 +193,37 @@ const fn button_index(b: Button) -> u32 {
     (b as u8).trailing_zeros()
 }
 
+/// Whose pad to read. Player two is the other side of a netplay session;
+/// outside netplay only player one's pad is connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Player {
+    One,
+    Two,
+}
+
+/// How serious a log line is. The console shows each level in its own color
+/// and can filter its log view down to the lines at or above one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Chatter worth seeing only while chasing a bug.
+    Debug,
+    /// Ordinary progress lines (the level of [`Context::log`]).
+    #[default]
+    Info,
+    /// Something off that the game can carry on from.
+    Warn,
+    /// Something broken.
+    Error,
+}
+
+/// The ABI button index for `player`'s button: player two's sit at `8..=13`.
+const fn player_button_index(player: Player, b: Button) -> u32 {
+    match player {
+        Player::One => button_index(b),
+        Player::Two => 8 + button_index(b),
+    }
+}
+
 bitflag_enum! {
     /// One of a sprite's eight flags. The flags carry no fixed meaning — a cart
     /// assigns its own (e.g. "solid"). Used by [`Context::sprite_flags`] /
@@ -289,6 +329,18 @@ impl Context {
         self.is_button_pressed(b)
     }
 
+    /// Is a button held on `player`'s pad? [`is_button_down`](Context::is_button_down)
+    /// reads player one.
+    pub fn is_player_button_down(&self, player: Player, b: Button) -> bool {
+        unsafe { ffi::is_button_down(player_button_index(player, b)) != 0 }
+    }
+
+    /// Was a button just pressed on `player`'s pad? Same repeat as
+    /// [`is_button_pressed`](Context::is_button_pressed).
+    pub fn is_player_button_pressed(&self, player: Player, b: Button) -> bool {
+        unsafe { ffi::is_button_pressed(player_button_index(player, b)) != 0 }
+    }
+
     /// Every button currently held down, as a set.
     pub fn buttons_down(&self) -> BitFlags<Button> {
         BitFlags::from_bits(unsafe { ffi::buttons_down() } as u8)
@@ -390,16 +442,35 @@ impl Context {
         self.set_sprite_flags(sprite, flags)
     }
 
-    /// Play a sound effect on a free channel.
+    /// Play a sound effect on a free channel, at priority 0.
+    ///
+    /// See [`Context::sfx_priority`] for what happens when all four channels
+    /// are busy.
     pub fn sfx(&mut self, s: SfxId) {
         unsafe { ffi::sfx(s.0 as i32, -1) }
     }
 
-    /// Play a sound effect on a specific channel.
+    /// Play a sound effect on a specific channel, at priority 0.
     pub fn sfx_on(&mut self, s: SfxId, channel: Channel) {
         unsafe { ffi::sfx(s.0 as i32, channel_index(channel) as i32) }
     }
 
+    /// Play a sound effect on a free channel at `priority` (higher matters
+    /// more). When no channel outside the music's reserved ones is idle, it
+    /// replaces the lowest-priority sound — the oldest among equals — as
+    /// long as that is no more important than this one, then a non-reserved
+    /// music channel; failing both, this sound is dropped. Reserved channels
+    /// are never taken.
+    pub fn sfx_priority(&mut self, s: SfxId, priority: u8) {
+        unsafe { ffi::sfx_priority(s.0 as i32, -1, priority as i32) }
+    }
+
+    /// Play a sound effect on a specific channel, tagged with `priority` so
+    /// later [`Context::sfx_priority`] calls know how hard to hold on to it.
+    pub fn sfx_on_priority(&mut self, s: SfxId, channel: Channel, priority: u8) {
+        unsafe { ffi::sfx_priority(s.0 as i32, channel_index(channel) as i32, priority as i32) }
+    }
+
     /// Stop whatever is playing on a channel.
     pub fn sfx_stop(&mut self, channel: Channel) {
         unsafe { ffi::sfx(-1, channel_index(channel) as i32) }
@@ -407,7 +478,8 @@ impl Context {
 
     /// Begin a music-playback request for pattern `m`.
     ///
-    /// Nothing plays until [`Music::play`]; set a fade-in or reserved channels
+    /// Nothing plays until [`Music::play`]; set a fade-in, reserved channels
+    /// or a [`crossfade`](Music::crossfade)/[`queue`](Music::queue) takeover
     /// on the returned [`Music`] first.
     pub fn music(&mut self, m: MusicId) -> Music {
         Music::new(m)
@@ -467,12 +539,19 @@ impl Context {
         self.seed_rng(seed)
     }
 
-    /// Log a line to the RICO-8 console (visible after Esc). For
-    /// `format!`-style arguments, see [`logf!`](crate::logf).
+    /// Log a line to the RICO-8 console at [`LogLevel::Info`] (the `log`
+    /// command shows it after Esc). For `format!`-style arguments, see
+    /// [`logf!`](crate::logf).
     pub fn log(&mut self, msg: &str) {
         unsafe { ffi::log(msg.as_ptr(), msg.len() as u32) }
     }
 
+    /// Log a line at `level`. The console stamps it with the frame number.
+    /// For `format!`-style arguments, see [`log!`](crate::log).
+    pub fn log_at(&mut self, level: LogLevel, msg: &str) {
+        unsafe { ffi::log_level(level as i32, msg.as_ptr(), msg.len() as u32) }
+    }
+
     /// Fraction (`0.0`–`1.0`) of last frame's `update` CPU budget used.
     ///
     /// Reports the previous completed frame: mid-`update` the current call's
@@ -967,6 +1046,20 @@ pub trait Game {
     fn update(&mut self, ctx: &mut Context);
     /// Called after `update`. Draw the world.
     fn draw(&self, gfx: &mut Graphics);
+
+    /// Bump this when the layout [`save_state`](Game::save_state) writes
+    /// changes; a hot reload across versions cold-boots instead of loading.
+    const STATE_VERSION: u32 = 0;
+    /// Called on the running game just before a hot reload swaps in the
+    /// rebuilt cart. Write whatever [`load_state`](Game::load_state) needs
+    /// to carry on where it was.
+    fn save_state(&self, _state: &mut StateWriter) {}
+    /// Called on the rebuilt cart's freshly initialized game with what
+    /// `save_state` wrote. `None` (the default) cold-boots the new cart,
+    /// as does leaving saved bytes unread.
+    fn load_state(&mut self, _state: &mut StateReader) -> Option<()> {
+        None
+    }
 }
 
 /// Declare your game's entry point.
@@ -1013,6 +1106,16 @@ macro_rules! game {
         pub extern "C" fn rico8_draw() {
             GAME.draw();
         }
+
+        #[no_mangle]
+        pub extern "C" fn rico8_save_state() {
+            GAME.save_state();
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rico8_load_state() -> u32 {
+            GAME.load_state() as u32
+        }
     };
     ($game:ident { $($field:tt)* }) => {
         $crate::game!($game = $game { $($field)* });
@@ -1080,6 +1183,32 @@ macro_rules! logf {
     }};
 }
 
+/// Log formatted text at a [`LogLevel`] — like [`Context::log_at`], but with
+/// `format!`-style arguments. Same fixed buffer as [`logf!`].
+///
+/// ```ignore
+/// use rico8::*;
+///
+/// fn update(&mut self, ctx: &mut Context) {
+///     if self.hp < 10 {
+///         rico8::log!(ctx, LogLevel::Warn, "low hp: {}", self.hp);
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($cap:literal; $ctx:expr, $level:expr, $($arg:tt)*) => {{
+        let __buf = $crate::__internal::format_args_to_buf::<$cap>(::core::format_args!($($arg)*));
+        $ctx.log_at($level, __buf.as_str());
+    }};
+    ($ctx:expr, $level:expr, $($arg:tt)*) => {{
+        let __buf = $crate::__internal::format_args_to_buf::<{ $crate::__internal::LINE_CAP }>(
+            ::core::format_args!($($arg)*),
+        );
+        $ctx.log_at($level, __buf.as_str());
+    }};
+}
+
 /// Whether `(x, y)` falls inside a `w x h` surface anchored at the origin.
 fn in_bounds(x: i16, y: i16, w: u16, h: u16) -> bool {
     x >= 0 && y >= 0 && (x as u16) < w && (y as u16) < h
@@ -1510,6 +1639,8 @@ mod tests {
         let mut ctx = Context { _private: () };
         ctx.sfx(SfxId::new(0).unwrap());
         ctx.sfx_on(SfxId::new(1).unwrap(), Channel::Channel2);
+        ctx.sfx_priority(SfxId::new(2).unwrap(), 200);
+        ctx.sfx_on_priority(SfxId::new(3).unwrap(), Channel::Channel1, 9);
         ctx.sfx_stop(Channel::Channel2);
     }
 
@@ -104,11 +104,10 @@ pub fn used_fraction() -> f32 {
     used_bytes() as f32 / CAP as f32
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
-    #[cfg(feature = "std")]
     #[test]
     fn tracking_alloc_counts_live_heap() {
         use core::alloc::GlobalAlloc;
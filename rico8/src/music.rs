@@ -5,13 +5,24 @@ use crate::{ffi, BitFlags, Channel, MusicId};
 /// A configured-but-not-yet-playing music request.
 ///
 /// Build it with [`Context::music`](crate::Context::music), optionally set a
-/// fade-in and reserved channels, then [`play`](Self::play).
+/// fade-in, reserved channels and how to take over from a playing song, then
+/// [`play`](Self::play).
 #[must_use = "music does nothing until you call .play()"]
 #[derive(Debug, Clone, Copy)]
 pub struct Music {
     pattern: MusicId,
     fade_in_duration: u32,
     channels: BitFlags<Channel>,
+    start: Start,
+}
+
+/// What a start does when a song is already playing. The discriminants are
+/// the ABI's start-mode values (`music`'s last argument on a start).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Start {
+    Exclusive = 0,
+    Crossfade = 1,
+    Queue = 2,
 }
 
 impl Music {
@@ -20,6 +31,7 @@ impl Music {
             pattern,
             fade_in_duration: 0,
             channels: BitFlags::empty(),
+            start: Start::Exclusive,
         }
     }
 
@@ -39,18 +51,39 @@ impl Music {
         self
     }
 
+    /// Crossfade from the playing song: it fades out over `duration`
+    /// milliseconds while this one fades in over the same time. With nothing
+    /// playing this is just a [`fade_in`](Self::fade_in).
+    pub fn crossfade(mut self, duration: u32) -> Self {
+        self.fade_in_duration = duration;
+        self.start = Start::Crossfade;
+        self
+    }
+
+    /// Queue behind the playing song: this one starts when the current
+    /// pattern ends, instead of being refused. With nothing playing it starts
+    /// right away. Stopping its handle before then cancels it and leaves the
+    /// playing song alone.
+    pub fn queue(mut self) -> Self {
+        self.start = Start::Queue;
+        self
+    }
+
     /// Start playing.
     ///
     /// On success returns a [`PlayingMusic`] handle that controls — and, when
     /// dropped, stops — this song. Fails with [`MusicBusy`] if a song is already
-    /// playing; stop it first, then retry.
+    /// playing; stop it first, then retry — or ask for a
+    /// [`crossfade`](Self::crossfade) or [`queue`](Self::queue), which take
+    /// over from the playing song and never fail. Taking over also hands the
+    /// old song's handle off: dropping it no longer stops anything.
     pub fn play(self) -> Result<PlayingMusic, MusicBusy> {
         let token = unsafe {
             ffi::music(
                 self.pattern.0 as i32,
                 self.fade_in_duration as i32,
                 self.channels.bits() as i32,
-                0,
+                self.start as i32,
             )
         };
         if token != 0 {
@@ -142,6 +175,19 @@ mod tests {
         handle.unwrap().fade_out(500).stop();
     }
 
+    #[test]
+    fn transitions_set_the_start_mode() {
+        let id = MusicId::new(3).unwrap();
+        let m = Music::new(id).crossfade(750);
+        assert_eq!((m.start, m.fade_in_duration), (Start::Crossfade, 750));
+        assert_eq!(Music::new(id).queue().start, Start::Queue);
+        assert_eq!(
+            Music::new(id).start as i32,
+            0,
+            "plain starts stay exclusive"
+        );
+    }
+
     #[test]
     fn music_busy_is_an_error() {
         // It satisfies the standard `Error` bound (`core::error::Error`, so this
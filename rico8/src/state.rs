@@ -0,0 +1,134 @@
+//! Game state that survives a hot reload.
+//!
+//! When a rebuilt cart is swapped in, the console asks the old instance to
+//! [`save_state`](crate::Game::save_state) and hands the bytes to the new
+//! one's [`load_state`](crate::Game::load_state). The bytes live on the host
+//! side, so a cart pays no memory for them. Values are little-endian and
+//! must be read back in the order they were written.
+//!
+//! ```no_run
+//! use rico8::*;
+//!
+//! struct Walker { x: i16, y: i16, coins: u32 }
+//!
+//! impl Game for Walker {
+//!     fn update(&mut self, ctx: &mut Context) {}
+//!     fn draw(&self, gfx: &mut Graphics) {}
+//!
+//!     fn save_state(&self, state: &mut StateWriter) {
+//!         state.i16(self.x);
+//!         state.i16(self.y);
+//!         state.u32(self.coins);
+//!     }
+//!
+//!     fn load_state(&mut self, state: &mut StateReader) -> Option<()> {
+//!         self.x = state.i16()?;
+//!         self.y = state.i16()?;
+//!         self.coins = state.u32()?;
+//!         Some(())
+//!     }
+//! }
+//! ```
+
+use crate::ffi;
+
+/// Writes game state to the host during a hot reload. See [`Game::save_state`](crate::Game::save_state).
+pub struct StateWriter {
+    /// The state version, written ahead of the first bytes the game writes.
+    /// A game that writes nothing leaves the save empty, which the host
+    /// takes as having no state to carry over.
+    pub(crate) version: Option<u32>,
+}
+
+impl StateWriter {
+    pub fn bytes(&mut self, data: &[u8]) {
+        if let Some(version) = self.version.take() {
+            let tag = version.to_le_bytes();
+            unsafe { ffi::state_write(tag.as_ptr(), tag.len() as u32) }
+        }
+        unsafe { ffi::state_write(data.as_ptr(), data.len() as u32) }
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    pub fn i8(&mut self, v: i8) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn i16(&mut self, v: i16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, v: i32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+}
+
+/// Reads back what [`StateWriter`] wrote. Every read returns `None` once the
+/// saved bytes run out, so a `load_state` written with `?` gives up cleanly.
+pub struct StateReader {
+    pub(crate) _private: (),
+}
+
+impl StateReader {
+    /// Fill `buf` completely, or `None` if fewer bytes are left.
+    pub fn bytes(&mut self, buf: &mut [u8]) -> Option<()> {
+        let n = unsafe { ffi::state_read(buf.as_mut_ptr(), buf.len() as u32) };
+        (n as usize == buf.len()).then_some(())
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let mut buf = [0; N];
+        self.bytes(&mut buf).map(|()| buf)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[b]| b)
+    }
+
+    pub fn i8(&mut self) -> Option<i8> {
+        self.array().map(i8::from_le_bytes)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        self.u8().map(|b| b != 0)
+    }
+}
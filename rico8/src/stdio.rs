@@ -0,0 +1,64 @@
+//! `println!` and `eprintln!` for `std` carts, sent to the console log.
+//!
+//! A cart built for `wasm32-unknown-unknown` has no stdout: std's own
+//! `println!` compiles, but what it prints goes nowhere. Import these in
+//! its place and the lines land in the console's log instead, `println!`
+//! at [`LogLevel::Info`] and `eprintln!` at [`LogLevel::Error`]:
+//!
+//! ```ignore
+//! use rico8::*;
+//! use rico8::stdio::{eprintln, println};
+//! ```
+//!
+//! They must be imported by name. `use rico8::*` can't carry them: a glob
+//! import of `println` would make every `println!` in the cart ambiguous
+//! with the prelude's. Natively, with no [`Host`](crate::host::Host)
+//! installed, they print to the real stdout and stderr.
+//!
+//! These macros are the only supported route to the log. Nothing captures
+//! the process's stdout or stderr: std's own `println!`/`eprintln!`, and
+//! any other write to them, go nowhere in the console and straight to the
+//! terminal in a native test, even with a [`Host`](crate::host::Host)
+//! installed — never into its log.
+
+use crate::LogLevel;
+
+pub use crate::{__rico8_eprintln as eprintln, __rico8_println as println};
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rico8_println {
+    () => {
+        $crate::stdio::__line($crate::LogLevel::Info, ::core::format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::stdio::__line($crate::LogLevel::Info, ::core::format_args!($($arg)*))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __rico8_eprintln {
+    () => {
+        $crate::stdio::__line($crate::LogLevel::Error, ::core::format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::stdio::__line($crate::LogLevel::Error, ::core::format_args!($($arg)*))
+    };
+}
+
+/// One log line from [`println!`] or [`eprintln!`]. Unlike [`logf!`](crate::logf)'s
+/// fixed buffer, the line is as long as it needs to be.
+#[doc(hidden)]
+pub fn __line(level: LogLevel, args: core::fmt::Arguments) {
+    let text = std::fmt::format(args);
+    #[cfg(not(target_arch = "wasm32"))]
+    if !crate::host::installed() {
+        match level {
+            LogLevel::Error => std::eprintln!("{text}"),
+            _ => std::println!("{text}"),
+        }
+        return;
+    }
+    unsafe { crate::ffi::log_level(level as i32, text.as_ptr(), text.len() as u32) }
+}
@@ -1,7 +1,7 @@
-//! Guards that `use rico8::*;` keeps `printf!`/`logf!` unambiguous with every
-//! prelude macro — the reason `print!` itself was unusable. Stand-in sinks
-//! avoid the crate-private `Graphics`/`Context` while still exercising macro
-//! resolution and expansion.
+//! Guards that `use rico8::*;` keeps `printf!`/`logf!`/`log!` unambiguous
+//! with every prelude macro — the reason `print!` itself was unusable. Stand-in
+//! sinks avoid the crate-private `Graphics`/`Context` while still exercising
+//! macro resolution and expansion.
 
 use rico8::*;
 
@@ -17,6 +17,8 @@ struct FakeCtx;
 
 impl FakeCtx {
     fn log(&mut self, _s: &str) {}
+
+    fn log_at(&mut self, _level: LogLevel, _s: &str) {}
 }
 
 #[test]
@@ -27,8 +29,10 @@ fn macros_resolve_through_glob_and_path() {
     // Unqualified, through the glob import.
     let _: f32 = printf!(gfx, 0.0, 0.0, Color::WHITE, "x {}", 1);
     logf!(ctx, "y {}", 2);
+    log!(ctx, LogLevel::Warn, "z {}", 3);
 
     // Path-qualified.
     let _: f32 = rico8::printf!(gfx, 0.0, 0.0, Color::WHITE, "x {}", 1);
     rico8::logf!(ctx, "y {}", 2);
+    rico8::log!(ctx, LogLevel::Warn, "z {}", 3);
 }
@@ -0,0 +1,15 @@
+//! Guards that `rico8::stdio`'s `println!`/`eprintln!` can be imported next to
+//! `use rico8::*;` and shadow the prelude's without ambiguity. With no host
+//! installed they print to the real stdout and stderr.
+#![cfg(feature = "std")]
+
+use rico8::stdio::{eprintln, println};
+use rico8::*;
+
+#[test]
+fn stdio_macros_shadow_the_prelude() {
+    let level = LogLevel::Info;
+    println!("p {level:?}");
+    println!();
+    eprintln!("e {}", 5);
+}